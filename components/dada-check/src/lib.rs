@@ -14,10 +14,12 @@ use dada_ir_sym::{
         functions::{SignatureSymbols, SymFunction, SymFunctionSignature, SymInputOutput},
         generics::{SymWhereClause, SymWhereClauseKind},
         module::{SymItem, SymModule},
-        types::{SymGenericKind, SymGenericTerm, SymPerm, SymPlace, SymTy},
-        variables::SymVariable,
+        traits::SymTrait,
+        types::{SymGenericKind, SymGenericTerm, SymPerm, SymPlace, SymTy, SymTyName},
+        variables::{FromVar, SymVariable},
     },
     prelude::*,
+    well_known::is_host_function,
 };
 
 pub use dada_ir_sym::Db;
@@ -57,6 +59,7 @@ impl<'db> Check<'db> for SymItem<'db> {
     fn check(&self, db: &'db dyn crate::Db) {
         match self {
             SymItem::SymClass(sym_class) => sym_class.check(db),
+            SymItem::SymTrait(sym_trait) => sym_trait.check(db),
             SymItem::SymFunction(sym_function) => sym_function.check(db),
             SymItem::SymPrimitive(_sym_primtive) => (),
         }
@@ -66,6 +69,229 @@ impl<'db> Check<'db> for SymItem<'db> {
 impl<'db> Check<'db> for SymAggregate<'db> {
     fn check(&self, db: &'db dyn crate::Db) {
         self.members(db).iter().for_each(|member| member.check(db));
+        self.traits(db)
+            .iter()
+            .for_each(|&sym_trait| check_trait_impl(db, *self, sym_trait));
+    }
+}
+
+/// Check that `aggr` provides a method for each method declared in `sym_trait`,
+/// with the signature the trait declares once `Self` is replaced by `aggr`.
+fn check_trait_impl<'db>(
+    db: &'db dyn crate::Db,
+    aggr: SymAggregate<'db>,
+    sym_trait: SymTrait<'db>,
+) {
+    for &trait_method in sym_trait.methods(db) {
+        let id = trait_method.name(db);
+        let Some(SymClassMember::SymFunction(method)) = aggr.inherent_member(db, id) else {
+            Diagnostic::error(
+                db,
+                aggr.name_span(db),
+                format!("missing method `{id}` required by trait `{sym_trait}`"),
+            )
+            .label(
+                db,
+                Level::Error,
+                aggr.name_span(db),
+                format!(
+                    "`{aggr}` is declared to implement `{sym_trait}`, but it has no method `{id}`"
+                ),
+            )
+            .label(
+                db,
+                Level::Info,
+                trait_method.name_span(db),
+                "the trait method is declared here",
+            )
+            .report(db);
+            continue;
+        };
+
+        let expected = trait_method.source(db).inputs(db).len();
+        let found = method.source(db).inputs(db).len();
+        if expected != found {
+            Diagnostic::error(
+                db,
+                method.name_span(db),
+                format!("method `{id}` has {found} parameters, but trait `{sym_trait}` expects {expected}"),
+            )
+            .label(
+                db,
+                Level::Error,
+                method.name_span(db),
+                format!("this method has {found} parameters"),
+            )
+            .label(
+                db,
+                Level::Info,
+                trait_method.name_span(db),
+                format!("the trait method is declared with {expected} parameters"),
+            )
+            .report(db);
+            continue;
+        }
+
+        check_trait_method_signature(db, aggr, sym_trait, trait_method, method);
+    }
+}
+
+/// Check that `method`, which implements `trait_method` for `aggr`,
+/// has the same input and output types as `trait_method`.
+///
+/// The generic parameters of `trait_method` are mapped onto those of `method`:
+/// `Self` becomes the type of `aggr` (with the implicit permission of `self`, if `method` has one),
+/// and the remaining generic parameters correspond one to one.
+fn check_trait_method_signature<'db>(
+    db: &'db dyn crate::Db,
+    aggr: SymAggregate<'db>,
+    sym_trait: SymTrait<'db>,
+    trait_method: SymFunction<'db>,
+    method: SymFunction<'db>,
+) {
+    let (Ok(trait_signature), Ok(signature)) = (
+        trait_method.checked_signature(db),
+        method.checked_signature(db),
+    ) else {
+        return;
+    };
+    let id = method.name(db);
+    let trait_input_output = trait_signature.input_output(db);
+    let input_output = signature.input_output(db);
+
+    let (aggr_vars, method_vars) = input_output.variables.split_at(aggr.len_generics(db));
+    let self_perm_var = method.implicit_self_perm_var(db);
+    let method_vars: Vec<SymVariable<'db>> = method_vars
+        .iter()
+        .copied()
+        .filter(|&var| Some(var) != self_perm_var)
+        .collect();
+    let trait_method_vars = &trait_input_output.variables[sym_trait.symbols(db).len()..];
+    if method_vars.len() != trait_method_vars.len()
+        || method_vars
+            .iter()
+            .zip(trait_method_vars)
+            .any(|(var, trait_var)| var.kind(db) != trait_var.kind(db))
+    {
+        Diagnostic::error(
+            db,
+            method.name_span(db),
+            format!("method `{id}` has different generic parameters than trait `{sym_trait}` declares"),
+        )
+        .label(
+            db,
+            Level::Error,
+            method.name_span(db),
+            "the generic parameters of this method, including the implicit permissions of its parameters, do not match the trait method",
+        )
+        .label(
+            db,
+            Level::Info,
+            trait_method.name_span(db),
+            "the trait method is declared here",
+        )
+        .report(db);
+        return;
+    }
+
+    let aggr_ty = SymTy::named(
+        db,
+        SymTyName::Aggregate(aggr),
+        aggr_vars
+            .iter()
+            .map(|&var| SymGenericTerm::var(db, var))
+            .collect(),
+    );
+    let self_ty = match self_perm_var {
+        Some(var) => SymTy::perm(db, SymPerm::var(db, var), aggr_ty),
+        None => aggr_ty,
+    };
+    let generics: Vec<SymGenericTerm<'db>> = std::iter::once(self_ty.into())
+        .chain(method_vars.iter().map(|&var| SymGenericTerm::var(db, var)))
+        .collect();
+    let inputs: Vec<SymGenericTerm<'db>> = input_output
+        .bound_value
+        .variables
+        .iter()
+        .map(|&var| SymGenericTerm::var(db, var))
+        .collect();
+    let expected = trait_input_output
+        .substitute(db, &generics)
+        .substitute(db, &inputs);
+    let found = &input_output.bound_value.bound_value;
+
+    let ast_inputs = method.source(db).inputs(db);
+    for ((ast_input, &expected_ty), &found_ty) in ast_inputs
+        .iter()
+        .zip(&expected.input_tys)
+        .zip(&found.input_tys)
+    {
+        if expected_ty != found_ty {
+            Diagnostic::error(
+                db,
+                ast_input.span(db),
+                format!(
+                    "parameter `{}` of method `{id}` has type `{found_ty}`, but trait `{sym_trait}` expects `{expected_ty}`",
+                    ast_input.symbol(db)
+                ),
+            )
+            .label(
+                db,
+                Level::Error,
+                ast_input.span(db),
+                format!("this parameter has type `{found_ty}`"),
+            )
+            .label(
+                db,
+                Level::Info,
+                trait_method.name_span(db),
+                "the trait method is declared here",
+            )
+            .report(db);
+        }
+    }
+
+    if expected.output_ty != found.output_ty {
+        let (expected_ty, found_ty) = (expected.output_ty, found.output_ty);
+        Diagnostic::error(
+            db,
+            method.name_span(db),
+            format!(
+                "method `{id}` returns `{found_ty}`, but trait `{sym_trait}` expects `{expected_ty}`"
+            ),
+        )
+        .label(
+            db,
+            Level::Error,
+            method.name_span(db),
+            format!("this method returns `{found_ty}`"),
+        )
+        .label(
+            db,
+            Level::Info,
+            trait_method.name_span(db),
+            "the trait method is declared here",
+        )
+        .report(db);
+    }
+}
+
+impl<'db> Check<'db> for SymTrait<'db> {
+    fn check(&self, db: &'db dyn crate::Db) {
+        for &method in self.methods(db) {
+            if method.has_body(db) {
+                Diagnostic::error(db, method.name_span(db), "trait methods cannot have a body")
+                    .label(
+                        db,
+                        Level::Error,
+                        method.name_span(db),
+                        "trait methods only declare a signature; the body belongs in each class that implements the trait",
+                    )
+                    .report(db);
+            }
+
+            let _ = method.checked_signature(db);
+        }
     }
 }
 
@@ -74,6 +300,10 @@ impl<'db> Check<'db> for SymClassMember<'db> {
         match self {
             SymClassMember::SymField(sym_field) => sym_field.check(db),
             SymClassMember::SymFunction(sym_function) => sym_function.check(db),
+            SymClassMember::SymVariant(sym_variant) => sym_variant
+                .fields(db)
+                .iter()
+                .for_each(|field| field.check(db)),
         }
     }
 }
@@ -86,6 +316,21 @@ impl<'db> Check<'db> for SymField<'db> {
 
 impl<'db> Check<'db> for SymFunction<'db> {
     fn check(&self, db: &'db dyn crate::Db) {
+        if !self.has_body(db) && !is_host_function(db, *self) {
+            Diagnostic::error(
+                db,
+                self.name_span(db),
+                format!("function `{}` has no body", self.name(db)),
+            )
+            .label(
+                db,
+                Level::Error,
+                self.name_span(db),
+                "only functions in `libdada` can be declared without a body, since they are provided by the host",
+            )
+            .report(db);
+        }
+
         let _ = self.checked_signature(db);
         self.checked_body(db);
     }
@@ -199,13 +444,14 @@ impl<'db> Check<'db> for SymWhereClause<'db> {
     }
 }
 
-impl<'db> Check<'db> for SymWhereClauseKind {
+impl<'db> Check<'db> for SymWhereClauseKind<'db> {
     fn check(&self, _db: &'db dyn crate::Db) {
         match self {
             SymWhereClauseKind::Unique => (),
             SymWhereClauseKind::Shared => (),
             SymWhereClauseKind::Owned => (),
             SymWhereClauseKind::Lent => (),
+            SymWhereClauseKind::Trait(_) => (),
         }
     }
 }
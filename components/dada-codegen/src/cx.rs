@@ -1,10 +1,21 @@
-use dada_ir_sym::{ir::functions::SymFunction, ir::types::SymGenericTerm};
+use std::collections::VecDeque;
+
+use dada_ir_sym::{
+    ir::exprs::SymByteLiteralData, ir::functions::SymFunction, ir::module::SymItem,
+    ir::module::SymModule, ir::types::SymGenericTerm, ir::types::SymTy,
+};
 use dada_util::{FromImpls, Map};
+use generate_expr::closure::ClosureKey;
+use memory::RuntimeFn;
 use salsa::Update;
-use wasm_encoder::{CodeSection, FunctionSection, TypeSection};
+use wasm_encoder::{
+    CodeSection, ExportKind, ExportSection, FunctionSection, ImportSection, Instruction, NameMap,
+    NameSection, TypeSection, ValType,
+};
 
 mod generate_expr;
 mod generate_fn;
+mod memory;
 mod wasm_fn_type;
 mod wasm_repr;
 
@@ -13,52 +24,327 @@ pub(crate) struct Cx<'db> {
     db: &'db dyn crate::Db,
     function_section: FunctionSection,
     type_section: TypeSection,
-    code_section: CodeSection,
+    import_section: ImportSection,
+    export_section: ExportSection,
+
+    /// Code of each defined function, in the order of the function section.
+    code: Vec<FunctionCode>,
+
+    /// Functions exported under the given names.
+    exported_fns: Vec<(String, FnIndex)>,
+
     functions: Map<FnKey<'db>, FnIndex>,
-    codegen_queue: Vec<CodegenQueueItem<'db>>,
+    runtime_fns: Map<RuntimeFn, FnIndex>,
+    drop_glues: Map<SymTy<'db>, FnIndex>,
+
+    /// Imports of async host functions, which are only called from their poll functions.
+    host_imports: Map<FnKey<'db>, FnIndex>,
+
+    /// Poll function of each async fn, along with its index in the table.
+    poll_fns: Map<FnKey<'db>, (FnIndex, u32)>,
+    block_on_fns: Map<FnKey<'db>, FnIndex>,
+    poll_fn_type: Option<u32>,
+
+    /// Function for the body of each closure, along with its index in the table.
+    closures: Map<ClosureKey<'db>, (FnIndex, u32)>,
+
+    /// Functions in the table (used for `call_indirect`), which starts at index 1.
+    table: Vec<u32>,
+
+    /// Contents of the static data segment (see [`memory`]).
+    static_data: Vec<u8>,
+
+    /// Address of each byte literal within the static data segment.
+    byte_literals: Map<SymByteLiteralData<'db>, u32>,
+
+    /// Items awaiting code generation, in the order they were declared.
+    /// Code must be generated in that same order so that the code section lines up with the function section.
+    codegen_queue: VecDeque<CodegenQueueItem<'db>>,
 }
 
 impl<'db> Cx<'db> {
-    pub fn new(db: &'db dyn crate::Db) -> Self {
+    fn new(db: &'db dyn crate::Db) -> Self {
         Self {
             db,
             function_section: Default::default(),
             type_section: Default::default(),
-            code_section: Default::default(),
+            import_section: Default::default(),
+            export_section: Default::default(),
+            code: Default::default(),
+            exported_fns: Default::default(),
             functions: Default::default(),
+            runtime_fns: Default::default(),
+            drop_glues: Default::default(),
+            host_imports: Default::default(),
+            poll_fns: Default::default(),
+            block_on_fns: Default::default(),
+            poll_fn_type: Default::default(),
+            closures: Default::default(),
+            table: Default::default(),
+            static_data: Default::default(),
+            byte_literals: Default::default(),
             codegen_queue: Default::default(),
         }
     }
 
     /// Generates all code reachable from the given fn instantiated with the given arguments.
+    /// The starting fn is exported from the module under its Dada name.
+    /// If it is async, the export runs it to completion (see [`generate_expr::async_fn`]).
     pub fn generate_from_fn(
-        mut self,
+        db: &'db dyn crate::Db,
         function: SymFunction<'db>,
         generics: Vec<SymGenericTerm<'db>>,
     ) -> wasm_encoder::Module {
-        self.declare_fn(function, generics);
-        while let Some(item) = self.codegen_queue.pop() {
-            match item {
-                CodegenQueueItem::Function(fn_key) => self.codegen_fn(fn_key),
-            }
+        Self::generate(db, |cx| cx.export_fn(function, generics))
+    }
+
+    /// Generates all code reachable from `main_fn` along with every `export fn` in `module`.
+    /// Each of them is exported from the module under its Dada name.
+    pub fn generate_from_module(
+        db: &'db dyn crate::Db,
+        module: SymModule<'db>,
+        main_fn: SymFunction<'db>,
+    ) -> wasm_encoder::Module {
+        Self::generate(db, |cx| cx.export_module_fns(module, main_fn))
+    }
+
+    /// Generates a module containing the functions exported by `export`
+    /// and everything they reach.
+    ///
+    /// Imports come first in the WASM function index space, but host functions are only
+    /// imported once the generated code calls them. So while generating code, the indices
+    /// of imports are offset by [`FnIndex::FIRST_IMPORT`] and those of defined functions
+    /// start at 0; they are mapped to their final indices when the module is assembled.
+    fn generate(db: &'db dyn crate::Db, export: impl FnOnce(&mut Self)) -> wasm_encoder::Module {
+        let mut cx = Self::new(db);
+        export(&mut cx);
+        cx.assemble()
+    }
+
+    /// Exports `main_fn` along with every non-generic `export fn` in `module`.
+    fn export_module_fns(&mut self, module: SymModule<'db>, main_fn: SymFunction<'db>) {
+        let db = self.db;
+        self.export_fn(main_fn, vec![]);
+
+        let mut exported_fns = module
+            .items(db)
+            .filter_map(|item| match item {
+                SymItem::SymFunction(function) => Some(function),
+                SymItem::SymClass(_) | SymItem::SymTrait(_) | SymItem::SymPrimitive(_) => None,
+            })
+            .filter(|&function| function != main_fn && function.is_export(db))
+            // Generic functions have no single instantiation to export.
+            .filter(|&function| function.symbols(db).has_generics_of_kind(db, &[]))
+            .collect::<Vec<_>>();
+        exported_fns.sort_by_key(|function| function.name(db).text(db).clone());
+
+        for function in exported_fns {
+            self.export_fn(function, vec![]);
         }
+    }
+
+    /// Declares `function` and exports it under its Dada name.
+    /// Async functions are exported as functions that run them to completion.
+    fn export_fn(&mut self, function: SymFunction<'db>, generics: Vec<SymGenericTerm<'db>>) {
+        let index = if function.effects(self.db).async_effect {
+            self.declare_block_on_fn(function, generics)
+        } else {
+            self.declare_fn(function, generics)
+        };
+        self.exported_fns
+            .push((function.name(self.db).text(self.db).clone(), index));
+    }
+
+    /// Generates code for everything that has been declared and assembles the final module.
+    fn assemble(mut self) -> wasm_encoder::Module {
+        self.generate_queued();
+
+        for (name, index) in &self.exported_fns {
+            self.export_section
+                .export(name, ExportKind::Func, self.final_fn_index(*index));
+        }
+
+        let mut code_section = CodeSection::new();
+        for code in std::mem::take(&mut self.code) {
+            code_section.function(&code.encode(|index| self.final_fn_index(index)));
+        }
+
+        let (table_section, element_section) = self.table_sections();
+        let (memory_section, global_section, data_section) = self.memory_sections();
 
         let mut module = wasm_encoder::Module::new();
         module.section(&self.type_section);
+        module.section(&self.import_section);
         module.section(&self.function_section);
-        module.section(&self.code_section);
+        module.section(&table_section);
+        module.section(&memory_section);
+        module.section(&global_section);
+        module.section(&self.export_section);
+        module.section(&element_section);
+        module.section(&code_section);
+        module.section(&data_section);
+        module.section(&self.name_section());
 
         module
     }
+
+    /// Generates code for each item on the codegen queue, including those queued along the way.
+    fn generate_queued(&mut self) {
+        while let Some(item) = self.codegen_queue.pop_front() {
+            match item {
+                CodegenQueueItem::Function(fn_key) => self.codegen_fn(fn_key),
+                CodegenQueueItem::Runtime(runtime_fn) => self.codegen_runtime_fn(runtime_fn),
+                CodegenQueueItem::DropGlue(ty) => self.codegen_drop_glue(ty),
+                CodegenQueueItem::Poll(fn_key) => self.codegen_poll_fn(fn_key),
+                CodegenQueueItem::BlockOn(fn_key) => self.codegen_block_on_fn(fn_key),
+                CodegenQueueItem::Closure(key) => self.codegen_closure_fn(key),
+            }
+        }
+    }
+
+    /// Maps an index assigned while generating code to its index in the module,
+    /// where the imports come before the defined functions (see [`Cx::generate`]).
+    fn final_fn_index(&self, FnIndex(index): FnIndex) -> u32 {
+        match index.checked_sub(FnIndex::FIRST_IMPORT) {
+            Some(import) => import,
+            None => self.import_section.len() + index,
+        }
+    }
+
+    /// Index of the next function added to the function section.
+    fn next_defined_fn_index(&self) -> FnIndex {
+        FnIndex(self.function_section.len())
+    }
+
+    /// The `name` custom section giving each generated function a readable name.
+    fn name_section(&self) -> NameSection {
+        let mut fn_names = self
+            .functions
+            .iter()
+            .map(|(key, &FnIndex(index))| (index, key.mangled_name(self.db)))
+            .chain(
+                self.host_imports
+                    .iter()
+                    .map(|(key, &FnIndex(index))| (index, key.mangled_name(self.db))),
+            )
+            .chain(self.poll_fns.iter().map(|(key, &(FnIndex(index), _))| {
+                (index, format!("$poll[{}]", key.mangled_name(self.db)))
+            }))
+            .chain(self.block_on_fns.iter().map(|(key, &FnIndex(index))| {
+                (index, format!("$block_on[{}]", key.mangled_name(self.db)))
+            }))
+            .chain(
+                self.closures
+                    .values()
+                    .map(|&(FnIndex(index), table_index)| {
+                        (index, format!("$closure{table_index}"))
+                    }),
+            )
+            .chain(
+                self.runtime_fns
+                    .iter()
+                    .map(|(runtime_fn, &FnIndex(index))| (index, runtime_fn.name().to_string())),
+            )
+            .chain(
+                self.drop_glues
+                    .iter()
+                    .map(|(ty, &FnIndex(index))| (index, format!("$drop[{ty}]"))),
+            )
+            .collect::<Vec<_>>();
+
+        // Entries in a name map must be sorted by index.
+        for (index, _) in &mut fn_names {
+            *index = self.final_fn_index(FnIndex(*index));
+        }
+        fn_names.sort();
+
+        let mut name_map = NameMap::new();
+        for (index, name) in &fn_names {
+            name_map.append(*index, name);
+        }
+
+        let mut names = NameSection::new();
+        names.functions(&name_map);
+        names
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Update)]
 pub(crate) struct FnKey<'db>(SymFunction<'db>, Vec<SymGenericTerm<'db>>);
 
+impl<'db> FnKey<'db> {
+    /// A readable name for this instantiation, e.g., `String.len` or `identity[u32]`.
+    pub(crate) fn mangled_name(&self, db: &'db dyn crate::Db) -> String {
+        let FnKey(function, generics) = self;
+
+        let mut name = String::new();
+        if let Some(aggr) = function.owner_aggregate(db) {
+            name.push_str(aggr.name(db).text(db));
+            name.push('.');
+        }
+        name.push_str(function.name(db).text(db));
+
+        if !generics.is_empty() {
+            let generics = generics
+                .iter()
+                .map(|g| g.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            name.push_str(&format!("[{generics}]"));
+        }
+
+        name
+    }
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Update)]
 pub(crate) struct FnIndex(u32);
 
+impl FnIndex {
+    /// Offset of the indices of imports while generating code (see [`Cx::generate`]).
+    const FIRST_IMPORT: u32 = 1 << 31;
+}
+
+/// The code of a defined function, which is encoded when the module is assembled
+/// and the final indices of the functions it calls are known.
+pub(crate) enum FunctionCode {
+    /// Code that calls no other functions.
+    Encoded(wasm_encoder::Function),
+
+    /// The types of the locals (other than the parameters) and the instructions of the body.
+    Instructions(Vec<ValType>, Vec<Instruction<'static>>),
+}
+
+impl FunctionCode {
+    /// Encodes the function, replacing the index of each function it calls with `final_index(index)`.
+    fn encode(self, final_index: impl Fn(FnIndex) -> u32) -> wasm_encoder::Function {
+        match self {
+            FunctionCode::Encoded(function) => function,
+            FunctionCode::Instructions(locals, instructions) => {
+                let mut f = wasm_encoder::Function::new_with_locals_types(locals);
+                for instruction in instructions {
+                    match instruction {
+                        Instruction::Call(index) => {
+                            f.instruction(&Instruction::Call(final_index(FnIndex(index))))
+                        }
+                        instruction => f.instruction(&instruction),
+                    };
+                }
+                f.instruction(&Instruction::End);
+                f
+            }
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Update, FromImpls)]
 enum CodegenQueueItem<'db> {
     Function(FnKey<'db>),
+    Runtime(RuntimeFn),
+    DropGlue(SymTy<'db>),
+    #[no_from_impl]
+    Poll(FnKey<'db>),
+    #[no_from_impl]
+    BlockOn(FnKey<'db>),
+    Closure(ClosureKey<'db>),
 }
@@ -7,13 +7,17 @@ use dada_ir_sym::{
     ir::primitive::SymPrimitiveKind, ir::subst::Subst, ir::types::SymTyName,
     ir::variables::SymVariable,
 };
-use dada_util::Map;
+use dada_util::{Map, Set};
 use wasm_encoder::{Instruction, ValType};
 use wasm_place_repr::{WasmLocal, WasmPlaceRepr};
 
-use super::wasm_repr::WasmReprCx;
-use super::{Cx, wasm_repr::WasmRepr};
+use super::memory::stack_check_instructions;
+use super::wasm_repr::{FLAG_MY, WasmReprCx};
+use super::{Cx, FunctionCode, wasm_repr::WasmRepr};
 
+mod async_fn;
+pub(crate) mod closure;
+mod drop_glue;
 pub(crate) mod wasm_place_repr;
 
 pub(crate) struct ExprCodegen<'cx, 'db> {
@@ -25,6 +29,9 @@ pub(crate) struct ExprCodegen<'cx, 'db> {
     /// Accumulates wasm locals. We make no effort to reduce the number of local variables created.
     wasm_locals: Vec<wasm_encoder::ValType>,
 
+    /// The first `wasm_num_params` of the `wasm_locals` are the parameters of the function.
+    wasm_num_params: usize,
+
     /// Local variable that stores starting address in our stack frame
     wasm_stack_pointer: WasmLocal,
 
@@ -34,10 +41,57 @@ pub(crate) struct ExprCodegen<'cx, 'db> {
     /// Maps each Dada variable to a range of wasm locals. Note that a single value can be inlined into multiple wasm locals.
     variables: Map<SymVariable<'db>, Arc<WasmPlaceRepr>>,
 
+    /// Variables that are leased somewhere in the code being generated, other than through a class object.
+    /// A lease is a pointer, so these are stored in the stack frame rather than in WASM locals
+    /// (see [`Self::note_leased_variables`]).
+    leased_variables: Set<SymVariable<'db>>,
+
+    /// The type of each Dada variable introduced so far,
+    /// used to reduce the `given[..]` permissions that name them.
+    variable_tys: Map<SymVariable<'db>, SymTy<'db>>,
+
+    /// The Dada variables in scope (innermost last) and their types.
+    /// Each is dropped when it goes out of scope or when we return.
+    scopes: Vec<(SymVariable<'db>, SymTy<'db>)>,
+
+    /// For each `let`-bound variable in scope, the environments of the closures that capture it
+    /// and whose other captured variables outlive it (see [`Self::push_closure`]).
+    /// Each is a local holding a pointer to the environment (or 0) along with its field representations.
+    closure_envs: Map<SymVariable<'db>, Vec<(Arc<WasmPlaceRepr>, Vec<WasmRepr>)>>,
+
+    /// Set when generating the body of an async fn, which runs in its poll function.
+    /// Returned values are then stored into the future rather than left on the WASM stack.
+    poll: Option<async_fn::PollState<'db>>,
+
+    /// The loops enclosing the code being generated, innermost last.
+    loops: Vec<EnclosingLoop>,
+
+    /// Indices of the WASM locals holding object pointers that are known to be non-null
+    /// whenever the code being generated runs (see [`Self::object_places`]).
+    /// A local is removed when it is assigned, and the set is narrowed at the end of each
+    /// conditional and cleared around loops, so it only holds locals checked on every path.
+    null_checked: Set<u32>,
+
+    /// Number of WASM blocks (`block`, `loop`, or `if`) enclosing the code being generated.
+    /// Used to compute the label of a `br` instruction.
+    block_depth: u32,
+
     /// Accumulates wasm instructions.
     instructions: Vec<Instruction<'static>>,
 }
 
+/// A Dada loop is compiled to a WASM `loop` nested inside of a `block`:
+/// `break` branches to the end of the `block` and `continue` to the start of the `loop`.
+#[derive(Copy, Clone, Debug)]
+struct EnclosingLoop {
+    /// Value of `block_depth` inside the `block`.
+    block_depth: u32,
+
+    /// Number of `scopes` in effect when the loop was entered.
+    /// The variables introduced after that must be dropped when we exit an iteration.
+    num_scopes: usize,
+}
+
 impl<'cx, 'db> ExprCodegen<'cx, 'db> {
     pub fn new(cx: &'cx mut Cx<'db>, generics: Map<SymVariable<'db>, SymGenericTerm<'db>>) -> Self {
         // Initially there is one local variable, the stack pointer.
@@ -45,36 +99,97 @@ impl<'cx, 'db> ExprCodegen<'cx, 'db> {
             cx,
             generics,
             wasm_locals: vec![ValType::I32],
+            wasm_num_params: 1,
             variables: Default::default(),
+            variable_tys: Default::default(),
+            leased_variables: Default::default(),
+            scopes: Default::default(),
+            closure_envs: Default::default(),
+            poll: None,
+            loops: Default::default(),
+            null_checked: Default::default(),
+            block_depth: 0,
             instructions: Default::default(),
             wasm_stack_frame_size: 0,
             wasm_stack_pointer: WasmLocal { index: 0 },
         }
     }
 
-    pub fn into_function(self) -> wasm_encoder::Function {
-        let mut f = wasm_encoder::Function::new_with_locals_types(self.wasm_locals);
-        for instruction in self.instructions {
-            f.instruction(&instruction);
-        }
-        f
+    /// Finishes the function. If it has a stack frame, it first checks that the frame fits on the stack.
+    pub fn into_function(self) -> FunctionCode {
+        let locals = self
+            .wasm_locals
+            .into_iter()
+            .skip(self.wasm_num_params)
+            .collect();
+        let instructions = if self.wasm_stack_frame_size > 0 {
+            stack_check_instructions(self.wasm_stack_pointer.index, self.wasm_stack_frame_size)
+                .into_iter()
+                .chain(self.instructions)
+                .collect()
+        } else {
+            self.instructions
+        };
+        FunctionCode::Instructions(locals, instructions)
     }
 
     /// Returns the [`WasmRepr`][] for a Dada type.
     pub fn wasm_repr_of_type(&self, ty: SymTy<'db>) -> WasmRepr {
         let db = self.cx.db;
-        let mut wrcx = WasmReprCx::new(db, &self.generics);
+        let mut wrcx = WasmReprCx::new(db, &self.generics).with_variable_tys(&self.variable_tys);
         wrcx.wasm_repr_of_type(ty)
     }
 
-    pub fn pop_arguments(&mut self, inputs: &[SymVariable<'db>], input_tys: &[SymTy<'db>]) {
+    /// Introduces places for the function's inputs.
+    /// The inputs are owned by the function and dropped when it returns.
+    pub fn bind_arguments(&mut self, inputs: &[SymVariable<'db>], input_tys: &[SymTy<'db>]) {
+        self.bind_parameters(inputs, input_tys);
+        for (&input, &input_ty) in inputs.iter().zip(input_tys) {
+            self.scopes.push((input, input_ty));
+        }
+    }
+
+    /// Introduces places for the function's inputs without taking responsibility for dropping them.
+    /// The WASM parameters are the first locals, so the locals allocated for the inputs
+    /// (after the stack pointer, in order) are exactly the parameters and nothing needs to be stored,
+    /// except that inputs that are leased are then moved into the stack frame.
+    fn bind_parameters(&mut self, inputs: &[SymVariable<'db>], input_tys: &[SymTy<'db>]) {
         assert_eq!(inputs.len(), input_tys.len());
-        for (&input, &input_ty) in inputs.iter().zip(input_tys).rev() {
-            self.insert_variable(input, input_ty);
-            self.pop_and_store(&self.place_for_local(input));
+        for (&input, &input_ty) in inputs.iter().zip(input_tys) {
+            self.variable_tys.insert(input, input_ty);
+            let param = self.emplace_local(&self.wasm_repr_of_type(input_ty));
+            self.variables.insert(input, param);
+        }
+        self.wasm_num_params = self.wasm_locals.len();
+
+        for (&input, &input_ty) in inputs.iter().zip(input_tys) {
+            if self.leased_variables.contains(&input) {
+                let param = self.place_for_local(input);
+                self.insert_variable(input, input_ty);
+                self.push_from(&param);
+                self.pop_and_store(&self.place_for_local(input));
+            }
+        }
+    }
+
+    /// Records the variables that are leased within `body`, which must be stored in memory.
+    /// Must be called before any of those variables are inserted.
+    pub(super) fn note_leased_variables(&mut self, body: SymExpr<'db>) {
+        let db = self.cx.db;
+        let mut leased_places = vec![];
+        body.visit(db, &mut |expr| {
+            if let SymExprKind::PermissionOp(PermissionOp::Mutate, place) = *expr.kind(db) {
+                leased_places.push(place);
+            }
+        });
+
+        for place in leased_places {
+            // A lease of a class object is a copy of the pointer to it.
+            if let WasmRepr::Class(_) = self.wasm_repr_of_type(place.ty(db)) {
+                continue;
+            }
+            self.leased_variables.extend(place.root_variable(db));
         }
-        self.instructions
-            .push(Instruction::LocalSet(self.wasm_stack_pointer.index));
     }
 
     /// Generate code to execute the expression, leaving the result on the top of the wasm stack.
@@ -82,15 +197,15 @@ impl<'cx, 'db> ExprCodegen<'cx, 'db> {
         let db = self.cx.db;
         match *expr.kind(db) {
             SymExprKind::Semi(object_expr, object_expr1) => {
-                self.push_expr(object_expr);
-                self.pop_and_drop(object_expr.ty(db));
+                self.unless_resuming_into(&[object_expr1], |this| {
+                    this.push_expr(object_expr);
+                    this.pop_and_drop(object_expr.ty(db));
+                });
                 self.push_expr(object_expr1);
             }
             SymExprKind::Tuple(ref elements) => {
                 // the representation of a tuple is inlined onto the stack (like any other struct type)
-                for &element in elements {
-                    self.push_expr(element);
-                }
+                self.push_exprs(elements);
             }
             SymExprKind::Primitive(literal) => self.push_literal(expr.ty(db), literal),
             SymExprKind::LetIn {
@@ -100,48 +215,64 @@ impl<'cx, 'db> ExprCodegen<'cx, 'db> {
                 body,
             } => {
                 self.insert_variable(lv, ty);
+                let place = self.place_for_local(lv);
+
+                self.unless_resuming_into(&[body], |this| {
+                    if let Some(initializer) = initializer {
+                        this.push_expr(initializer);
+                        this.pop_and_store(&place);
+                    } else {
+                        // Dropping a variable that was never assigned must do nothing.
+                        this.store_zeroes(&place);
+                    }
+                });
 
-                if let Some(initializer) = initializer {
-                    self.push_expr(initializer);
-                    self.pop_and_store(&self.variables[&lv].clone());
-                } else {
-                    // FIXME: should zero out the values
-                }
-
+                self.closure_envs.insert(lv, vec![]);
+                self.scopes.push((lv, ty));
                 self.push_expr(body);
+                self.scopes.pop();
+
+                self.drop_variable(lv, ty);
+                self.closure_envs.remove(&lv);
             }
             SymExprKind::Await {
                 future,
                 await_keyword: _,
-            } => {
-                self.push_expr(future);
-                // FIXME: for now we just ignore futures and execute everything synchronously
-            }
+            } => self.push_await(expr, future),
             SymExprKind::Assign { place, value } => {
                 let wasm_place = self.place(place);
                 self.push_expr(value);
-
-                // FIXME: have to drop the old value
-
+                self.drop_place(&wasm_place, place.ty(db));
                 self.pop_and_store(&wasm_place);
             }
             SymExprKind::PermissionOp(permission_op, object_place_expr) => {
                 let wasm_place_repr = self.place(object_place_expr);
                 match permission_op {
                     PermissionOp::Mutate => {
-                        self.push_leased_from(&wasm_place_repr);
+                        self.push_leased_from(wasm_place_repr, object_place_expr.ty(db));
                     }
 
                     PermissionOp::Reference => {
-                        self.push_shared_from(&wasm_place_repr);
+                        // References are copies of the value (for classes, of the pointer).
+                        // They do not own the object and are never dropped.
+                        self.push_from(&wasm_place_repr);
                     }
 
                     PermissionOp::Give => {
                         self.push_from(&wasm_place_repr);
+
+                        // Giving away an owned value leaves nothing behind to drop.
+                        if self.needs_drop(object_place_expr.ty(db)) {
+                            self.store_zeroes(&wasm_place_repr);
+                        }
                     }
 
                     PermissionOp::Share => {
-                        todo!()
+                        if self.needs_drop(object_place_expr.ty(db)) {
+                            self.push_shared_from(&wasm_place_repr);
+                        } else {
+                            self.push_from(&wasm_place_repr);
+                        }
                     }
                 }
             }
@@ -157,16 +288,24 @@ impl<'cx, 'db> ExprCodegen<'cx, 'db> {
                 self.push_pointer(self.next_stack_frame());
 
                 // Now push each of the arguments in turn.
+                // The callee takes ownership of the arguments, so the temporaries are zeroed
+                // to ensure they are not dropped again when they go out of scope.
                 for arg_temp in arg_temps {
                     let place = self.variables[arg_temp].clone();
                     self.push_from(&place);
+                    self.store_zeroes(&place);
                 }
 
                 self.instructions.push(Instruction::Call(fn_index.0));
             }
+            SymExprKind::CallIndirect {
+                callee,
+                ref arg_temps,
+            } => self.push_call_indirect(callee, arg_temps),
+            SymExprKind::Closure { ref captures, .. } => self.push_closure(expr, captures),
             SymExprKind::Return(object_expr) => {
                 self.push_expr(object_expr);
-                self.instructions.push(Instruction::Return);
+                self.pop_and_return(object_expr.ty(db));
             }
             SymExprKind::Not {
                 operand,
@@ -177,49 +316,137 @@ impl<'cx, 'db> ExprCodegen<'cx, 'db> {
                 self.instructions.push(Instruction::I32Xor);
             }
             SymExprKind::BinaryOp(binary_op, object_expr, object_expr1) => {
-                self.push_expr(object_expr);
-                self.push_expr(object_expr1);
+                self.push_exprs(&[object_expr, object_expr1]);
                 self.execute_binary_op(binary_op, object_expr.ty(db), object_expr.ty(db));
             }
-            SymExprKind::Aggregate { ty, ref fields } => {
+            SymExprKind::Aggregate {
+                ty,
+                ref fields,
+                labels: _,
+            } => {
                 let wasm_repr = self.wasm_repr_of_type(ty);
                 match wasm_repr {
                     WasmRepr::Struct(field_reprs) => {
                         assert_eq!(fields.len(), field_reprs.len());
-                        for &field in fields {
-                            self.push_expr(field);
-                        }
+                        self.push_exprs(fields);
                     }
                     WasmRepr::Class(field_reprs) => {
                         assert_eq!(fields.len(), field_reprs.len());
 
-                        // push flag word
-                        self.instructions.push(Instruction::I32Const(1));
-
-                        for &field in fields {
-                            self.push_expr(field);
+                        // initialize the flag word and then each field
+                        let (object, field_places) = self.unless_resuming_into(fields, |this| {
+                            let object = this.allocate_object(&field_reprs);
+                            let (flags, field_places) = this.object_places(&object, &field_reprs);
+                            this.instructions.push(Instruction::I32Const(FLAG_MY));
+                            this.pop_and_store(&WasmPlaceRepr::Heap(flags, ValType::I32));
+                            (object, field_places)
+                        });
+                        for (index, (&field, field_place)) in
+                            fields.iter().zip(&field_places).enumerate()
+                        {
+                            self.unless_resuming_into(&fields[index + 1..], |this| {
+                                this.push_expr(field);
+                                this.pop_and_store(field_place);
+                            });
                         }
+
+                        // the value is the pointer to the object
+                        self.push_from(&object);
                     }
                     WasmRepr::Val(_) | WasmRepr::Nothing => {
                         panic!("not an aggregate: {ty:?}")
                     }
                 }
             }
+            SymExprKind::Variant {
+                ty,
+                variant,
+                ref fields,
+            } => {
+                // The tag comes first, followed by the fields of each variant in turn.
+                // Only the fields of `variant` are initialized; the others are zeroed.
+                let WasmRepr::Struct(field_reprs) = self.wasm_repr_of_type(ty) else {
+                    panic!("not an enum: {ty:?}")
+                };
+
+                // The tag is pushed first, so fields that can suspend are evaluated beforehand.
+                let spilled = self
+                    .first_await_within(fields)
+                    .map(|_| self.spill_exprs(fields));
+
+                self.instructions
+                    .push(Instruction::I32Const(variant.index(db) as i32));
+                let mut field_reprs = field_reprs[1..].iter();
+                for other_variant in variant.owner(db).variants(db) {
+                    let other_reprs: Vec<_> = (&mut field_reprs)
+                        .take(other_variant.fields(db).len())
+                        .collect();
+                    if other_variant == variant {
+                        match &spilled {
+                            Some(temps) => temps.iter().for_each(|temp| self.push_from(temp)),
+                            None => fields.iter().for_each(|&field| self.push_expr(field)),
+                        }
+                    } else {
+                        for field_repr in other_reprs {
+                            self.push_zeroes(field_repr);
+                        }
+                    }
+                }
+            }
+            SymExprKind::IsVariant { place, variant } => {
+                let wasm_place = self.place(place);
+                let (place, _) = self.deref_leases(wasm_place, place.ty(db));
+                match &*place {
+                    WasmPlaceRepr::Struct(fields) => self.push_from(&fields[0]),
+                    _ => panic!("unexpected place for enum: {place:?}"),
+                }
+                self.instructions
+                    .push(Instruction::I32Const(variant.index(db) as i32));
+                self.instructions.push(Instruction::I32Eq);
+            }
             SymExprKind::Match { ref arms } => {
                 self.push_match_expr(expr.ty(db), arms);
             }
+            SymExprKind::Loop { body } => self.push_loop(expr.ty(db), body),
+            SymExprKind::Break(value) => {
+                self.push_expr(value);
+                let enclosing_loop = self.exit_iteration();
+                self.instructions.push(Instruction::Br(
+                    self.block_depth - enclosing_loop.block_depth,
+                ));
+            }
+            SymExprKind::Continue => {
+                let enclosing_loop = self.exit_iteration();
+                self.instructions.push(Instruction::Br(
+                    self.block_depth - enclosing_loop.block_depth - 1,
+                ));
+            }
             SymExprKind::Error(reported) => self.push_error(reported),
-            #[expect(unused_variables)]
-            SymExprKind::ByteLiteral(sym_byte_literal) => todo!(),
+            SymExprKind::ByteLiteral(sym_byte_literal) => {
+                // Byte literals are stored in static data; their value is a pointer to the bytes.
+                let address = self.cx.declare_byte_literal(sym_byte_literal.data(db));
+                self.instructions
+                    .push(Instruction::I32Const(address as i32));
+            }
         }
     }
 
-    fn pop_and_drop(&mut self, _of_type: SymTy<'db>) {
-        // currently everything is stack allocated, no dropping required
+    /// Pop a value of type `of_type` from the WASM stack and drop it.
+    fn pop_and_drop(&mut self, of_type: SymTy<'db>) {
+        let place = self.emplace_local(&self.wasm_repr_of_type(of_type));
+        self.pop_and_store(&place);
+        self.drop_place(&place, of_type);
     }
 
+    /// Return the value of type `_of_type` on the WASM stack, dropping all variables in scope.
+    /// In the body of an async fn, the value is stored into the future, which is then ready.
     pub(super) fn pop_and_return(&mut self, _of_type: SymTy<'db>) {
-        self.instructions.push(Instruction::Return);
+        self.drop_scopes();
+        if self.poll.is_some() {
+            self.pop_and_complete();
+        } else {
+            self.instructions.push(Instruction::Return);
+        }
     }
 
     /// Push the correct instructions to execute `binary_op` on operands of type `lhs_ty` and `rhs_ty`
@@ -467,17 +694,19 @@ impl<'cx, 'db> ExprCodegen<'cx, 'db> {
         match ty.kind(db) {
             SymTyKind::Named(ty_name, _ty_args) => match ty_name {
                 SymTyName::Primitive(sym_primitive) => Ok(sym_primitive.kind(db)),
-                SymTyName::Aggregate(_) | SymTyName::Future | SymTyName::Tuple { arity: _ } => {
-                    Err(NotPrimitive::OtherType)
-                }
+                SymTyName::Aggregate(_)
+                | SymTyName::Future
+                | SymTyName::Tuple { arity: _ }
+                | SymTyName::Function { arity: _ } => Err(NotPrimitive::OtherType),
             },
             SymTyKind::Var(sym_variable) => {
                 self.primitive_kind(self.generics[sym_variable].assert_type(db))
             }
             SymTyKind::Never | SymTyKind::Error(_) => Err(NotPrimitive::DeadCode),
             SymTyKind::Infer(_) => panic!("unexpected inference variable"),
-            #[expect(unused_variables)]
-            SymTyKind::Perm(sym_perm, sym_ty) => todo!(),
+
+            // Only classes can be leased, so a primitive with any permission is just a copy.
+            SymTyKind::Perm(_, sym_ty) => self.primitive_kind(*sym_ty),
         }
     }
 
@@ -488,22 +717,28 @@ impl<'cx, 'db> ExprCodegen<'cx, 'db> {
 
         if let Some(condition) = if_arm.condition {
             // Evaluate the condition.
-            self.push_expr(condition);
+            let later_exprs: Vec<_> = else_arms
+                .iter()
+                .flat_map(|arm| arm.condition.into_iter().chain([arm.body]))
+                .collect();
+            self.push_arm_condition(condition, if_arm.body, &later_exprs);
 
             // The `If` block will execute the next set of instructions
             // if the condition was true. Otherwise it will skip to the `Else` or `End.`
             let block_type = self.block_type(match_ty);
             self.instructions.push(Instruction::If(block_type));
+            self.block_depth += 1;
 
             // Code to execute if true.
-            self.push_expr(if_arm.body);
+            self.in_conditional_block(|this| this.push_expr(if_arm.body));
 
             // If false push an `Else` and evaluate it recursively.
             self.instructions.push(Instruction::Else);
-            self.push_match_expr(match_ty, else_arms);
+            self.in_conditional_block(|this| this.push_match_expr(match_ty, else_arms));
 
             // End the if.
             self.instructions.push(Instruction::End);
+            self.block_depth -= 1;
         } else {
             // Execute body unconditionally.
             self.push_expr(if_arm.body);
@@ -513,6 +748,80 @@ impl<'cx, 'db> ExprCodegen<'cx, 'db> {
         }
     }
 
+    /// Push a loop that executes `body` until it breaks with a value of type `loop_ty`
+    /// (see [`EnclosingLoop`]).
+    fn push_loop(&mut self, loop_ty: SymTy<'db>, body: SymExpr<'db>) {
+        let db = self.cx.db;
+
+        let block_type = self.block_type(loop_ty);
+        self.instructions.push(Instruction::Block(block_type));
+        self.block_depth += 1;
+        self.loops.push(EnclosingLoop {
+            block_depth: self.block_depth,
+            num_scopes: self.scopes.len(),
+        });
+
+        self.instructions
+            .push(Instruction::Loop(wasm_encoder::BlockType::Empty));
+        self.block_depth += 1;
+
+        // Pointers checked in one iteration may be reassigned before the next,
+        // and the loop may be exited from any iteration.
+        self.null_checked.clear();
+        self.push_expr(body);
+        self.pop_and_drop(body.ty(db));
+        self.instructions.push(Instruction::Br(0));
+        self.instructions.push(Instruction::End);
+        self.block_depth -= 1;
+
+        // The loop is only exited by branching to the end of the block.
+        self.instructions.push(Instruction::Unreachable);
+        self.loops.pop();
+        self.instructions.push(Instruction::End);
+        self.block_depth -= 1;
+        self.null_checked.clear();
+    }
+
+    /// Generate code with `op` that may or may not run, e.g., one arm of an `if`.
+    /// Afterwards, only the pointers that were known to be non-null before are still known
+    /// to be, and only if they were not reassigned.
+    fn in_conditional_block<R>(&mut self, op: impl FnOnce(&mut Self) -> R) -> R {
+        let null_checked_before = self.null_checked.clone();
+        let result = op(self);
+        self.null_checked
+            .retain(|local| null_checked_before.contains(local));
+        result
+    }
+
+    /// Generate an `if` (without an `else`) whose body is generated by `op`.
+    /// The condition must already be on the WASM stack.
+    fn push_if<R>(&mut self, op: impl FnOnce(&mut Self) -> R) -> R {
+        self.instructions
+            .push(Instruction::If(wasm_encoder::BlockType::Empty));
+        self.block_depth += 1;
+        let result = self.in_conditional_block(op);
+        self.instructions.push(Instruction::End);
+        self.block_depth -= 1;
+        result
+    }
+
+    /// Drop the variables introduced within the current iteration of the innermost loop,
+    /// in preparation for a `break` or `continue`, and return that loop.
+    fn exit_iteration(&mut self) -> EnclosingLoop {
+        let enclosing_loop = *self
+            .loops
+            .last()
+            .expect("`break` or `continue` outside of loop");
+        for (variable, ty) in self.scopes[enclosing_loop.num_scopes..]
+            .to_vec()
+            .into_iter()
+            .rev()
+        {
+            self.drop_variable(variable, ty);
+        }
+        enclosing_loop
+    }
+
     /// [Block control-flow instructions][cfi] like `if` and friends
     /// come equipped with an associated "block type". This is a function
     /// type indicating the *inputs* they consume from the stack (in our case,
@@ -537,7 +846,10 @@ impl<'cx, 'db> ExprCodegen<'cx, 'db> {
         let kind = match ty.kind(db) {
             SymTyKind::Named(sym_ty_name, _) => match sym_ty_name {
                 SymTyName::Primitive(sym_primitive) => sym_primitive.kind(db),
-                SymTyName::Aggregate(_) | SymTyName::Future | SymTyName::Tuple { arity: _ } => {
+                SymTyName::Aggregate(_)
+                | SymTyName::Future
+                | SymTyName::Tuple { arity: _ }
+                | SymTyName::Function { arity: _ } => {
                     panic!("unexpected type for literal {literal:?}: {ty:?}")
                 }
             },
@@ -550,8 +862,9 @@ impl<'cx, 'db> ExprCodegen<'cx, 'db> {
             SymTyKind::Error(reported) => {
                 return self.push_error(*reported);
             }
-            #[expect(unused_variables)]
-            SymTyKind::Perm(sym_perm, sym_ty) => todo!(),
+            SymTyKind::Perm(_, sym_ty) => {
+                return self.push_literal(*sym_ty, literal);
+            }
         };
         match kind {
             SymPrimitiveKind::Bool
@@ -0,0 +1,871 @@
+//! Lowering of `async fn` and `.await`.
+//!
+//! Calling an async fn `f` runs no user code: it moves the arguments into a
+//! freshly allocated "environment" object and returns a future object
+//! (see [`FUTURE_POLL`] and friends) that records them along with the
+//! table index of `f`'s *poll function*. Polling the future calls the poll
+//! function through the table, which runs the body of `f` until it either completes,
+//! storing the result into the future, or reaches an `.await` whose future is not ready.
+//! This gives `.await` its real semantics: effects happen when (and in the
+//! order that) futures are awaited, not when async fns are called.
+//!
+//! # Suspending and resuming
+//!
+//! The poll function is a state machine. Its state ([`FUTURE_STATE`]) is 0 before
+//! it first runs and otherwise the id of the `.await` it is suspended at. To suspend,
+//! it saves its WASM locals and its stack frame into a heap object that takes the place
+//! of the environment and returns "pending". When polled again, it restores them and
+//! re-enters the body, skipping whatever ran before that `.await` (see
+//! [`ExprCodegen::unless_resuming_into`]), and polls the awaited future again.
+//! The WASM stack is not saved, so operands evaluated before an expression that
+//! can suspend are kept in locals instead (see [`ExprCodegen::spill_exprs`]).
+//!
+//! The stack frame is saved by value, so a lease of a variable stored there remains valid
+//! only because a future is always polled with the same stack pointer.
+//!
+//! # Executor
+//!
+//! Exported async fns (including `main`) are wrapped in a *block-on* function that creates
+//! the future and polls it until it is ready, which is all the executor the host needs.
+//! Async host functions are polled too (see [`Cx::declare_host_poll_fn_type`]).
+//!
+//! Polling a future with the `cancel` flag set drops whatever it owns instead of running it.
+//! The drop glue of a future that has not completed does this.
+
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use dada_ir_sym::{
+    ir::{
+        exprs::{SymExpr, SymExprKind},
+        functions::SymFunction,
+        types::{SymGenericTerm, SymTy, SymTyKind, SymTyName},
+        variables::SymVariable,
+    },
+    prelude::CheckedBody,
+    well_known::is_host_function,
+};
+use dada_util::Map;
+use wasm_encoder::{
+    BlockType, ConstExpr, ElementSection, Elements, Instruction, RefType, TableSection, TableType,
+    ValType,
+};
+
+use super::{ExprCodegen, WasmLocal, WasmPlaceRepr};
+use crate::cx::{
+    CodegenQueueItem, Cx, FnIndex, FnKey,
+    generate_fn::CodegenSignature,
+    wasm_fn_type::FnTypeIndex,
+    wasm_repr::{
+        FLAG_MY, FUTURE_ENV, FUTURE_POLL, FUTURE_RESULT, FUTURE_STATE, WasmRepr, WasmReprCx,
+    },
+};
+
+/// What the poll function of an async fn does to produce its result.
+enum PollBody<'db> {
+    /// Evaluates the body of the function.
+    Expr(SymExpr<'db>),
+
+    /// Polls the given host import.
+    Host(FnIndex),
+}
+
+/// Code generation state for the body of an async fn, which runs in its poll function.
+pub(super) struct PollState<'db> {
+    /// The places of the fields of the future being polled.
+    future_fields: Vec<Arc<WasmPlaceRepr>>,
+
+    /// Local storing the id of the `.await` that the body is resuming at,
+    /// or 0 if the body is starting or has already got there.
+    resuming: Arc<WasmPlaceRepr>,
+
+    /// The id of each `.await` in the body, numbered from 1 in the order that
+    /// [`SymExpr::visit`] reaches them. So the ids within any expression are consecutive.
+    await_ids: Map<SymExpr<'db>, u32>,
+
+    /// Memoizes [`ExprCodegen::await_ids_within`].
+    await_ranges: Map<SymExpr<'db>, Option<(u32, u32)>>,
+
+    /// The block depth just inside the block that is exited to suspend.
+    suspend_depth: u32,
+
+    /// Locals holding operands that have been spilled (see [`ExprCodegen::spill_exprs`])
+    /// but not yet pushed back onto the WASM stack, and their types.
+    spilled: Vec<(Arc<WasmPlaceRepr>, SymTy<'db>)>,
+
+    /// For each `.await` id, the places that own values while suspended there, and their types.
+    /// They are dropped if the future is cancelled.
+    suspended_owners: Vec<(u32, Vec<(Arc<WasmPlaceRepr>, SymTy<'db>)>)>,
+}
+
+impl<'db> Cx<'db> {
+    /// Declares the poll function of the async fn instantiation `key`
+    /// and returns its index in the function table.
+    pub(crate) fn declare_poll_fn(&mut self, key: FnKey<'db>) -> u32 {
+        if let Some(&(_, table_index)) = self.poll_fns.get(&key) {
+            return table_index;
+        }
+
+        let ty_index = self.poll_fn_type();
+        let fn_index = self.next_defined_fn_index();
+        self.function_section.function(ty_index);
+        self.codegen_queue
+            .push_back(CodegenQueueItem::Poll(key.clone()));
+
+        // Slot 0 of the table is left empty, so a future whose poll index is 0 is complete.
+        self.table.push(fn_index.0);
+        let table_index = u32::try_from(self.table.len()).expect("too many poll functions");
+
+        self.poll_fns.insert(key, (fn_index, table_index));
+        table_index
+    }
+
+    /// Declares a function that calls the async fn `function` and awaits the result,
+    /// returning the function's index. It has the signature `function` would have if it were not async.
+    pub(crate) fn declare_block_on_fn(
+        &mut self,
+        function: SymFunction<'db>,
+        generics: Vec<SymGenericTerm<'db>>,
+    ) -> FnIndex {
+        let key = FnKey(function, generics);
+        if let Some(index) = self.block_on_fns.get(&key).copied() {
+            return index;
+        }
+
+        let ty_index = self.declare_fn_signature_type(function, &key.1, true);
+        let fn_index = self.next_defined_fn_index();
+        self.function_section.function(u32::from(ty_index));
+        self.codegen_queue
+            .push_back(CodegenQueueItem::BlockOn(key.clone()));
+        self.block_on_fns.insert(key, fn_index);
+        fn_index
+    }
+
+    /// The type of every poll function: `(stack_pointer: i32, future: i32, cancel: i32) -> (ready: i32)`.
+    pub(crate) fn poll_fn_type(&mut self) -> u32 {
+        if let Some(ty_index) = self.poll_fn_type {
+            return ty_index;
+        }
+
+        let ty_index = u32::from(self.declare_fn_type(
+            vec![ValType::I32, ValType::I32, ValType::I32],
+            vec![ValType::I32],
+        ));
+        self.poll_fn_type = Some(ty_index);
+        ty_index
+    }
+
+    /// Declares the type of the import for the async host function `function`:
+    /// `(stack_pointer: i32, polls: i32, inputs...) -> (ready: i32, output...)`,
+    /// where `polls` is the number of times the same future was polled before.
+    /// The output is only meaningful once `ready` is nonzero, at which point
+    /// the host has taken ownership of the inputs.
+    pub(crate) fn declare_host_poll_fn_type(&mut self, function: SymFunction<'db>) -> FnTypeIndex {
+        let CodegenSignature {
+            inputs: _,
+            ref generics,
+            input_output,
+        } = self.codegen_signature(function, &[]);
+        let output_ty = self.awaited_ty(input_output.output_ty);
+
+        let mut wrcx = WasmReprCx::new(self.db, generics);
+        let input_val_types = [ValType::I32, ValType::I32]
+            .into_iter()
+            .chain(
+                input_output
+                    .input_tys
+                    .iter()
+                    .flat_map(|&t| wrcx.wasm_repr_of_type(t).flatten()),
+            )
+            .collect::<Vec<_>>();
+        let output_val_types = std::iter::once(ValType::I32)
+            .chain(wrcx.wasm_repr_of_type(output_ty).flatten())
+            .collect::<Vec<_>>();
+        self.declare_fn_type(input_val_types, output_val_types)
+    }
+
+    /// The type of the value produced by awaiting a future of type `future_ty`.
+    pub(crate) fn awaited_ty(&self, future_ty: SymTy<'db>) -> SymTy<'db> {
+        let db = self.db;
+        match *future_ty.kind(db) {
+            SymTyKind::Named(SymTyName::Future, ref ty_args) => ty_args[0].assert_type(db),
+            SymTyKind::Perm(_, sym_ty) => self.awaited_ty(sym_ty),
+            SymTyKind::Error(_) => future_ty,
+            _ => panic!("expected a future type: {future_ty:?}"),
+        }
+    }
+
+    /// Generates the function that creates the future for an async fn.
+    pub(crate) fn codegen_async_fn_constructor(&mut self, key: FnKey<'db>) {
+        let CodegenSignature {
+            inputs,
+            generics,
+            input_output,
+        } = self.codegen_signature(key.0, &key.1);
+        let poll_index = self.declare_poll_fn(key);
+
+        let function = {
+            let mut ecx = ExprCodegen::new(self, generics);
+            ecx.push_future_constructor_body(
+                inputs,
+                &input_output.input_tys,
+                input_output.output_ty,
+                poll_index,
+            );
+            ecx.into_function()
+        };
+        self.code.push(function);
+    }
+
+    /// Generates the poll function for an async fn.
+    pub(crate) fn codegen_poll_fn(&mut self, key: FnKey<'db>) {
+        let body = if is_host_function(self.db, key.0) {
+            PollBody::Host(self.declare_host_import(key.0))
+        } else {
+            match key.0.checked_body(self.db) {
+                Some(body) => PollBody::Expr(body),
+                None => panic!("asked to codegen function with no body: {:?}", key.0),
+            }
+        };
+
+        let CodegenSignature {
+            inputs,
+            generics,
+            input_output,
+        } = self.codegen_signature(key.0, &key.1);
+
+        let function = {
+            let mut ecx = ExprCodegen::new(self, generics);
+            ecx.push_poll_body(
+                inputs,
+                &input_output.input_tys,
+                input_output.output_ty,
+                body,
+            );
+            ecx.into_function()
+        };
+        self.code.push(function);
+    }
+
+    /// Generates the block-on function for an async fn.
+    pub(crate) fn codegen_block_on_fn(&mut self, key: FnKey<'db>) {
+        let CodegenSignature {
+            inputs,
+            generics,
+            input_output,
+        } = self.codegen_signature(key.0, &key.1);
+        let FnIndex(async_fn) = self.declare_fn(key.0, key.1);
+
+        let function = {
+            let mut ecx = ExprCodegen::new(self, generics);
+            ecx.bind_parameters(inputs, &input_output.input_tys);
+            ecx.push_call(async_fn, inputs);
+            ecx.pop_and_await(input_output.output_ty);
+            ecx.into_function()
+        };
+        self.code.push(function);
+    }
+
+    /// Creates the table holding the poll functions and the element section that fills it in.
+    pub(crate) fn table_sections(&self) -> (TableSection, ElementSection) {
+        let mut table_section = TableSection::new();
+        table_section.table(TableType {
+            element_type: RefType::FUNCREF,
+            table64: false,
+            minimum: self.table.len() as u64 + 1,
+            maximum: None,
+            shared: false,
+        });
+
+        let mut element_section = ElementSection::new();
+        if !self.table.is_empty() {
+            element_section.active(
+                None,
+                &ConstExpr::i32_const(1),
+                Elements::Functions(Cow::Owned(
+                    self.table
+                        .iter()
+                        .map(|&index| self.final_fn_index(FnIndex(index)))
+                        .collect(),
+                )),
+            );
+        }
+
+        (table_section, element_section)
+    }
+}
+
+impl<'db> ExprCodegen<'_, 'db> {
+    /// Pushes the arguments `inputs` and calls `function` with them.
+    /// The callee takes ownership of the arguments, so their places are zeroed.
+    fn push_call(&mut self, function: u32, inputs: &[SymVariable<'db>]) {
+        self.push_pointer(self.next_stack_frame());
+        for &input in inputs {
+            let place = self.place_for_local(input);
+            self.push_from(&place);
+            self.store_zeroes(&place);
+        }
+        self.instructions.push(Instruction::Call(function));
+    }
+
+    /// Moves the inputs into an environment object and pushes a new future
+    /// of type `future_ty` whose poll function is at `poll_index` in the table.
+    fn push_future_constructor_body(
+        &mut self,
+        inputs: &[SymVariable<'db>],
+        input_tys: &[SymTy<'db>],
+        future_ty: SymTy<'db>,
+        poll_index: u32,
+    ) {
+        self.bind_parameters(inputs, input_tys);
+
+        // Move the arguments into the environment.
+        let env_reprs = self.env_reprs(input_tys);
+        let env = self.allocate_object(&env_reprs);
+        let (env_flags, env_fields) = self.object_places(&env, &env_reprs);
+        self.instructions.push(Instruction::I32Const(FLAG_MY));
+        self.pop_and_store(&WasmPlaceRepr::Heap(env_flags, ValType::I32));
+        for (&input, env_field) in inputs.iter().zip(&env_fields) {
+            let place = self.place_for_local(input);
+            self.push_from(&place);
+            self.pop_and_store(env_field);
+        }
+
+        // Create the future.
+        let WasmRepr::Class(future_reprs) = self.wasm_repr_of_type(future_ty) else {
+            panic!("expected a future type: {future_ty:?}")
+        };
+        let future = self.allocate_object(&future_reprs);
+        let (flags, fields) = self.object_places(&future, &future_reprs);
+        self.instructions.push(Instruction::I32Const(FLAG_MY));
+        self.pop_and_store(&WasmPlaceRepr::Heap(flags, ValType::I32));
+        self.instructions
+            .push(Instruction::I32Const(poll_index as i32));
+        self.pop_and_store(&fields[FUTURE_POLL]);
+        self.push_from(&env);
+        self.pop_and_store(&fields[FUTURE_ENV]);
+        self.store_zeroes(&fields[FUTURE_STATE]);
+        self.store_zeroes(&fields[FUTURE_RESULT]);
+
+        self.push_from(&future);
+    }
+
+    /// Generates the poll function for the future of type `future_ty`
+    /// (the function's second parameter, followed by the `cancel` flag).
+    fn push_poll_body(
+        &mut self,
+        inputs: &[SymVariable<'db>],
+        input_tys: &[SymTy<'db>],
+        future_ty: SymTy<'db>,
+        body: PollBody<'db>,
+    ) {
+        if let PollBody::Expr(expr) = body {
+            self.note_leased_variables(expr);
+        }
+        let future = self.emplace_local(&self.wasm_repr_of_type(future_ty));
+        let cancel = self.emplace_local(&WasmRepr::Val(ValType::I32));
+        self.wasm_num_params = self.wasm_locals.len();
+        let WasmPlaceRepr::Class(pointer, future_reprs) = &*future else {
+            panic!("expected a future type: {future_ty:?}")
+        };
+        let (_, fields) = self.object_places(pointer, future_reprs);
+
+        let state = self.emplace_local(&WasmRepr::Val(ValType::I32));
+        self.push_from(&fields[FUTURE_STATE]);
+        self.pop_and_store(&state);
+
+        match body {
+            PollBody::Expr(expr) => {
+                self.push_resumable_body(inputs, input_tys, fields, &state, &cancel, expr)
+            }
+            PollBody::Host(FnIndex(import)) => self.push_host_poll_body(
+                input_tys,
+                self.cx.awaited_ty(future_ty),
+                &fields,
+                &state,
+                &cancel,
+                import,
+            ),
+        }
+    }
+
+    /// Generates the poll function for an async fn whose body is `body`.
+    /// The future's fields are `fields`, and its state was loaded into `state`.
+    fn push_resumable_body(
+        &mut self,
+        inputs: &[SymVariable<'db>],
+        input_tys: &[SymTy<'db>],
+        fields: Vec<Arc<WasmPlaceRepr>>,
+        state: &WasmPlaceRepr,
+        cancel: &WasmPlaceRepr,
+        body: SymExpr<'db>,
+    ) {
+        let db = self.cx.db;
+
+        let mut await_ids = Map::default();
+        body.visit(db, &mut |expr| {
+            if let SymExprKind::Await { .. } = expr.kind(db) {
+                let id = await_ids.len() as u32 + 1;
+                await_ids.insert(expr, id);
+            }
+        });
+        let resuming = self.emplace_local(&WasmRepr::Val(ValType::I32));
+        let saved_frame = self.emplace_local(&WasmRepr::Val(ValType::I32));
+        self.poll = Some(PollState {
+            future_fields: fields.clone(),
+            resuming: resuming.clone(),
+            await_ids,
+            await_ranges: Default::default(),
+            suspend_depth: 0,
+            spilled: vec![],
+            suspended_owners: vec![],
+        });
+
+        // Everything from here on is saved when suspending.
+        let first_saved_local = self.wasm_locals.len();
+
+        // When starting, take the arguments out of the environment and free it.
+        // When resuming, restore what was saved instead (generated below and inserted here).
+        let env_reprs = self.env_reprs(input_tys);
+        self.push_from(state);
+        self.instructions.push(Instruction::I32Eqz);
+        self.instructions.push(Instruction::If(BlockType::Empty));
+        self.block_depth += 1;
+        self.in_conditional_block(|this| {
+            let (_, env_fields) = this.object_places(&fields[FUTURE_ENV], &env_reprs);
+            for ((&input, &input_ty), env_field) in inputs.iter().zip(input_tys).zip(&env_fields) {
+                this.insert_variable(input, input_ty);
+                this.push_from(env_field);
+                this.pop_and_store(&this.place_for_local(input));
+                this.scopes.push((input, input_ty));
+            }
+            this.free_object(&fields[FUTURE_ENV], &env_reprs);
+            this.store_zeroes(&fields[FUTURE_ENV]);
+        });
+        self.instructions.push(Instruction::Else);
+        let restore_at = self.instructions.len();
+        self.instructions.push(Instruction::End);
+        self.block_depth -= 1;
+        let inputs_in_scope = self.scopes.clone();
+
+        // Cancelling (generated below and inserted here) comes next, then the body itself,
+        // which branches out of this block to suspend.
+        let cancel_at = self.instructions.len();
+        self.instructions.push(Instruction::Block(BlockType::Empty));
+        self.block_depth += 1;
+        self.poll_state().suspend_depth = self.block_depth;
+        self.push_expr(body);
+        self.pop_and_return(body.ty(db));
+        self.instructions.push(Instruction::End);
+        self.block_depth -= 1;
+
+        // To suspend, save the locals and the stack frame into a new object,
+        // which takes the place of the environment, and return "pending".
+        let saved_locals: Vec<_> = (first_saved_local..self.wasm_locals.len())
+            .map(|index| {
+                let local = WasmLocal {
+                    index: index as u32,
+                };
+                WasmPlaceRepr::Local(local, self.wasm_locals[index])
+            })
+            .chain(self.stack_frame_words())
+            .collect();
+        let frame_reprs: Vec<_> = saved_locals
+            .iter()
+            .map(|place| match *place {
+                WasmPlaceRepr::Local(_, v) | WasmPlaceRepr::Heap(_, v) => WasmRepr::Val(v),
+                _ => panic!("unexpected place for saved state: {place:?}"),
+            })
+            .collect();
+        let frame = self.allocate_object(&frame_reprs);
+        let (flags, frame_fields) = self.object_places(&frame, &frame_reprs);
+        self.instructions.push(Instruction::I32Const(FLAG_MY));
+        self.pop_and_store(&WasmPlaceRepr::Heap(flags, ValType::I32));
+        for (saved, frame_field) in saved_locals.iter().zip(&frame_fields) {
+            self.push_from(saved);
+            self.pop_and_store(frame_field);
+        }
+        self.push_from(&frame);
+        self.pop_and_store(&fields[FUTURE_ENV]);
+        self.instructions.push(Instruction::I32Const(0));
+
+        // Generate the code to restore the saved state...
+        let generated = self.instructions.len();
+        self.push_from(&fields[FUTURE_ENV]);
+        self.pop_and_store(&saved_frame);
+        let (_, frame_fields) = self.object_places(&saved_frame, &frame_reprs);
+        for (saved, frame_field) in saved_locals.iter().zip(&frame_fields) {
+            self.push_from(frame_field);
+            self.pop_and_store(saved);
+        }
+        self.free_object(&saved_frame, &frame_reprs);
+        self.store_zeroes(&fields[FUTURE_ENV]);
+        self.push_from(state);
+        self.pop_and_store(&resuming);
+        let restore_code: Vec<_> = self.instructions.drain(generated..).collect();
+
+        // ...and to cancel, dropping what is owned at the `.await` where the body is suspended
+        // (or the inputs, if it has not started).
+        self.push_from(cancel);
+        let suspended_owners = std::mem::take(&mut self.poll_state().suspended_owners);
+        let inputs_owners: Vec<_> = inputs_in_scope
+            .into_iter()
+            .map(|(input, ty)| (self.place_for_local(input), ty))
+            .collect();
+        self.push_if(|this| {
+            for (id, owners) in suspended_owners.into_iter().chain([(0, inputs_owners)]) {
+                this.push_from(state);
+                this.instructions.push(Instruction::I32Const(id as i32));
+                this.instructions.push(Instruction::I32Eq);
+                this.push_if(|this| {
+                    for (place, ty) in owners {
+                        this.drop_place(&place, ty);
+                    }
+                });
+            }
+            this.push_ready(&fields);
+        });
+        let cancel_code: Vec<_> = self.instructions.drain(generated..).collect();
+
+        self.instructions.splice(cancel_at..cancel_at, cancel_code);
+        self.instructions
+            .splice(restore_at..restore_at, restore_code);
+    }
+
+    /// Generates the poll function for an async host function, which polls the import `import`.
+    /// The arguments stay in the environment until the host is ready.
+    fn push_host_poll_body(
+        &mut self,
+        input_tys: &[SymTy<'db>],
+        output_ty: SymTy<'db>,
+        fields: &[Arc<WasmPlaceRepr>],
+        state: &WasmPlaceRepr,
+        cancel: &WasmPlaceRepr,
+        import: u32,
+    ) {
+        let env_reprs = self.env_reprs(input_tys);
+
+        // Cancelling drops the arguments.
+        self.push_from(cancel);
+        self.push_if(|this| {
+            let (_, env_fields) = this.object_places(&fields[FUTURE_ENV], &env_reprs);
+            for (env_field, &input_ty) in env_fields.iter().zip(input_tys) {
+                this.drop_place(env_field, input_ty);
+            }
+            this.free_object(&fields[FUTURE_ENV], &env_reprs);
+            this.store_zeroes(&fields[FUTURE_ENV]);
+            this.push_ready(fields);
+        });
+
+        self.push_pointer(self.next_stack_frame());
+        self.push_from(state);
+        let (_, env_fields) = self.object_places(&fields[FUTURE_ENV], &env_reprs);
+        for env_field in &env_fields {
+            self.push_from(env_field);
+        }
+        self.instructions.push(Instruction::Call(import));
+        let result = self.emplace_local(&self.wasm_repr_of_type(output_ty));
+        self.pop_and_store(&result);
+
+        // If the host is not ready, count the poll and return "pending".
+        self.instructions.push(Instruction::I32Eqz);
+        self.push_if(|this| {
+            this.push_from(state);
+            this.instructions.push(Instruction::I32Const(1));
+            this.instructions.push(Instruction::I32Add);
+            this.pop_and_store(&fields[FUTURE_STATE]);
+            this.instructions.push(Instruction::I32Const(0));
+            this.instructions.push(Instruction::Return);
+        });
+
+        // Otherwise the host owns the arguments now.
+        self.free_object(&fields[FUTURE_ENV], &env_reprs);
+        self.store_zeroes(&fields[FUTURE_ENV]);
+        self.push_from(&result);
+        self.pop_and_store(&fields[FUTURE_RESULT]);
+        self.push_ready(fields);
+    }
+
+    /// Pops the result of the body of an async fn, stores it into the future,
+    /// and returns "ready" from the poll function.
+    pub(super) fn pop_and_complete(&mut self) {
+        let future_fields = self.poll_state().future_fields.clone();
+        self.pop_and_store(&future_fields[FUTURE_RESULT]);
+        self.push_ready(&future_fields);
+    }
+
+    /// Marks the future being polled, whose fields are `future_fields`, as complete
+    /// and returns "ready" from the poll function.
+    fn push_ready(&mut self, future_fields: &[Arc<WasmPlaceRepr>]) {
+        self.instructions.push(Instruction::I32Const(0));
+        self.pop_and_store(&future_fields[FUTURE_POLL]);
+        self.instructions.push(Instruction::I32Const(1));
+        self.instructions.push(Instruction::Return);
+    }
+
+    /// Pushes the result of the `.await` expression `await_expr`, whose operand is `future`.
+    ///
+    /// In the body of an async fn, the body suspends here until the future is ready.
+    /// Elsewhere (i.e., in a block-on function), see [`Self::pop_and_await`].
+    pub(super) fn push_await(&mut self, await_expr: SymExpr<'db>, future: SymExpr<'db>) {
+        let db = self.cx.db;
+        let future_ty = future.ty(db);
+        let Some(id) = self.poll.as_ref().map(|poll| poll.await_ids[&await_expr]) else {
+            self.push_expr(future);
+            self.pop_and_await(future_ty);
+            return;
+        };
+        let resuming = self.poll_state().resuming.clone();
+
+        // When resuming here, the future was already stored into its local.
+        let place = self.emplace_local(&self.wasm_repr_of_type(future_ty));
+        self.push_from(&resuming);
+        self.instructions.push(Instruction::I32Const(id as i32));
+        self.instructions.push(Instruction::I32Eq);
+        self.instructions.push(Instruction::If(BlockType::Empty));
+        self.block_depth += 1;
+        self.instructions.push(Instruction::I32Const(0));
+        self.pop_and_store(&resuming);
+        self.instructions.push(Instruction::Else);
+        self.in_conditional_block(|this| {
+            this.push_expr(future);
+            this.pop_and_store(&place);
+        });
+        self.instructions.push(Instruction::End);
+        self.block_depth -= 1;
+
+        let WasmPlaceRepr::Class(pointer, future_reprs) = &*place else {
+            // e.g., awaiting an expression with an error type
+            return;
+        };
+
+        // If the future is cancelled while suspended here, these are dropped.
+        let mut owners: Vec<_> = self
+            .scopes
+            .iter()
+            .map(|&(variable, ty)| (self.place_for_local(variable), ty))
+            .collect();
+        owners.extend(self.poll_state().spilled.iter().cloned());
+        owners.push((place.clone(), future_ty));
+        self.poll_state().suspended_owners.push((id, owners));
+
+        // Poll the future unless it is already complete, and suspend if it is pending.
+        let (_, fields) = self.object_places(pointer, future_reprs);
+        self.push_from(&fields[FUTURE_POLL]);
+        self.push_if(|this| {
+            this.push_poll(pointer, &fields, false);
+            this.instructions.push(Instruction::I32Eqz);
+            this.push_if(|this| {
+                let PollState {
+                    future_fields,
+                    suspend_depth,
+                    ..
+                } = this.poll_state();
+                let (state, suspend_depth) = (future_fields[FUTURE_STATE].clone(), *suspend_depth);
+                this.instructions.push(Instruction::I32Const(id as i32));
+                this.pop_and_store(&state);
+                this.instructions
+                    .push(Instruction::Br(this.block_depth - suspend_depth));
+            });
+        });
+
+        // Move the result out of the future and then drop what remains of it.
+        self.push_from(&fields[FUTURE_RESULT]);
+        self.store_zeroes(&fields[FUTURE_RESULT]);
+        self.drop_place(&place, future_ty);
+    }
+
+    /// Pops a future of type `future_ty`, polls it until it is ready, and pushes its result.
+    /// This is the executor: nothing else can run in the meantime, so it just polls again.
+    pub(super) fn pop_and_await(&mut self, future_ty: SymTy<'db>) {
+        let future = self.emplace_local(&self.wasm_repr_of_type(future_ty));
+        self.pop_and_store(&future);
+        let WasmPlaceRepr::Class(pointer, future_reprs) = &*future else {
+            // e.g., awaiting an expression with an error type
+            return;
+        };
+        let (_, fields) = self.object_places(pointer, future_reprs);
+
+        // Poll the future until it is complete.
+        self.instructions.push(Instruction::Loop(BlockType::Empty));
+        self.block_depth += 1;
+        self.push_from(&fields[FUTURE_POLL]);
+        self.push_if(|this| {
+            this.push_poll(pointer, &fields, false);
+            this.instructions.push(Instruction::I32Eqz);
+            this.instructions.push(Instruction::BrIf(1));
+        });
+        self.instructions.push(Instruction::End);
+        self.block_depth -= 1;
+
+        // Move the result out of the future and then drop what remains of it.
+        self.push_from(&fields[FUTURE_RESULT]);
+        self.store_zeroes(&fields[FUTURE_RESULT]);
+        self.drop_place(&future, future_ty);
+    }
+
+    /// Calls the poll function of the future whose pointer is in `pointer`
+    /// and whose fields are `fields`, pushing whether it is ready.
+    /// If `cancel` is true, the future drops what it owns instead of running.
+    pub(super) fn push_poll(
+        &mut self,
+        pointer: &WasmPlaceRepr,
+        fields: &[Arc<WasmPlaceRepr>],
+        cancel: bool,
+    ) {
+        let poll_fn_type = self.cx.poll_fn_type();
+        self.push_pointer(self.next_stack_frame());
+        self.push_from(pointer);
+        self.instructions.push(Instruction::I32Const(cancel as i32));
+        self.push_from(&fields[FUTURE_POLL]);
+        self.instructions.push(Instruction::CallIndirect {
+            type_index: poll_fn_type,
+            table_index: 0,
+        });
+    }
+
+    /// Generates `op`, which comes before `later` in evaluation order, so that it is skipped
+    /// when the body is resuming at an `.await` within `later`: it already ran before suspending.
+    pub(super) fn unless_resuming_into<R>(
+        &mut self,
+        later: &[SymExpr<'db>],
+        op: impl FnOnce(&mut Self) -> R,
+    ) -> R {
+        let Some(first_later_await) = self.first_await_within(later) else {
+            return op(self);
+        };
+
+        let resuming = self.poll_state().resuming.clone();
+        self.push_from(&resuming);
+        self.instructions
+            .push(Instruction::I32Const(first_later_await as i32));
+        self.instructions.push(Instruction::I32LtU);
+        self.push_if(op)
+    }
+
+    /// Pushes the values of `exprs` in order. If evaluating one of them can suspend,
+    /// the values of those before it are spilled into locals first (see [`Self::spill_exprs`]).
+    pub(super) fn push_exprs(&mut self, exprs: &[SymExpr<'db>]) {
+        let last_suspending = exprs
+            .iter()
+            .rposition(|&expr| self.await_ids_within(expr).is_some());
+        let spilled = match last_suspending {
+            Some(last) if last > 0 => self.spill_exprs(&exprs[..=last]),
+            _ => vec![],
+        };
+
+        for temp in &spilled {
+            self.push_from(temp);
+        }
+        for &expr in &exprs[spilled.len()..] {
+            self.push_expr(expr);
+        }
+    }
+
+    /// Evaluates `exprs` in order, storing each value into a fresh local, and returns those locals.
+    /// When resuming, the expressions that were evaluated before suspending are skipped,
+    /// and their locals hold their saved values.
+    pub(super) fn spill_exprs(&mut self, exprs: &[SymExpr<'db>]) -> Vec<Arc<WasmPlaceRepr>> {
+        let db = self.cx.db;
+        let mut temps = vec![];
+        for (index, &expr) in exprs.iter().enumerate() {
+            let ty = expr.ty(db);
+            let temp = self.emplace_local(&self.wasm_repr_of_type(ty));
+            self.unless_resuming_into(&exprs[index + 1..], |this| {
+                this.push_expr(expr);
+                this.pop_and_store(&temp);
+            });
+            self.poll_state().spilled.push((temp.clone(), ty));
+            temps.push(temp);
+        }
+
+        let spilled = &mut self.poll_state().spilled;
+        spilled.truncate(spilled.len() - temps.len());
+        temps
+    }
+
+    /// Pushes the condition of a match arm whose body is `body` and which is followed by the arms
+    /// with conditions and bodies `later`. When resuming at an `.await` within `body`,
+    /// the condition is skipped and taken to be true; within `later`, to be false.
+    pub(super) fn push_arm_condition(
+        &mut self,
+        condition: SymExpr<'db>,
+        body: SymExpr<'db>,
+        later: &[SymExpr<'db>],
+    ) {
+        let body_and_later: Vec<_> = std::iter::once(body).chain(later.iter().copied()).collect();
+        let Some(first_await) = self.first_await_within(&body_and_later) else {
+            self.push_expr(condition);
+            return;
+        };
+
+        let resuming = self.poll_state().resuming.clone();
+        self.push_from(&resuming);
+        self.instructions
+            .push(Instruction::I32Const(first_await as i32));
+        self.instructions.push(Instruction::I32GeU);
+        self.instructions
+            .push(Instruction::If(BlockType::Result(ValType::I32)));
+        self.block_depth += 1;
+        match self.await_ids_within(body) {
+            Some((_, last_in_body)) => {
+                self.push_from(&resuming);
+                self.instructions
+                    .push(Instruction::I32Const(last_in_body as i32));
+                self.instructions.push(Instruction::I32LeU);
+            }
+            None => self.instructions.push(Instruction::I32Const(0)),
+        }
+        self.instructions.push(Instruction::Else);
+        self.in_conditional_block(|this| this.push_expr(condition));
+        self.instructions.push(Instruction::End);
+        self.block_depth -= 1;
+    }
+
+    /// The first and last ids of the `.await` expressions within `expr`, if it is part of
+    /// the body of an async fn and contains any.
+    pub(super) fn await_ids_within(&mut self, expr: SymExpr<'db>) -> Option<(u32, u32)> {
+        let db = self.cx.db;
+        let PollState {
+            await_ids,
+            await_ranges,
+            ..
+        } = self.poll.as_mut()?;
+        if let Some(&range) = await_ranges.get(&expr) {
+            return range;
+        }
+
+        let mut range = None;
+        expr.visit(db, &mut |expr| {
+            if let Some(&id) = await_ids.get(&expr) {
+                range = Some(match range {
+                    None => (id, id),
+                    Some((first, last)) => (u32::min(first, id), u32::max(last, id)),
+                });
+            }
+        });
+        await_ranges.insert(expr, range);
+        range
+    }
+
+    /// The first id of the `.await` expressions within `exprs`, if any.
+    pub(super) fn first_await_within(&mut self, exprs: &[SymExpr<'db>]) -> Option<u32> {
+        exprs
+            .iter()
+            .filter_map(|&expr| self.await_ids_within(expr))
+            .map(|(first, _)| first)
+            .min()
+    }
+
+    fn poll_state(&mut self) -> &mut PollState<'db> {
+        self.poll.as_mut().expect("not in the body of an async fn")
+    }
+
+    /// The representations of the fields of the environment object holding arguments of types `input_tys`.
+    fn env_reprs(&self, input_tys: &[SymTy<'db>]) -> Vec<WasmRepr> {
+        input_tys
+            .iter()
+            .map(|&ty| self.wasm_repr_of_type(ty))
+            .collect()
+    }
+}
@@ -0,0 +1,254 @@
+//! Lowering of closures and calls through function values.
+//!
+//! A function value is a pair of the table index of a WASM function
+//! and a pointer to an "environment" object (see [`FUNCTION_TABLE_INDEX`] and [`FUNCTION_ENV`]).
+//! Creating a closure stores the captured values into a freshly allocated environment
+//! (see [`FUNCTION_ENV_SIZE`]), which is freed when a function value that owns it is dropped
+//! or, for a closure that captures variables (and so is only a reference), when those go out of scope;
+//! the closure body is compiled to its own function, which takes the environment pointer
+//! after the stack pointer and before the arguments and reads the captured values back out.
+//! Calling a function value calls that function through the table.
+
+use dada_ir_sym::ir::{
+    exprs::{SymCapture, SymExpr, SymExprKind, SymPlaceExpr},
+    types::{SymGenericTerm, SymTy, SymTyKind, SymTyName},
+    variables::SymVariable,
+};
+use dada_util::Map;
+use salsa::Update;
+use wasm_encoder::{Instruction, ValType};
+
+use super::{ExprCodegen, WasmPlaceRepr};
+use crate::cx::{
+    CodegenQueueItem, Cx, FnIndex,
+    wasm_fn_type::FnTypeIndex,
+    wasm_repr::{
+        FLAG_MY, FUNCTION_ENV, FUNCTION_ENV_SIZE, FUNCTION_TABLE_INDEX, WasmRepr, WasmReprCx,
+    },
+};
+
+/// A closure expression along with the values of the generic variables in scope where it appears.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Update)]
+pub(crate) struct ClosureKey<'db>(SymExpr<'db>, Vec<(SymVariable<'db>, SymGenericTerm<'db>)>);
+
+impl<'db> Cx<'db> {
+    /// Declares the function for the body of a closure and returns its index in the function table.
+    fn declare_closure_fn(&mut self, key: ClosureKey<'db>) -> u32 {
+        if let Some(&(_, table_index)) = self.closures.get(&key) {
+            return table_index;
+        }
+
+        let generics = key.1.iter().copied().collect();
+        let (input_tys, output_ty) = self.function_ty_inputs_output(key.0.ty(self.db));
+        let ty_index = self.declare_function_value_type(&generics, &input_tys, output_ty);
+        let fn_index = self.next_defined_fn_index();
+        self.function_section.function(u32::from(ty_index));
+        self.codegen_queue
+            .push_back(CodegenQueueItem::Closure(key.clone()));
+
+        // Slot 0 of the table is left empty (see `declare_poll_fn`).
+        self.table.push(fn_index.0);
+        let table_index = u32::try_from(self.table.len()).expect("too many closures");
+
+        self.closures.insert(key, (fn_index, table_index));
+        table_index
+    }
+
+    /// Declares the WASM type of the function called through a function value
+    /// with inputs `input_tys` and output `output_ty`:
+    /// `(stack_pointer: i32, env: i32, inputs...) -> output`.
+    fn declare_function_value_type(
+        &mut self,
+        generics: &Map<SymVariable<'db>, SymGenericTerm<'db>>,
+        input_tys: &[SymTy<'db>],
+        output_ty: SymTy<'db>,
+    ) -> FnTypeIndex {
+        let mut wrcx = WasmReprCx::new(self.db, generics);
+        let input_val_types = [ValType::I32, ValType::I32]
+            .into_iter()
+            .chain(
+                input_tys
+                    .iter()
+                    .flat_map(|&t| wrcx.wasm_repr_of_type(t).flatten()),
+            )
+            .collect::<Vec<_>>();
+        let output_val_types = wrcx.wasm_repr_of_type(output_ty).flatten();
+        self.declare_fn_type(input_val_types, output_val_types)
+    }
+
+    /// The input and output types of the function type `function_ty`.
+    fn function_ty_inputs_output(&self, function_ty: SymTy<'db>) -> (Vec<SymTy<'db>>, SymTy<'db>) {
+        let db = self.db;
+        match *function_ty.kind(db) {
+            SymTyKind::Named(SymTyName::Function { arity }, ref ty_args) => {
+                let tys = ty_args
+                    .iter()
+                    .map(|ty_arg| ty_arg.assert_type(db))
+                    .collect::<Vec<_>>();
+                (tys[..arity].to_vec(), tys[arity])
+            }
+            SymTyKind::Perm(_, sym_ty) => self.function_ty_inputs_output(sym_ty),
+            _ => panic!("expected a function type: {function_ty:?}"),
+        }
+    }
+
+    /// Generates the function for the body of a closure.
+    pub(crate) fn codegen_closure_fn(&mut self, ClosureKey(closure, generics): ClosureKey<'db>) {
+        let db = self.db;
+        let SymExprKind::Closure {
+            ref captures,
+            ref inputs,
+            ref input_tys,
+            body,
+        } = *closure.kind(db)
+        else {
+            panic!("expected a closure: {closure:?}")
+        };
+
+        let function = {
+            let mut ecx = ExprCodegen::new(self, generics.into_iter().collect());
+            ecx.push_closure_body(captures, inputs, input_tys, body);
+            ecx.into_function()
+        };
+        self.code.push(function);
+    }
+}
+
+impl<'db> ExprCodegen<'_, 'db> {
+    /// Stores the captured values into a new environment object
+    /// and pushes the function value for `closure`.
+    ///
+    /// A closure that captures variables cannot outlive them, so its environment
+    /// is freed when the first of them goes out of scope (see [`Self::drop_variable`]).
+    /// If that variable already has an environment to free from an earlier evaluation
+    /// of `closure` (e.g., in a loop), the earlier one is leaked, as copies of it may still be in use.
+    pub(super) fn push_closure(&mut self, closure: SymExpr<'db>, captures: &[SymCapture<'db>]) {
+        let env_reprs = self.env_reprs(captures);
+        let env = self.allocate_object(&env_reprs);
+        if let Some(owner) = self.closure_env_owner(captures) {
+            self.closure_envs
+                .get_mut(&owner)
+                .expect("owner is `let`-bound")
+                .push((env.clone(), env_reprs.clone()));
+        }
+        let (env_flags, env_fields) = self.object_places(&env, &env_reprs);
+        self.instructions.push(Instruction::I32Const(FLAG_MY));
+        self.pop_and_store(&WasmPlaceRepr::Heap(env_flags, ValType::I32));
+        let size = WasmRepr::object_size_in_bytes(&env_reprs);
+        self.instructions.push(Instruction::I32Const(size as i32));
+        self.pop_and_store(&env_fields[FUNCTION_ENV_SIZE]);
+        for (capture, env_field) in captures.iter().zip(&env_fields[FUNCTION_ENV_SIZE + 1..]) {
+            self.push_expr(capture.value);
+            self.pop_and_store(env_field);
+        }
+
+        let mut generics = self
+            .generics
+            .iter()
+            .map(|(&var, &term)| (var, term))
+            .collect::<Vec<_>>();
+        generics.sort();
+        let table_index = self.cx.declare_closure_fn(ClosureKey(closure, generics));
+
+        self.instructions
+            .push(Instruction::I32Const(table_index as i32));
+        self.push_from(&env);
+    }
+
+    /// The captured variable of `captures` that goes out of scope first, if any,
+    /// or `None` if one of them is not `let`-bound in the function being generated:
+    /// the inputs of a function are dropped when it returns, but a closure capturing them may be returned.
+    fn closure_env_owner(&self, captures: &[SymCapture<'db>]) -> Option<SymVariable<'db>> {
+        let db = self.cx.db;
+        let mut owner: Option<(usize, SymVariable<'db>)> = None;
+        for capture in captures {
+            let SymExprKind::PermissionOp(_, place) = *capture.value.kind(db) else {
+                return None;
+            };
+            let variable = place.root_variable(db)?;
+            if !self.closure_envs.contains_key(&variable) {
+                return None;
+            }
+            let depth = self.scopes.iter().rposition(|&(v, _)| v == variable)?;
+            if owner.is_none_or(|(owner_depth, _)| depth > owner_depth) {
+                owner = Some((depth, variable));
+            }
+        }
+        owner.map(|(_, variable)| variable)
+    }
+
+    /// Reads the captured values out of the environment (the function's parameter
+    /// after the stack pointer) and runs `body` with the given inputs.
+    fn push_closure_body(
+        &mut self,
+        captures: &[SymCapture<'db>],
+        inputs: &[SymVariable<'db>],
+        input_tys: &[SymTy<'db>],
+        body: SymExpr<'db>,
+    ) {
+        let db = self.cx.db;
+
+        self.note_leased_variables(body);
+        let env = self.emplace_local(&WasmRepr::Val(ValType::I32));
+        self.bind_arguments(inputs, input_tys);
+
+        // The environment is shared by every call of the closure, so it is left in place.
+        // Captured values are references, which are never dropped.
+        let env_reprs = self.env_reprs(captures);
+        let (_, env_fields) = self.object_places(&env, &env_reprs);
+        for (capture, env_field) in captures.iter().zip(&env_fields[FUNCTION_ENV_SIZE + 1..]) {
+            self.insert_variable(capture.lv, capture.value.ty(db));
+            self.push_from(env_field);
+            self.pop_and_store(&self.place_for_local(capture.lv));
+        }
+
+        self.push_expr(body);
+        self.pop_and_return(body.ty(db));
+    }
+
+    /// The representations of the fields of the environment object holding `captures`.
+    fn env_reprs(&self, captures: &[SymCapture<'db>]) -> Vec<WasmRepr> {
+        let db = self.cx.db;
+        std::iter::once(WasmRepr::Val(ValType::I32))
+            .chain(
+                captures
+                    .iter()
+                    .map(|capture| self.wasm_repr_of_type(capture.value.ty(db))),
+            )
+            .collect()
+    }
+
+    /// Calls the function value found in `callee` with the arguments stored in `arg_temps`.
+    /// Like a direct call, the callee takes ownership of the arguments, so their places are zeroed.
+    pub(super) fn push_call_indirect(
+        &mut self,
+        callee: SymPlaceExpr<'db>,
+        arg_temps: &[SymVariable<'db>],
+    ) {
+        let db = self.cx.db;
+        let callee_place = self.place(callee);
+        let WasmPlaceRepr::Struct(fields) = &*callee_place else {
+            // e.g., calling an expression with an error type
+            self.instructions.push(Instruction::Unreachable);
+            return;
+        };
+
+        let (input_tys, output_ty) = self.cx.function_ty_inputs_output(callee.ty(db));
+        let type_index = self
+            .cx
+            .declare_function_value_type(&self.generics, &input_tys, output_ty);
+
+        self.push_pointer(self.next_stack_frame());
+        self.push_from(&fields[FUNCTION_ENV]);
+        for arg_temp in arg_temps {
+            let place = self.place_for_local(*arg_temp);
+            self.push_from(&place);
+            self.store_zeroes(&place);
+        }
+        self.push_from(&fields[FUNCTION_TABLE_INDEX]);
+        self.instructions.push(Instruction::CallIndirect {
+            type_index: u32::from(type_index),
+            table_index: 0,
+        });
+    }
+}
@@ -0,0 +1,309 @@
+//! Drop glue: the code that runs when a value goes out of scope.
+//!
+//! Each type whose values own class objects gets a drop glue function
+//! that follows the Dada calling convention (a stack pointer followed by the value).
+//! Dropping a class object frees it if it is still uniquely owned
+//! (see [`WasmRepr::Class`]), after dropping its fields.
+//! The environment of a function value is freed the same way (see [`FUNCTION_ENV`]).
+//!
+//! # What leaks
+//!
+//! We do not count references to class objects, so some objects are never freed:
+//!
+//! * Objects that have been shared, along with everything they own. Shared copies are
+//!   not tracked, so no single copy can tell that it is the last one.
+//! * Values whose permission is `our` (e.g., a `my` value upcast to `our`), for the same reason.
+//! * Values whose permission is `A | B` where only one of `A` and `B` owns its data,
+//!   since we cannot tell at runtime which of the two applies.
+//! * Values of a field whose type has a `given[..]` permission, since the types of its
+//!   places are not known when generating the drop glue of the enclosing type.
+//! * A class object that a future was initializing the fields of when it was dropped
+//!   while suspended.
+//! * The environments of closures that capture an input of the enclosing function
+//!   (or a variable captured by an enclosing closure), since such a closure may be returned.
+//!   Closures that capture variables have a `ref` permission (see `check_closure`), so they are
+//!   copied freely and never dropped; instead, their environment is freed when the captured
+//!   variable that goes out of scope first does (see `ExprCodegen::push_closure`).
+//! * The environment of a closure created again before that variable goes out of scope
+//!   (e.g., in a loop), since earlier copies may still be in use, and the environments
+//!   of closures owned by a future that is dropped while suspended.
+
+use std::iter;
+use std::sync::Arc;
+
+use dada_ir_sym::{
+    ir::subst::Subst,
+    ir::types::{SymPerm, SymPermKind, SymTy, SymTyKind, SymTyName},
+    ir::variables::SymVariable,
+    well_known,
+};
+use dada_util::Map;
+use wasm_encoder::{Instruction, ValType};
+
+use super::{ExprCodegen, WasmPlaceRepr};
+use crate::cx::{
+    Cx, FnIndex,
+    memory::RuntimeFn,
+    wasm_repr::{FLAG_MY, FUNCTION_ENV, FUNCTION_ENV_SIZE, FUTURE_POLL, WasmReprCx},
+};
+
+impl<'db> Cx<'db> {
+    /// Declares the drop glue for values of type `ty` and returns its index,
+    /// or `None` if dropping such a value does nothing.
+    /// `ty` must not reference any generic variables.
+    pub(crate) fn declare_drop_glue(&mut self, ty: SymTy<'db>) -> Option<FnIndex> {
+        if !self.needs_drop(ty) {
+            return None;
+        }
+
+        if let Some(index) = self.drop_glues.get(&ty).copied() {
+            return Some(index);
+        }
+
+        let generics = Map::default();
+        let repr = WasmReprCx::new(self.db, &generics).wasm_repr_of_type(ty);
+        let inputs = iter::once(ValType::I32).chain(repr.flatten()).collect();
+        let ty_index = self.declare_fn_type(inputs, vec![]);
+
+        let fn_index = self.next_defined_fn_index();
+        self.function_section.function(u32::from(ty_index));
+        self.codegen_queue.push_back(ty.into());
+        self.drop_glues.insert(ty, fn_index);
+        Some(fn_index)
+    }
+
+    pub(crate) fn codegen_drop_glue(&mut self, ty: SymTy<'db>) {
+        let function = {
+            let mut ecx = ExprCodegen::new(self, Default::default());
+            ecx.push_drop_glue_body(ty);
+            ecx.into_function()
+        };
+        self.code.push(function);
+    }
+
+    /// True if a value of type `ty` owns class objects that must be freed when it is dropped.
+    fn needs_drop(&self, ty: SymTy<'db>) -> bool {
+        let db = self.db;
+        match *ty.kind(db) {
+            SymTyKind::Named(ty_name, ref ty_args) => match ty_name {
+                SymTyName::Primitive(_) => false,
+                SymTyName::Aggregate(aggr) if well_known::pointer_struct(db) == Ok(aggr) => false,
+                SymTyName::Aggregate(aggr) if aggr.is_class(db) => true,
+                SymTyName::Aggregate(aggr) => {
+                    let generics = Map::default();
+                    WasmReprCx::new(db, &generics)
+                        .aggr_field_tys(aggr, ty_args)
+                        .into_iter()
+                        .any(|field_ty| self.needs_drop(field_ty))
+                }
+                SymTyName::Future => true,
+                SymTyName::Function { arity: _ } => true,
+                SymTyName::Tuple { arity: _ } => ty_args
+                    .iter()
+                    .any(|ty_arg| self.needs_drop(ty_arg.assert_type(db))),
+            },
+            SymTyKind::Perm(sym_perm, sym_ty) => self.owns(sym_perm) && self.needs_drop(sym_ty),
+            SymTyKind::Var(_) | SymTyKind::Infer(_) => {
+                panic!("unexpected variable in drop glue type: {ty:?}")
+            }
+            SymTyKind::Never | SymTyKind::Error(_) => false,
+        }
+    }
+
+    /// True if a value with permission `sym_perm` owns its data.
+    fn owns(&self, sym_perm: SymPerm<'db>) -> bool {
+        let db = self.db;
+        match *sym_perm.kind(db) {
+            SymPermKind::My => true,
+            SymPermKind::Our | SymPermKind::Referenced(_) | SymPermKind::Mutable(_) => false,
+            SymPermKind::Apply(left, right) => self.owns(left) && self.owns(right),
+
+            // We cannot tell which side applies at runtime, so unless both sides own their data,
+            // leak rather than risk a double free.
+            SymPermKind::Or(left, right) => self.owns(left) && self.owns(right),
+
+            SymPermKind::Var(_) | SymPermKind::Infer(_) => {
+                panic!("unexpected variable in drop glue permission: {sym_perm:?}")
+            }
+            // Types are reduced before drop glue is generated for them, so this can only be
+            // a `given[..]` in a field type, whose places we do not know; leak.
+            SymPermKind::Given(_) => false,
+
+            SymPermKind::Error(_) => false,
+        }
+    }
+}
+
+impl<'db> ExprCodegen<'_, 'db> {
+    /// Drop the value found in `place`, which has type `ty`.
+    pub(super) fn drop_place(&mut self, place: &WasmPlaceRepr, ty: SymTy<'db>) {
+        let ty = WasmReprCx::new(self.cx.db, &self.generics)
+            .with_variable_tys(&self.variable_tys)
+            .reduce_given_perms(ty.subst_vars(self.cx.db, &self.generics));
+        let Some(FnIndex(drop_glue)) = self.cx.declare_drop_glue(ty) else {
+            return;
+        };
+
+        self.push_pointer(self.next_stack_frame());
+        self.push_from(place);
+        self.instructions.push(Instruction::Call(drop_glue));
+    }
+
+    /// Drop each of the variables in scope, innermost first.
+    pub(super) fn drop_scopes(&mut self) {
+        for (variable, ty) in self.scopes.clone().into_iter().rev() {
+            self.drop_variable(variable, ty);
+        }
+    }
+
+    /// Drop `variable`, which has type `ty`, as it goes out of scope.
+    /// The environments of the closures that capture it are freed first,
+    /// since those closures cannot be used once it is gone.
+    pub(super) fn drop_variable(&mut self, variable: SymVariable<'db>, ty: SymTy<'db>) {
+        let envs = self
+            .closure_envs
+            .get(&variable)
+            .cloned()
+            .unwrap_or_default();
+        for (env, env_reprs) in envs {
+            self.push_from(&env);
+            self.push_if(|this| {
+                this.free_object(&env, &env_reprs);
+                this.store_zeroes(&env);
+            });
+        }
+
+        let place = self.place_for_local(variable);
+        self.drop_place(&place, ty);
+    }
+
+    /// True if a value of type `ty` must be dropped.
+    pub(super) fn needs_drop(&self, ty: SymTy<'db>) -> bool {
+        let ty = ty.subst_vars(self.cx.db, &self.generics);
+        self.cx.needs_drop(ty)
+    }
+
+    /// Generates the body of the drop glue for `ty`, whose value is the function's parameter.
+    fn push_drop_glue_body(&mut self, ty: SymTy<'db>) {
+        let place = self.emplace_local(&self.wasm_repr_of_type(ty));
+        self.wasm_num_params = self.wasm_locals.len();
+        self.drop_contents(&place, ty);
+    }
+
+    /// Drop each of the values owned by the value of type `ty` found in `place`.
+    fn drop_contents(&mut self, place: &WasmPlaceRepr, ty: SymTy<'db>) {
+        let db = self.cx.db;
+        match *ty.kind(db) {
+            SymTyKind::Perm(_, sym_ty) => self.drop_contents(place, sym_ty),
+            SymTyKind::Named(SymTyName::Aggregate(aggr), ref ty_args) => {
+                let field_tys = WasmReprCx::new(db, &self.generics).aggr_field_tys(aggr, ty_args);
+                match place {
+                    WasmPlaceRepr::Struct(fields) => {
+                        for (field, &field_ty) in fields.iter().zip(&field_tys) {
+                            self.drop_place(field, field_ty);
+                        }
+                    }
+                    WasmPlaceRepr::Class(..) => self.drop_object(place, &field_tys, |_, _, _| ()),
+                    _ => panic!("unexpected place for {ty:?}: {place:?}"),
+                }
+            }
+            SymTyKind::Named(SymTyName::Future, ref ty_args) => {
+                // See `FUTURE_POLL` and friends. A future that has not completed
+                // is cancelled, which drops the arguments and whatever else it owns.
+                let u32 = SymTy::u32(db);
+                let field_tys = [u32, u32, u32, ty_args[0].assert_type(db)];
+                self.drop_object(place, &field_tys, |this, object, fields| {
+                    this.push_from(&fields[FUTURE_POLL]);
+                    this.push_if(|this| {
+                        this.push_poll(object, fields, true);
+                        this.instructions.push(Instruction::Drop);
+                    });
+                })
+            }
+            SymTyKind::Named(SymTyName::Tuple { arity: _ }, ref ty_args) => {
+                let WasmPlaceRepr::Struct(fields) = place else {
+                    panic!("unexpected place for {ty:?}: {place:?}")
+                };
+                for (field, ty_arg) in fields.iter().zip(ty_args) {
+                    self.drop_place(field, ty_arg.assert_type(db));
+                }
+            }
+            SymTyKind::Named(SymTyName::Function { arity: _ }, _) => {
+                let WasmPlaceRepr::Struct(fields) = place else {
+                    panic!("unexpected place for {ty:?}: {place:?}")
+                };
+
+                // Captured values are references, so there is nothing to drop but the
+                // environment itself, whose size is stored within it.
+                self.if_uniquely_owned(&fields[FUNCTION_ENV], |this, object, env_fields| {
+                    let free = this.cx.declare_runtime_fn(RuntimeFn::Free);
+                    this.push_from(object);
+                    this.push_from(&env_fields[FUNCTION_ENV_SIZE]);
+                    this.instructions.push(Instruction::Call(free.0));
+                })
+            }
+            SymTyKind::Named(SymTyName::Primitive(_), _)
+            | SymTyKind::Var(_)
+            | SymTyKind::Infer(_)
+            | SymTyKind::Never
+            | SymTyKind::Error(_) => (),
+        }
+    }
+
+    /// Drop the class object whose pointer is found in `place`, with fields of type `field_tys`.
+    /// The object is freed only if it is still uniquely owned, in which case `drop_owned`
+    /// is given the pointer and the places of the fields to drop anything else the object owns.
+    ///
+    /// This returns from the current function early, so it must come last in the drop glue.
+    fn drop_object(
+        &mut self,
+        place: &WasmPlaceRepr,
+        field_tys: &[SymTy<'db>],
+        drop_owned: impl FnOnce(&mut Self, &WasmPlaceRepr, &[Arc<WasmPlaceRepr>]),
+    ) {
+        let WasmPlaceRepr::Class(_, field_reprs) = place else {
+            panic!("expected a class object: {place:?}")
+        };
+        self.if_uniquely_owned(place, |this, object, field_places| {
+            drop_owned(this, object, field_places);
+            for (field_place, &field_ty) in field_places.iter().zip(field_tys) {
+                this.drop_place(field_place, field_ty);
+            }
+            this.free_object(object, field_reprs);
+        })
+    }
+
+    /// Run `op` with the pointer to the class object found in `place` and the places of its fields,
+    /// but only if the object is still uniquely owned.
+    ///
+    /// This returns from the current function early, so it must come last in the drop glue.
+    fn if_uniquely_owned(
+        &mut self,
+        place: &WasmPlaceRepr,
+        op: impl FnOnce(&mut Self, &WasmPlaceRepr, &[Arc<WasmPlaceRepr>]),
+    ) {
+        let WasmPlaceRepr::Class(object, field_reprs) = place else {
+            panic!("expected a class object: {place:?}")
+        };
+
+        // Nothing to do if the object was given away.
+        self.push_from(object);
+        self.instructions.push(Instruction::I32Eqz);
+        self.instructions
+            .push(Instruction::If(wasm_encoder::BlockType::Empty));
+        self.instructions.push(Instruction::Return);
+        self.instructions.push(Instruction::End);
+
+        // Nothing to do unless we are the unique owner.
+        let (flags, field_places) = self.object_places(object, field_reprs);
+        self.push_from(&WasmPlaceRepr::Heap(flags, ValType::I32));
+        self.instructions.push(Instruction::I32Const(FLAG_MY));
+        self.instructions.push(Instruction::I32Ne);
+        self.instructions
+            .push(Instruction::If(wasm_encoder::BlockType::Empty));
+        self.instructions.push(Instruction::Return);
+        self.instructions.push(Instruction::End);
+
+        op(self, object, &field_places);
+    }
+}
@@ -8,7 +8,10 @@ use dada_ir_sym::{
 };
 use wasm_encoder::{Instruction, ValType};
 
-use crate::cx::wasm_repr::WasmRepr;
+use crate::cx::{
+    memory::{ALIGN, RuntimeFn},
+    wasm_repr::{FLAG_OUR, FUTURE_RESULT, WasmRepr, WasmReprCx},
+};
 
 use super::ExprCodegen;
 
@@ -19,9 +22,19 @@ use super::ExprCodegen;
 pub enum WasmPlaceRepr {
     /// A primitive value stored in a WASM local variable.
     Local(WasmLocal, ValType),
+
+    /// A primitive value stored in WASM memory.
     Heap(WasmPointer, ValType),
+
+    /// A struct, whose fields are each stored in their own places.
     Struct(Vec<Arc<WasmPlaceRepr>>),
-    Class(WasmPointer, Vec<Arc<WasmPlaceRepr>>),
+
+    /// A class. The first place stores the pointer to the object, which always lives
+    /// in WASM memory and is laid out as described by [`WasmRepr::Class`]
+    /// (see [`ExprCodegen::object_places`]).
+    Class(Arc<WasmPlaceRepr>, Vec<WasmRepr>),
+
+    /// Zero-sized data.
     Nowhere,
 }
 
@@ -39,8 +52,13 @@ impl<'db> ExprCodegen<'_, 'db> {
     /// This can allocate more stack space in WASM memory.
     /// You can find this place by invoking [`Self::place_for_local`] later on.
     pub(super) fn insert_variable(&mut self, lv: SymVariable<'db>, ty: SymTy<'db>) {
+        self.variable_tys.insert(lv, ty);
         let ty_repr = self.wasm_repr_of_type(ty);
-        let emplaced_repr = self.emplace_local(&ty_repr);
+        let emplaced_repr = if self.leased_variables.contains(&lv) {
+            self.emplace_in_stack_frame(&ty_repr)
+        } else {
+            self.emplace_local(&ty_repr)
+        };
         self.variables.insert(lv, emplaced_repr);
     }
 
@@ -50,7 +68,9 @@ impl<'db> ExprCodegen<'_, 'db> {
     }
 
     /// The representation of the given Dada place.
-    pub(super) fn place(&self, place: SymPlaceExpr<'db>) -> Arc<WasmPlaceRepr> {
+    /// Locating a field of a class may load the object pointer into a fresh local,
+    /// so this can emit instructions.
+    pub(super) fn place(&mut self, place: SymPlaceExpr<'db>) -> Arc<WasmPlaceRepr> {
         let db = self.cx.db;
         match *place.kind(db) {
             SymPlaceExprKind::Var(v) => self.place_for_local(v),
@@ -59,6 +79,10 @@ impl<'db> ExprCodegen<'_, 'db> {
                 let owner_place = self.place(owner);
                 self.field_place(owner_place, owner.ty(db), field)
             }
+            SymPlaceExprKind::TupleElement(owner, index) => {
+                let owner_place = self.place(owner);
+                self.tuple_element_place(owner_place, owner.ty(db), index)
+            }
         }
     }
 
@@ -74,26 +98,25 @@ impl<'db> ExprCodegen<'_, 'db> {
             WasmPlaceRepr::Struct(ref fields) => {
                 fields.iter().for_each(|r| self.push_from(r));
             }
-            WasmPlaceRepr::Class(flags, ref fields) => {
-                self.push_from_memory(ValType::I32, flags);
-                fields.iter().for_each(|r| self.push_from(r));
-            }
+            WasmPlaceRepr::Class(ref object, _) => self.push_from(object),
             WasmPlaceRepr::Nowhere => (),
         }
     }
 
     /// Push a shared copy the value found in `place` onto the WASM stack.
+    /// Any class objects that it owns are marked as shared.
     pub(super) fn push_shared_from(&mut self, place: &WasmPlaceRepr) {
         match *place {
             WasmPlaceRepr::Struct(ref fields) => {
                 fields.iter().for_each(|r| self.push_shared_from(r));
             }
-            WasmPlaceRepr::Class(flags, ref fields) => {
-                // Push a 0 for the flags if shared
-                self.instructions.push(Instruction::I32Const(0));
-
-                self.push_from_memory(ValType::I32, flags);
-                fields.iter().for_each(|r| self.push_shared_from(r));
+            // Shared copies of a class refer to the same object,
+            // which can no longer be freed by its owner.
+            WasmPlaceRepr::Class(ref object, ref field_reprs) => {
+                let (flags, _) = self.object_places(object, field_reprs);
+                self.instructions.push(Instruction::I32Const(FLAG_OUR));
+                self.pop_to_memory(ValType::I32, flags);
+                self.push_from(object);
             }
             WasmPlaceRepr::Local(..) | WasmPlaceRepr::Heap(..) | WasmPlaceRepr::Nowhere => {
                 self.push_from(place);
@@ -101,14 +124,88 @@ impl<'db> ExprCodegen<'_, 'db> {
         }
     }
 
-    /// Push a shared copy the value found in `place` onto the WASM stack.
-    pub(super) fn push_leased_from(&mut self, place: &WasmPlaceRepr) {
+    /// Push a lease of the value of type `ty` found in `place` onto the WASM stack.
+    /// Leasing does not change who owns the object, so its flag word is left alone.
+    pub(super) fn push_leased_from(&mut self, place: Arc<WasmPlaceRepr>, ty: SymTy<'db>) {
+        // Leasing something that is itself leased yields the same pointer.
+        let (place, _) = self.deref_leases(place, ty);
+        match *place {
+            // A lease of a class is a pointer to the object.
+            WasmPlaceRepr::Class(ref object, _) => self.push_from(object),
+
+            // A lease of anything else is a pointer to where it is stored.
+            _ => self.push_address_of(&place),
+        }
+    }
+
+    /// Push the address of the memory that stores `place`.
+    /// Leased variables are stored in the stack frame (see [`Self::note_leased_variables`]),
+    /// so anything that is leased is in memory.
+    fn push_address_of(&mut self, place: &WasmPlaceRepr) {
         match *place {
-            WasmPlaceRepr::Class(flags, _) => {
-                self.push_pointer(flags);
+            WasmPlaceRepr::Heap(pointer, _) => self.push_pointer(pointer),
+            WasmPlaceRepr::Class(ref object, _) => self.push_address_of(object),
+
+            // The fields of a struct are stored one after the other,
+            // so the struct starts where its first non-empty field does.
+            WasmPlaceRepr::Struct(ref fields) => {
+                match fields
+                    .iter()
+                    .find(|field| !matches!(***field, WasmPlaceRepr::Nowhere))
+                {
+                    Some(field) => self.push_address_of(field),
+                    None => self.instructions.push(Instruction::I32Const(0)),
+                }
+            }
+
+            // Zero-sized values can be found anywhere.
+            WasmPlaceRepr::Nowhere => self.instructions.push(Instruction::I32Const(0)),
+
+            WasmPlaceRepr::Local(..) => panic!("leased place is not in memory: {place:?}"),
+        }
+    }
+
+    /// Strips the permissions off of `ty`, which is the type of the value stored in `place`,
+    /// following any lease pointers along the way. Returns the place storing the underlying value
+    /// and its type.
+    pub(super) fn deref_leases(
+        &mut self,
+        place: Arc<WasmPlaceRepr>,
+        ty: SymTy<'db>,
+    ) -> (Arc<WasmPlaceRepr>, SymTy<'db>) {
+        let db = self.cx.db;
+        match *ty.kind(db) {
+            SymTyKind::Var(sym_variable) => {
+                self.deref_leases(place, self.generics[&sym_variable].assert_type(db))
+            }
+            SymTyKind::Perm(sym_perm, sym_ty) => {
+                let place = if WasmReprCx::new(db, &self.generics)
+                    .with_variable_tys(&self.variable_tys)
+                    .is_lease_pointer(sym_perm, sym_ty)
+                {
+                    self.leased_place(&place, sym_ty)
+                } else {
+                    place
+                };
+                self.deref_leases(place, sym_ty)
             }
-            _ => panic!("can only lease classes"),
+            _ => (place, ty),
+        }
+    }
+
+    /// The place of the value of type `leased_ty` that is pointed to by the lease stored in `lease`.
+    fn leased_place(&mut self, lease: &WasmPlaceRepr, leased_ty: SymTy<'db>) -> Arc<WasmPlaceRepr> {
+        if let WasmPlaceRepr::Nowhere = lease {
+            // e.g., a lease with an error type
+            return Arc::new(WasmPlaceRepr::Nowhere);
         }
+        let base_variable = self.pointer_in_local(lease);
+        let mut offset = 0;
+        Self::emplace_in_object(
+            base_variable,
+            &mut offset,
+            &self.wasm_repr_of_type(leased_ty),
+        )
     }
 
     /// Given that a value of type `value_ty` is on the wasm stack, pop it and store it into `to_place`.
@@ -121,18 +218,51 @@ impl<'db> ExprCodegen<'_, 'db> {
             WasmPlaceRepr::Struct(ref fields) => {
                 fields.iter().rev().for_each(|r| self.pop_and_store(r));
             }
-            WasmPlaceRepr::Class(flags, ref fields) => {
-                fields.iter().rev().for_each(|r| self.pop_and_store(r));
-                self.pop_to_memory(ValType::I32, flags);
+            WasmPlaceRepr::Class(ref object, _) => self.pop_and_store(object),
+            WasmPlaceRepr::Nowhere => (),
+        }
+    }
+
+    /// Store zeroes into each of the values in `place`. For a class, this nulls out
+    /// the pointer, marking a value that was given away (or never initialized)
+    /// so that it is not dropped.
+    pub(super) fn store_zeroes(&mut self, place: &WasmPlaceRepr) {
+        match *place {
+            WasmPlaceRepr::Local(_, val_type) | WasmPlaceRepr::Heap(_, val_type) => {
+                self.push_zero(val_type);
+                self.pop_and_store(place);
+            }
+            WasmPlaceRepr::Struct(ref fields) => {
+                fields.iter().for_each(|r| self.store_zeroes(r));
             }
+            WasmPlaceRepr::Class(ref object, _) => self.store_zeroes(object),
             WasmPlaceRepr::Nowhere => (),
         }
     }
 
+    /// Push zeroes for each of the values needed to represent `repr`.
+    /// For a class, this is a null pointer.
+    pub(super) fn push_zeroes(&mut self, repr: &WasmRepr) {
+        for val_type in repr.flatten() {
+            self.push_zero(val_type);
+        }
+    }
+
+    /// Push a zero value of type `val_type`.
+    fn push_zero(&mut self, val_type: ValType) {
+        self.instructions.push(match val_type {
+            ValType::I32 => Instruction::I32Const(0),
+            ValType::I64 => Instruction::I64Const(0),
+            ValType::F32 => Instruction::F32Const(0.0),
+            ValType::F64 => Instruction::F64Const(0.0),
+            ValType::V128 | ValType::Ref(_) => panic!("unexpected val type {val_type:?}"),
+        });
+    }
+
     /// Representation for the place storing a given field found in
     /// an owner of type `owner_ty` that is stored in `owner_place`.
     fn field_place(
-        &self,
+        &mut self,
         owner_place_repr: Arc<WasmPlaceRepr>,
         owner_ty: SymTy<'db>,
         field: SymField<'db>,
@@ -151,20 +281,35 @@ impl<'db> ExprCodegen<'_, 'db> {
             },
             SymTyKind::Named(ty_name, _) => match *ty_name {
                 SymTyName::Future => match &*owner_place_repr {
-                    WasmPlaceRepr::Class(_, vec) => vec[0].clone(),
+                    WasmPlaceRepr::Class(object, field_reprs) => {
+                        let (_, fields) = self.object_places(object, field_reprs);
+                        fields[FUTURE_RESULT].clone()
+                    }
                     WasmPlaceRepr::Nowhere => owner_place_repr,
                     _ => panic!("unexpeced place for {owner_ty:?}: {owner_place_repr:?}"),
                 },
                 SymTyName::Primitive(_) => panic!("primitive types do not have fields"),
-                SymTyName::Tuple { arity: _ } => todo!(),
+                SymTyName::Tuple { arity: _ } => panic!("tuples do not have named fields"),
+                SymTyName::Function { arity: _ } => panic!("functions do not have fields"),
                 SymTyName::Aggregate(aggr) => {
                     // Where is the owner's data stored?
+                    // Enums store a tag followed by the fields of each variant
+                    // (see `WasmReprCx::aggr_field_tys`).
+                    let field_index = if aggr.is_enum(db) {
+                        1 + aggr
+                            .variants(db)
+                            .flat_map(|v| v.fields(db))
+                            .take_while(|f: &&SymField<'_>| **f != field)
+                            .count()
+                    } else {
+                        aggr.fields(db)
+                            .take_while(|f: &SymField<'_>| *f != field)
+                            .count()
+                    };
                     match &*owner_place_repr {
-                        WasmPlaceRepr::Struct(fields) | WasmPlaceRepr::Class(_, fields) => {
-                            let field_index = aggr
-                                .fields(db)
-                                .take_while(|f: &SymField<'_>| *f != field)
-                                .count();
+                        WasmPlaceRepr::Struct(fields) => fields[field_index].clone(),
+                        WasmPlaceRepr::Class(object, field_reprs) => {
+                            let (_, fields) = self.object_places(object, field_reprs);
                             fields[field_index].clone()
                         }
                         WasmPlaceRepr::Nowhere => owner_place_repr,
@@ -172,14 +317,53 @@ impl<'db> ExprCodegen<'_, 'db> {
                     }
                 }
             },
-            #[expect(unused_variables)]
-            SymTyKind::Perm(sym_perm, sym_ty) => todo!(),
+
+            // Classes are represented by a pointer to the object whatever the permission,
+            // and other values are either copies or, if leased, a pointer to the value.
+            SymTyKind::Perm(..) => {
+                let (owner_place_repr, owner_ty) = self.deref_leases(owner_place_repr, owner_ty);
+                self.field_place(owner_place_repr, owner_ty, field)
+            }
+        }
+    }
+
+    /// Representation for the place storing the `index`th element of a tuple
+    /// of type `owner_ty` that is stored in `owner_place`.
+    /// Tuples are laid out like structs, so this is the place of the `index`th field.
+    fn tuple_element_place(
+        &mut self,
+        owner_place_repr: Arc<WasmPlaceRepr>,
+        owner_ty: SymTy<'db>,
+        index: usize,
+    ) -> Arc<WasmPlaceRepr> {
+        let db = self.cx.db;
+        match owner_ty.kind(db) {
+            SymTyKind::Var(sym_variable) => self.tuple_element_place(
+                owner_place_repr,
+                self.generics[sym_variable].assert_type(db),
+                index,
+            ),
+            SymTyKind::Infer(_) => panic!("unresolved inference variable"),
+            SymTyKind::Named(SymTyName::Tuple { arity: _ }, _) => match &*owner_place_repr {
+                WasmPlaceRepr::Struct(fields) => fields[index].clone(),
+                WasmPlaceRepr::Nowhere => owner_place_repr,
+                _ => panic!("unexpeced place for {owner_ty:?}: {owner_place_repr:?}"),
+            },
+            SymTyKind::Named(..) => panic!("only tuples have indexed elements: {owner_ty:?}"),
+            SymTyKind::Never | SymTyKind::Error(_) => match &*owner_place_repr {
+                WasmPlaceRepr::Nowhere => owner_place_repr,
+                _ => panic!("unexpeced place for {owner_ty:?}: {owner_place_repr:?}"),
+            },
+            SymTyKind::Perm(..) => {
+                let (owner_place_repr, owner_ty) = self.deref_leases(owner_place_repr, owner_ty);
+                self.tuple_element_place(owner_place_repr, owner_ty, index)
+            }
         }
     }
 
     /// Returns the representation of a "local" storing a value of type `repr`.
     /// A "local" place is one that uses WASM local variables as much as possible.
-    fn emplace_local(&mut self, repr: &WasmRepr) -> Arc<WasmPlaceRepr> {
+    pub(super) fn emplace_local(&mut self, repr: &WasmRepr) -> Arc<WasmPlaceRepr> {
         match repr {
             WasmRepr::Val(val_type) => {
                 let local = self.fresh_local_index(*val_type);
@@ -188,28 +372,140 @@ impl<'db> ExprCodegen<'_, 'db> {
             WasmRepr::Struct(vec) => Arc::new(WasmPlaceRepr::Struct(
                 vec.iter().map(|r| self.emplace_local(r)).collect(),
             )),
-            WasmRepr::Class(_) => self.emplace_memory(repr),
+            WasmRepr::Class(field_reprs) => {
+                let local = self.fresh_local_index(ValType::I32);
+                Arc::new(WasmPlaceRepr::Class(
+                    Arc::new(WasmPlaceRepr::Local(local, ValType::I32)),
+                    field_reprs.clone(),
+                ))
+            }
             WasmRepr::Nothing => Arc::new(WasmPlaceRepr::Nowhere),
         }
     }
 
-    /// The representation for a Dada place found in WASM memory
-    /// that stores values with representation `repr`.
-    fn emplace_memory(&mut self, repr: &WasmRepr) -> Arc<WasmPlaceRepr> {
+    /// Returns the representation of a place in the stack frame storing a value of type `repr`.
+    /// Unlike locals, such places have an address, so they can be leased.
+    fn emplace_in_stack_frame(&mut self, repr: &WasmRepr) -> Arc<WasmPlaceRepr> {
+        let mut offset = self.wasm_stack_frame_size;
+        let place = Self::emplace_in_object(self.wasm_stack_pointer, &mut offset, repr);
+        self.wasm_stack_frame_size = offset.next_multiple_of(ALIGN);
+        place
+    }
+
+    /// The places of each 8-byte word of the stack frame allocated so far.
+    pub(super) fn stack_frame_words(&self) -> Vec<WasmPlaceRepr> {
+        (0..self.wasm_stack_frame_size)
+            .step_by(val_type_size_in_bytes(ValType::I64) as usize)
+            .map(|offset| {
+                let pointer = WasmPointer {
+                    base_variable: self.wasm_stack_pointer,
+                    offset,
+                };
+                WasmPlaceRepr::Heap(pointer, ValType::I64)
+            })
+            .collect()
+    }
+
+    /// Allocates a class object whose fields have the given representations
+    /// and returns a place (a fresh local) storing the pointer to it.
+    /// The contents of the object are uninitialized.
+    pub(super) fn allocate_object(&mut self, field_reprs: &[WasmRepr]) -> Arc<WasmPlaceRepr> {
+        let alloc = self.cx.declare_runtime_fn(RuntimeFn::Alloc);
+        let size = WasmRepr::object_size_in_bytes(field_reprs);
+        self.instructions.push(Instruction::I32Const(size as i32));
+        self.instructions.push(Instruction::Call(alloc.0));
+
+        let object = self.fresh_local_index(ValType::I32);
+        self.pop_to_local(ValType::I32, object);
+        Arc::new(WasmPlaceRepr::Local(object, ValType::I32))
+    }
+
+    /// Frees the class object whose pointer is stored in `object`,
+    /// given the representations of its fields. Its fields are not dropped.
+    pub(super) fn free_object(&mut self, object: &WasmPlaceRepr, field_reprs: &[WasmRepr]) {
+        let free = self.cx.declare_runtime_fn(RuntimeFn::Free);
+        let size = WasmRepr::object_size_in_bytes(field_reprs);
+        self.push_from(object);
+        self.instructions.push(Instruction::I32Const(size as i32));
+        self.instructions.push(Instruction::Call(free.0));
+    }
+
+    /// Returns the places of the flag word and of each field for the class object
+    /// whose pointer is stored in `object`. The object is laid out in memory as a flag word
+    /// followed by each of the `field_reprs`.
+    ///
+    /// If the object pointer is itself stored in memory, it is loaded into a fresh local first.
+    /// Traps if the pointer is null, i.e., if the object was given away.
+    /// The check is skipped if the same local was already checked (see `null_checked`).
+    pub(super) fn object_places(
+        &mut self,
+        object: &WasmPlaceRepr,
+        field_reprs: &[WasmRepr],
+    ) -> (WasmPointer, Vec<Arc<WasmPlaceRepr>>) {
+        let base_variable = self.pointer_in_local(object);
+
+        if self.null_checked.insert(base_variable.index) {
+            self.push_from_local(ValType::I32, base_variable);
+            self.instructions.push(Instruction::I32Eqz);
+            self.instructions
+                .push(Instruction::If(wasm_encoder::BlockType::Empty));
+            self.instructions.push(Instruction::Unreachable);
+            self.instructions.push(Instruction::End);
+        }
+
+        let flags = WasmPointer {
+            base_variable,
+            offset: 0,
+        };
+        let mut offset = val_type_size_in_bytes(ValType::I32);
+        let fields = field_reprs
+            .iter()
+            .map(|r| Self::emplace_in_object(base_variable, &mut offset, r))
+            .collect();
+        (flags, fields)
+    }
+
+    /// Returns a local storing the pointer found in `pointer`.
+    /// If the pointer is stored in memory, it is loaded into a fresh local first.
+    fn pointer_in_local(&mut self, pointer: &WasmPlaceRepr) -> WasmLocal {
+        match *pointer {
+            WasmPlaceRepr::Local(local, ValType::I32) => local,
+            WasmPlaceRepr::Heap(..) => {
+                let local = self.fresh_local_index(ValType::I32);
+                self.push_from(pointer);
+                self.pop_to_local(ValType::I32, local);
+                local
+            }
+            _ => panic!("unexpected place for pointer: {pointer:?}"),
+        }
+    }
+
+    /// The representation for a value with representation `repr` stored
+    /// at `*offset` bytes from the address in `base_variable`.
+    /// Increments `offset` past the value.
+    fn emplace_in_object(
+        base_variable: WasmLocal,
+        offset: &mut u32,
+        repr: &WasmRepr,
+    ) -> Arc<WasmPlaceRepr> {
         match repr {
             WasmRepr::Val(val_type) => {
-                let pointer = self.fresh_memory_slot(*val_type);
+                let pointer = WasmPointer {
+                    base_variable,
+                    offset: *offset,
+                };
+                *offset += val_type_size_in_bytes(*val_type);
                 Arc::new(WasmPlaceRepr::Heap(pointer, *val_type))
             }
             WasmRepr::Struct(vec) => Arc::new(WasmPlaceRepr::Struct(
-                vec.iter().map(|r| self.emplace_memory(r)).collect(),
+                vec.iter()
+                    .map(|r| Self::emplace_in_object(base_variable, offset, r))
+                    .collect(),
             )),
-            WasmRepr::Class(vec) => {
-                let flag_word = self.fresh_memory_slot(ValType::I32);
-                Arc::new(WasmPlaceRepr::Class(
-                    flag_word,
-                    vec.iter().map(|r| self.emplace_memory(r)).collect(),
-                ))
+            WasmRepr::Class(field_reprs) => {
+                let object =
+                    Self::emplace_in_object(base_variable, offset, &WasmRepr::Val(ValType::I32));
+                Arc::new(WasmPlaceRepr::Class(object, field_reprs.clone()))
             }
             WasmRepr::Nothing => Arc::new(WasmPlaceRepr::Nowhere),
         }
@@ -222,16 +518,6 @@ impl<'db> ExprCodegen<'_, 'db> {
         WasmLocal { index }
     }
 
-    /// Create a fresh slot in memory storing a value of type `v`.
-    fn fresh_memory_slot(&mut self, v: ValType) -> WasmPointer {
-        let offset = self.wasm_stack_frame_size;
-        self.wasm_stack_frame_size += val_type_size_in_bytes(v);
-        WasmPointer {
-            base_variable: self.wasm_stack_pointer,
-            offset,
-        }
-    }
-
     /// Push a value of type `val_type` found in `local`.
     fn push_from_local(&mut self, val_type: wasm_encoder::ValType, local: WasmLocal) {
         assert_eq!(self.wasm_locals[local.index as usize], val_type);
@@ -242,39 +528,18 @@ impl<'db> ExprCodegen<'_, 'db> {
     fn pop_to_local(&mut self, v: ValType, local: WasmLocal) {
         assert_eq!(self.wasm_locals[local.index as usize], v);
         self.instructions.push(Instruction::LocalSet(local.index));
+        self.null_checked.remove(&local.index);
     }
 
     /// Push a value of type `val_type` found in the given memory slot.
-    fn push_from_memory(
-        &mut self,
-        v: ValType,
-        WasmPointer {
-            base_variable,
-            offset,
-        }: WasmPointer,
-    ) {
-        let offset = offset as u64;
+    fn push_from_memory(&mut self, v: ValType, pointer: WasmPointer) {
+        self.push_from_local(ValType::I32, pointer.base_variable);
+        let memarg = mem_arg(v, pointer.offset);
         self.instructions.push(match v {
-            ValType::I32 => Instruction::I32Load(wasm_encoder::MemArg {
-                offset,
-                align: 4,
-                memory_index: base_variable.index,
-            }),
-            ValType::I64 => Instruction::I64Load(wasm_encoder::MemArg {
-                offset,
-                align: 4,
-                memory_index: base_variable.index,
-            }),
-            ValType::F32 => Instruction::F32Load(wasm_encoder::MemArg {
-                offset,
-                align: 4,
-                memory_index: base_variable.index,
-            }),
-            ValType::F64 => Instruction::F64Load(wasm_encoder::MemArg {
-                offset,
-                align: 4,
-                memory_index: base_variable.index,
-            }),
+            ValType::I32 => Instruction::I32Load(memarg),
+            ValType::I64 => Instruction::I64Load(memarg),
+            ValType::F32 => Instruction::F32Load(memarg),
+            ValType::F64 => Instruction::F64Load(memarg),
             ValType::V128 | ValType::Ref(_) => panic!("unexpected val type {v:?}"),
         });
     }
@@ -293,41 +558,35 @@ impl<'db> ExprCodegen<'_, 'db> {
     }
 
     /// Pop a value of type `val_type` and store it to the given memory slot.
-    fn pop_to_memory(
-        &mut self,
-        v: ValType,
-        WasmPointer {
-            base_variable,
-            offset,
-        }: WasmPointer,
-    ) {
-        let offset = offset as u64;
+    fn pop_to_memory(&mut self, v: ValType, pointer: WasmPointer) {
+        // WASM stores expect the address beneath the value,
+        // so stash the value in a temporary while we push the address.
+        let temporary = self.fresh_local_index(v);
+        self.pop_to_local(v, temporary);
+        self.push_from_local(ValType::I32, pointer.base_variable);
+        self.push_from_local(v, temporary);
+
+        let memarg = mem_arg(v, pointer.offset);
         self.instructions.push(match v {
-            ValType::I32 => Instruction::I32Store(wasm_encoder::MemArg {
-                offset,
-                align: 4,
-                memory_index: base_variable.index,
-            }),
-            ValType::I64 => Instruction::I64Store(wasm_encoder::MemArg {
-                offset,
-                align: 4,
-                memory_index: base_variable.index,
-            }),
-            ValType::F32 => Instruction::F32Store(wasm_encoder::MemArg {
-                offset,
-                align: 4,
-                memory_index: base_variable.index,
-            }),
-            ValType::F64 => Instruction::F64Store(wasm_encoder::MemArg {
-                offset,
-                align: 4,
-                memory_index: base_variable.index,
-            }),
+            ValType::I32 => Instruction::I32Store(memarg),
+            ValType::I64 => Instruction::I64Store(memarg),
+            ValType::F32 => Instruction::F32Store(memarg),
+            ValType::F64 => Instruction::F64Store(memarg),
             ValType::V128 | ValType::Ref(_) => panic!("unexpected val type {v:?}"),
         });
     }
 }
 
+/// The [`wasm_encoder::MemArg`] to access a value of type `v` at `offset` bytes from some address.
+fn mem_arg(v: ValType, offset: u32) -> wasm_encoder::MemArg {
+    wasm_encoder::MemArg {
+        offset: u64::from(offset),
+        // alignment is expressed as a power of two
+        align: val_type_size_in_bytes(v).trailing_zeros(),
+        memory_index: 0,
+    }
+}
+
 impl WasmRepr {
     /// Primitive WASM values needed for a value with this representation stored on the WASM stack or in memory.
     pub fn flatten(&self) -> Vec<ValType> {
@@ -335,26 +594,27 @@ impl WasmRepr {
             WasmRepr::Val(val_type) => vec![*val_type],
 
             // Structs are just each field one after the other.
-            WasmRepr::Struct(fields) => fields.iter().flat_map(|r| r.local_val_tys()).collect(),
+            WasmRepr::Struct(fields) => fields.iter().flat_map(|r| r.flatten()).collect(),
 
-            // Classes begin with an `I32` flag word.
-            WasmRepr::Class(fields) => std::iter::once(ValType::I32)
-                .chain(fields.iter().flat_map(|r| r.local_val_tys()))
-                .collect(),
+            // Classes are a pointer to the object.
+            WasmRepr::Class(_) => vec![ValType::I32],
 
             WasmRepr::Nothing => vec![],
         }
     }
 
-    /// Returns the types of the WASM local variables that would be used to store a value with this representation.
-    /// Any data found inside of a class is stored in memory and hence not represented in the return type.
-    pub fn local_val_tys(&self) -> Vec<ValType> {
-        match self {
-            WasmRepr::Val(val_type) => vec![*val_type],
-            WasmRepr::Struct(fields) => fields.iter().flat_map(|r| r.local_val_tys()).collect(),
-            WasmRepr::Class(_) => vec![],
-            WasmRepr::Nothing => vec![],
-        }
+    /// Number of bytes needed to store a value with this representation in memory.
+    /// For a class, this is the size of the pointer, not of the object
+    /// (see [`WasmRepr::object_size_in_bytes`]).
+    pub fn size_in_bytes(&self) -> u32 {
+        self.flatten().into_iter().map(val_type_size_in_bytes).sum()
+    }
+
+    /// Number of bytes needed to store a class object whose fields have the given representations:
+    /// an `I32` flag word followed by each of the fields.
+    pub fn object_size_in_bytes(field_reprs: &[WasmRepr]) -> u32 {
+        val_type_size_in_bytes(ValType::I32)
+            + field_reprs.iter().map(|r| r.size_in_bytes()).sum::<u32>()
     }
 }
 
@@ -1,16 +1,24 @@
 use dada_ir_ast::diagnostic::Err;
 use dada_ir_sym::{
     ir::{
+        classes::SymClassMember,
         functions::{SymFunction, SymInputOutput},
-        types::{SymGenericTerm, SymPlace, SymTy},
-        variables::SymVariable,
+        subst::Subst,
+        types::{SymGenericTerm, SymPerm, SymPlace, SymTy, SymTyKind, SymTyName},
+        variables::{FromVar, SymVariable},
     },
     prelude::{CheckedBody, CheckedSignature},
+    well_known::is_host_function,
 };
 use dada_util::Map;
-use wasm_encoder::ValType;
+use wasm_encoder::{EntityType, ValType};
 
-use super::{Cx, FnIndex, FnKey, generate_expr::ExprCodegen, wasm_repr::WasmReprCx};
+use crate::HOST_MODULE;
+
+use super::{
+    Cx, FnIndex, FnKey, generate_expr::ExprCodegen, wasm_fn_type::FnTypeIndex,
+    wasm_repr::WasmReprCx,
+};
 
 impl<'db> Cx<'db> {
     /// Declares an instantiation of a function with a given set of arguments and returns its index.
@@ -21,14 +29,167 @@ impl<'db> Cx<'db> {
         function: SymFunction<'db>,
         generics: Vec<SymGenericTerm<'db>>,
     ) -> FnIndex {
+        let (function, generics) = self.dispatch_trait_method(function, generics);
         let key = FnKey(function, generics);
-        let generics: &Vec<SymGenericTerm<'_>> = &key.1;
 
-        // Check if we already declared this function and return the result if so
+        // Check if we already declared this function and return the result if so.
         if let Some(index) = self.functions.get(&key).copied() {
             return index;
         }
 
+        // Synchronous host functions are called through their import.
+        if is_host_function(self.db, function) && !function.effects(self.db).async_effect {
+            return self.declare_host_import(function);
+        }
+
+        // Create the type for this function.
+        // For an async fn, this function creates the future.
+        let ty_index = self.declare_fn_signature_type(function, &key.1, false);
+
+        // Add to the WASM function section.
+        // Imported functions come first in the WASM function index space.
+        let fn_index = self.next_defined_fn_index();
+        self.function_section.function(u32::from(ty_index));
+
+        // Record on the queue to generate code
+        self.codegen_queue.push_back(key.clone().into());
+
+        // Memoize the result for later
+        self.functions.insert(key, fn_index);
+
+        fn_index
+    }
+
+    /// Trait methods have no body: calls to them are dispatched to the method of the same name
+    /// in the class given for `Self`, which is always the first generic argument.
+    /// Returns the function to call along with its generic arguments;
+    /// functions that are not trait methods are returned unchanged.
+    fn dispatch_trait_method(
+        &self,
+        function: SymFunction<'db>,
+        generics: Vec<SymGenericTerm<'db>>,
+    ) -> (SymFunction<'db>, Vec<SymGenericTerm<'db>>) {
+        let db = self.db;
+        let Some(sym_trait) = function.owner_trait(db) else {
+            return (function, generics);
+        };
+
+        // Peel the permissions off of `Self` to find the class.
+        let mut self_ty = generics[0].assert_type(db);
+        let mut self_perm = None;
+        while let SymTyKind::Perm(perm, ty) = *self_ty.kind(db) {
+            self_perm = Some(match self_perm {
+                None => perm,
+                Some(outer) => SymPerm::apply(db, outer, perm),
+            });
+            self_ty = ty;
+        }
+        let self_perm = self_perm.unwrap_or_else(|| SymPerm::my(db));
+        let SymTyKind::Named(SymTyName::Aggregate(aggr), ref aggr_args) = *self_ty.kind(db) else {
+            panic!(
+                "trait method `{}` invoked on `{self_ty}`, which is not a class",
+                function.name(db)
+            );
+        };
+        let Some(SymClassMember::SymFunction(method)) = aggr.inherent_member(db, function.name(db))
+        else {
+            panic!(
+                "class `{aggr}` has no method `{}` required by trait `{sym_trait}`",
+                function.name(db)
+            );
+        };
+        let Ok(signature) = method.checked_signature(db) else {
+            return (method, aggr_args.clone());
+        };
+        let input_output = signature.input_output(db);
+
+        // If the `self` parameter of the method has an implicit permission (`fn m(self)`),
+        // it takes the permission of the receiver.
+        // The remaining generic arguments of the trait method correspond one to one
+        // to the method's own generic parameters, as `check_trait_impl` verified.
+        let self_perm_var = method.implicit_self_perm_var(db);
+        let mut trait_method_args = generics[sym_trait.symbols(db).len()..].iter().copied();
+        let method_args = input_output.variables[aggr_args.len()..]
+            .iter()
+            .map(|&var| {
+                if Some(var) == self_perm_var {
+                    return self_perm.into();
+                }
+                let Some(arg) = trait_method_args.next() else {
+                    panic!(
+                        "no generic argument for `{var}` when dispatching `{}` to `{aggr}`",
+                        function.name(db)
+                    )
+                };
+                assert!(
+                    arg.has_kind(db, var.kind(db)),
+                    "generic argument `{arg:?}` for `{var}` has the wrong kind when dispatching `{}` to `{aggr}`",
+                    function.name(db)
+                );
+                arg
+            })
+            .collect::<Vec<_>>();
+        assert!(
+            trait_method_args.next().is_none(),
+            "too many generic arguments when dispatching `{}` to `{aggr}`",
+            function.name(db)
+        );
+
+        (
+            method,
+            aggr_args.iter().copied().chain(method_args).collect(),
+        )
+    }
+
+    /// Declares the host-provided function `function` from `libdada` as a WASM import
+    /// and returns its index. If it is already imported, nothing happens.
+    /// Only the host functions that the generated code calls are imported.
+    pub(crate) fn declare_host_import(&mut self, function: SymFunction<'db>) -> FnIndex {
+        let key = FnKey(function, vec![]);
+
+        // Callers of an async host function get a future like any other,
+        // so only the poll function calls the import directly.
+        let async_effect = function.effects(self.db).async_effect;
+        let imports = if async_effect {
+            &self.host_imports
+        } else {
+            &self.functions
+        };
+        if let Some(index) = imports.get(&key).copied() {
+            return index;
+        }
+
+        // Async host functions are polled like any other future.
+        let ty_index = if async_effect {
+            self.declare_host_poll_fn_type(function)
+        } else {
+            self.declare_fn_signature_type(function, &key.1, false)
+        };
+
+        let fn_index = FnIndex(FnIndex::FIRST_IMPORT + self.import_section.len());
+        self.import_section.import(
+            HOST_MODULE,
+            &key.mangled_name(self.db),
+            EntityType::Function(u32::from(ty_index)),
+        );
+
+        if async_effect {
+            self.host_imports.insert(key, fn_index);
+        } else {
+            self.functions.insert(key, fn_index);
+        }
+        fn_index
+    }
+
+    /// Declares the WASM function type for `function` instantiated with `generics`.
+    /// If `awaited` is true and `function` is async, the function returns
+    /// the value of the future rather than the future itself.
+    pub(crate) fn declare_fn_signature_type(
+        &mut self,
+        function: SymFunction<'db>,
+        generics: &[SymGenericTerm<'db>],
+        awaited: bool,
+    ) -> FnTypeIndex {
         // Extract function signature
         let CodegenSignature {
             inputs: _,
@@ -41,38 +202,34 @@ impl<'db> Cx<'db> {
                 },
         } = self.codegen_signature(function, generics);
 
-        // Create the type for this function
-        let ty_index = {
-            let mut wrcx = WasmReprCx::new(self.db, generics);
-            // The first input is the stack pointer.
-            // The remainder are the values given by the user.
-            let input_val_types = std::iter::once(ValType::I32)
-                .chain(
-                    input_tys
-                        .iter()
-                        .flat_map(|&t| wrcx.wasm_repr_of_type(t).flatten()),
-                )
-                .collect::<Vec<_>>();
-            let output_val_types = wrcx.wasm_repr_of_type(output_ty).flatten();
-            self.declare_fn_type(input_val_types, output_val_types)
+        let mut wrcx = WasmReprCx::new(self.db, generics);
+        // The first input is the stack pointer.
+        // The remainder are the values given by the user.
+        let input_val_types = std::iter::once(ValType::I32)
+            .chain(
+                input_tys
+                    .iter()
+                    .flat_map(|&t| wrcx.wasm_repr_of_type(t).flatten()),
+            )
+            .collect::<Vec<_>>();
+        let output_ty = if awaited && function.effects(self.db).async_effect {
+            self.awaited_ty(output_ty)
+        } else {
+            output_ty
         };
-
-        // Add to the WASM function section
-        let fn_index = FnIndex(self.function_section.len());
-        self.function_section.function(u32::from(ty_index));
-
-        // Record on the queue to generate code
-        self.codegen_queue.push(key.clone().into());
-
-        // Memoize the result for later
-        self.functions.insert(key, fn_index);
-
-        fn_index
+        let output_val_types = wrcx.wasm_repr_of_type(output_ty).flatten();
+        self.declare_fn_type(input_val_types, output_val_types)
     }
 
     pub(crate) fn codegen_fn(&mut self, FnKey(function, generics): FnKey<'db>) {
         let db = self.db;
 
+        // The body of an async fn runs in its poll function;
+        // calling it just creates the future.
+        if function.effects(db).async_effect {
+            return self.codegen_async_fn_constructor(FnKey(function, generics));
+        }
+
         let object_check_body = match function.checked_body(self.db) {
             Some(body) => body,
             None => panic!("asked to codegen function with no body: {function:?}"),
@@ -87,16 +244,17 @@ impl<'db> Cx<'db> {
         // Generate the function body.
         let function = {
             let mut ecx = ExprCodegen::new(self, generics);
-            ecx.pop_arguments(inputs, &input_output.input_tys);
+            ecx.note_leased_variables(object_check_body);
+            ecx.bind_arguments(inputs, &input_output.input_tys);
             ecx.push_expr(object_check_body);
             ecx.pop_and_return(object_check_body.ty(db));
             ecx.into_function()
         };
 
-        self.code_section.function(&function);
+        self.code.push(function);
     }
 
-    fn codegen_signature(
+    pub(crate) fn codegen_signature(
         &self,
         function: SymFunction<'db>,
         generics: &[SymGenericTerm<'db>],
@@ -108,12 +266,38 @@ impl<'db> Cx<'db> {
                 let input_output = signature
                     .input_output(self.db)
                     .substitute(self.db, generics);
-                let dummy_places = symbols
+                let input_places = symbols
                     .input_variables
                     .iter()
-                    .map(|_| SymGenericTerm::Place(SymPlace::erased(self.db)))
+                    .map(|&input| SymGenericTerm::var(self.db, input))
                     .collect::<Vec<_>>();
-                let input_output = input_output.substitute(self.db, &dummy_places);
+                let input_output = input_output.substitute(self.db, &input_places);
+
+                // `given[..]` permissions name the inputs, so reduce them while the
+                // types of the inputs are known. After that, the places are erased.
+                let input_tys = symbols
+                    .input_variables
+                    .iter()
+                    .copied()
+                    .zip(input_output.input_tys.iter().copied())
+                    .collect();
+                let no_generics = Map::default();
+                let wrcx = WasmReprCx::new(self.db, &no_generics).with_variable_tys(&input_tys);
+                let input_output = SymInputOutput {
+                    input_tys: input_output
+                        .input_tys
+                        .iter()
+                        .map(|&ty| wrcx.reduce_given_perms(ty))
+                        .collect(),
+                    output_ty: wrcx.reduce_given_perms(input_output.output_ty),
+                    where_clauses: input_output.where_clauses,
+                };
+                let erased_places = symbols
+                    .input_variables
+                    .iter()
+                    .map(|&input| (input, SymGenericTerm::Place(SymPlace::erased(self.db))))
+                    .collect();
+                let input_output = input_output.subst_vars(self.db, &erased_places);
 
                 CodegenSignature {
                     inputs: &symbols.input_variables,
@@ -140,8 +324,8 @@ impl<'db> Cx<'db> {
     }
 }
 
-struct CodegenSignature<'db> {
-    inputs: &'db [SymVariable<'db>],
-    generics: Map<SymVariable<'db>, SymGenericTerm<'db>>,
-    input_output: SymInputOutput<'db>,
+pub(crate) struct CodegenSignature<'db> {
+    pub(crate) inputs: &'db [SymVariable<'db>],
+    pub(crate) generics: Map<SymVariable<'db>, SymGenericTerm<'db>>,
+    pub(crate) input_output: SymInputOutput<'db>,
 }
@@ -0,0 +1,341 @@
+//! Layout of WASM linear memory and the runtime functions that manage it.
+//!
+//! The generated module defines (and exports as `memory`) a single linear memory,
+//! laid out as follows:
+//!
+//! * `[0, FREE_LIST)` is never used, so that no valid pointer is null.
+//! * `[FREE_LIST, DATA_START)` stores the head of the free list (see below).
+//! * Static data, such as the bytes of string literals, comes next.
+//! * The next [`STACK_SIZE`][] bytes hold stack frames. The stack grows upward
+//!   from the address stored in the `__stack_pointer` global, which is exported
+//!   so that the host knows what stack pointer to give to `main`.
+//!   Every function with a stack frame traps on entry if its frame would extend
+//!   past the end of the stack (see [`stack_check_instructions`]).
+//! * Everything after that is the heap. Class objects are allocated from the heap
+//!   by [`RuntimeFn::Alloc`][] and returned to it by [`RuntimeFn::Free`][].
+//!
+//! Every heap block is a multiple of [`ALIGN`] bytes and at least [`ALIGN`] bytes long.
+//! Freed blocks are kept on a singly linked free list. Each free block stores
+//! the address of the next free block followed by its own size.
+//! Allocation reuses the first free block of exactly the right size
+//! and otherwise bumps the heap pointer, growing the memory as needed.
+
+use dada_ir_sym::ir::exprs::SymByteLiteralData;
+use salsa::Update;
+use wasm_encoder::{
+    BlockType, ConstExpr, DataSection, ExportKind, Function, GlobalSection, GlobalType,
+    Instruction, MemArg, MemorySection, MemoryType, ValType,
+};
+
+use super::{Cx, FnIndex, FunctionCode};
+
+/// Size of a WASM page in bytes.
+const PAGE_SIZE: u32 = 1 << 16;
+
+/// Address storing the first block on the free list (or 0 if the list is empty).
+const FREE_LIST: u32 = 4;
+
+/// Start of static data.
+const DATA_START: u32 = 8;
+
+/// Number of bytes reserved for stack frames.
+const STACK_SIZE: u32 = PAGE_SIZE;
+
+/// Alignment of the stack and of heap allocations.
+/// Allocations smaller than this are rounded up to it, so a freed block can hold its next pointer and size.
+pub(crate) const ALIGN: u32 = 8;
+
+/// Index of the (immutable) global storing the start of the stack.
+const STACK_POINTER_GLOBAL: u32 = 0;
+
+/// Index of the (mutable) global storing the next free address in the heap.
+const HEAP_POINTER_GLOBAL: u32 = 1;
+
+/// Index of the (immutable) global storing the end of the stack.
+const STACK_LIMIT_GLOBAL: u32 = 2;
+
+/// Functions that are part of the runtime rather than generated from Dada code.
+/// Like any other function, they are declared on first use and generated from the codegen queue.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Update)]
+pub(crate) enum RuntimeFn {
+    /// `alloc(size: i32) -> i32` returns the address of `size` fresh bytes on the heap.
+    Alloc,
+
+    /// `free(address: i32, size: i32)` returns `size` bytes allocated at `address` to the heap.
+    Free,
+}
+
+impl RuntimeFn {
+    /// Name of the function in the `name` section.
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            RuntimeFn::Alloc => "$alloc",
+            RuntimeFn::Free => "$free",
+        }
+    }
+}
+
+impl<'db> Cx<'db> {
+    /// Declares the given runtime function and returns its index.
+    /// If the function is already declared, nothing happens.
+    pub(crate) fn declare_runtime_fn(&mut self, runtime_fn: RuntimeFn) -> FnIndex {
+        if let Some(index) = self.runtime_fns.get(&runtime_fn).copied() {
+            return index;
+        }
+
+        let ty_index = match runtime_fn {
+            RuntimeFn::Alloc => self.declare_fn_type(vec![ValType::I32], vec![ValType::I32]),
+            RuntimeFn::Free => self.declare_fn_type(vec![ValType::I32, ValType::I32], vec![]),
+        };
+
+        let fn_index = self.next_defined_fn_index();
+        self.function_section.function(u32::from(ty_index));
+        self.codegen_queue.push_back(runtime_fn.into());
+        self.runtime_fns.insert(runtime_fn, fn_index);
+        fn_index
+    }
+
+    pub(crate) fn codegen_runtime_fn(&mut self, runtime_fn: RuntimeFn) {
+        let function = match runtime_fn {
+            RuntimeFn::Alloc => alloc_fn(),
+            RuntimeFn::Free => free_fn(),
+        };
+        self.code.push(FunctionCode::Encoded(function));
+    }
+
+    /// Returns the address of the given byte literal, adding it to the static data if needed.
+    /// Identical byte literals share the same address.
+    pub(crate) fn declare_byte_literal(&mut self, data: SymByteLiteralData<'db>) -> u32 {
+        if let Some(address) = self.byte_literals.get(&data).copied() {
+            return address;
+        }
+
+        let address = DATA_START + u32::try_from(self.static_data.len()).expect("too much data");
+        self.static_data.extend(data.value(self.db).iter().copied());
+        self.byte_literals.insert(data, address);
+        address
+    }
+
+    /// Creates the memory, global, and data sections described in the [module docs](self)
+    /// and exports the memory and the stack pointer.
+    pub(crate) fn memory_sections(&mut self) -> (MemorySection, GlobalSection, DataSection) {
+        let data_end = DATA_START + u32::try_from(self.static_data.len()).expect("too much data");
+        let stack_start = align(data_end);
+        let heap_start = stack_start + STACK_SIZE;
+
+        let mut memory_section = MemorySection::new();
+        memory_section.memory(MemoryType {
+            minimum: u64::from(heap_start.div_ceil(PAGE_SIZE)),
+            maximum: None,
+            memory64: false,
+            shared: false,
+            page_size_log2: None,
+        });
+
+        let mut global_section = GlobalSection::new();
+        for (mutable, value) in [
+            (false, stack_start),
+            (true, heap_start),
+            (false, heap_start),
+        ] {
+            global_section.global(
+                GlobalType {
+                    val_type: ValType::I32,
+                    mutable,
+                    shared: false,
+                },
+                &ConstExpr::i32_const(value as i32),
+            );
+        }
+
+        let mut data_section = DataSection::new();
+        if !self.static_data.is_empty() {
+            data_section.active(
+                0,
+                &ConstExpr::i32_const(DATA_START as i32),
+                self.static_data.iter().copied(),
+            );
+        }
+
+        self.export_section
+            .export("memory", ExportKind::Memory, 0)
+            .export("__stack_pointer", ExportKind::Global, STACK_POINTER_GLOBAL);
+
+        (memory_section, global_section, data_section)
+    }
+}
+
+/// Rounds `address` up to a multiple of [`ALIGN`].
+fn align(address: u32) -> u32 {
+    address.next_multiple_of(ALIGN)
+}
+
+/// Pushes the size of the heap block holding `size` bytes, where `size` is an `i32` local:
+/// `size` rounded up to a multiple of [`ALIGN`], and at least [`ALIGN`].
+fn block_size_instructions(f: &mut Function, size: u32) {
+    // max(size, ALIGN)
+    f.instruction(&Instruction::LocalGet(size));
+    f.instruction(&Instruction::I32Const(ALIGN as i32));
+    f.instruction(&Instruction::LocalGet(size));
+    f.instruction(&Instruction::I32Const(ALIGN as i32));
+    f.instruction(&Instruction::I32GtU);
+    f.instruction(&Instruction::Select);
+
+    // rounded up to a multiple of ALIGN
+    f.instruction(&Instruction::I32Const((ALIGN - 1) as i32));
+    f.instruction(&Instruction::I32Add);
+    f.instruction(&Instruction::I32Const(-(ALIGN as i32)));
+    f.instruction(&Instruction::I32And);
+}
+
+/// Instructions that trap if a stack frame of `frame_size` bytes starting at
+/// `stack_pointer` (an `i32` local) would extend past the end of the stack.
+pub(crate) fn stack_check_instructions(
+    stack_pointer: u32,
+    frame_size: u32,
+) -> [Instruction<'static>; 8] {
+    [
+        Instruction::LocalGet(stack_pointer),
+        Instruction::I32Const(frame_size as i32),
+        Instruction::I32Add,
+        Instruction::GlobalGet(STACK_LIMIT_GLOBAL),
+        Instruction::I32GtU,
+        Instruction::If(BlockType::Empty),
+        Instruction::Unreachable,
+        Instruction::End,
+    ]
+}
+
+/// The allocator: returns the first block on the free list whose size is the block size
+/// for `size` (see [`block_size_instructions`]). If there is none, returns the current heap pointer and advances it,
+/// growing the memory if needed. Traps if the memory cannot grow.
+fn alloc_fn() -> Function {
+    const SIZE: u32 = 0;
+    const RESULT: u32 = 1;
+    const PREVIOUS: u32 = 2;
+    const BLOCK: u32 = 3;
+
+    // Pushes the current size of memory in bytes.
+    let memory_bytes = [
+        Instruction::MemorySize(0),
+        Instruction::I32Const(PAGE_SIZE.trailing_zeros() as i32),
+        Instruction::I32Shl,
+    ];
+
+    let mut f = Function::new([(3, ValType::I32)]);
+
+    // size = block_size(size)
+    block_size_instructions(&mut f, SIZE);
+    f.instruction(&Instruction::LocalSet(SIZE));
+
+    // Search the free list. `previous` is the address of the link to `block`.
+    f.instruction(&Instruction::I32Const(FREE_LIST as i32));
+    f.instruction(&Instruction::LocalSet(PREVIOUS));
+    f.instruction(&Instruction::Block(BlockType::Empty));
+    f.instruction(&Instruction::Loop(BlockType::Empty));
+
+    // block = *previous; if block is null, stop searching
+    f.instruction(&Instruction::LocalGet(PREVIOUS));
+    f.instruction(&Instruction::I32Load(mem_arg(0)));
+    f.instruction(&Instruction::LocalTee(BLOCK));
+    f.instruction(&Instruction::I32Eqz);
+    f.instruction(&Instruction::BrIf(1));
+
+    // if block.size == size, unlink the block and return it
+    f.instruction(&Instruction::LocalGet(BLOCK));
+    f.instruction(&Instruction::I32Load(mem_arg(4)));
+    f.instruction(&Instruction::LocalGet(SIZE));
+    f.instruction(&Instruction::I32Eq);
+    f.instruction(&Instruction::If(BlockType::Empty));
+    f.instruction(&Instruction::LocalGet(PREVIOUS));
+    f.instruction(&Instruction::LocalGet(BLOCK));
+    f.instruction(&Instruction::I32Load(mem_arg(0)));
+    f.instruction(&Instruction::I32Store(mem_arg(0)));
+    f.instruction(&Instruction::LocalGet(BLOCK));
+    f.instruction(&Instruction::Return);
+    f.instruction(&Instruction::End);
+
+    // otherwise continue with the next block
+    f.instruction(&Instruction::LocalGet(BLOCK));
+    f.instruction(&Instruction::LocalSet(PREVIOUS));
+    f.instruction(&Instruction::Br(0));
+    f.instruction(&Instruction::End);
+    f.instruction(&Instruction::End);
+
+    // result = heap_pointer
+    f.instruction(&Instruction::GlobalGet(HEAP_POINTER_GLOBAL));
+    f.instruction(&Instruction::LocalSet(RESULT));
+
+    // heap_pointer = heap_pointer + size
+    f.instruction(&Instruction::GlobalGet(HEAP_POINTER_GLOBAL));
+    f.instruction(&Instruction::LocalGet(SIZE));
+    f.instruction(&Instruction::I32Add);
+    f.instruction(&Instruction::GlobalSet(HEAP_POINTER_GLOBAL));
+
+    // if heap_pointer > memory size, grow by enough pages to cover the difference
+    f.instruction(&Instruction::GlobalGet(HEAP_POINTER_GLOBAL));
+    memory_bytes.iter().for_each(|i| {
+        f.instruction(i);
+    });
+    f.instruction(&Instruction::I32GtU);
+    f.instruction(&Instruction::If(BlockType::Empty));
+    f.instruction(&Instruction::GlobalGet(HEAP_POINTER_GLOBAL));
+    memory_bytes.iter().for_each(|i| {
+        f.instruction(i);
+    });
+    f.instruction(&Instruction::I32Sub);
+    f.instruction(&Instruction::I32Const((PAGE_SIZE - 1) as i32));
+    f.instruction(&Instruction::I32Add);
+    f.instruction(&Instruction::I32Const(PAGE_SIZE.trailing_zeros() as i32));
+    f.instruction(&Instruction::I32ShrU);
+    f.instruction(&Instruction::MemoryGrow(0));
+
+    // memory.grow returns -1 on failure
+    f.instruction(&Instruction::I32Const(-1));
+    f.instruction(&Instruction::I32Eq);
+    f.instruction(&Instruction::If(BlockType::Empty));
+    f.instruction(&Instruction::Unreachable);
+    f.instruction(&Instruction::End);
+    f.instruction(&Instruction::End);
+
+    f.instruction(&Instruction::LocalGet(RESULT));
+    f.instruction(&Instruction::End);
+    f
+}
+
+/// Pushes the block at `address` onto the front of the free list, recording its block size
+/// (computed from `size` as it was when allocated).
+fn free_fn() -> Function {
+    const ADDRESS: u32 = 0;
+    const SIZE: u32 = 1;
+
+    let mut f = Function::new([]);
+
+    // block.size = block_size(size)
+    f.instruction(&Instruction::LocalGet(ADDRESS));
+    block_size_instructions(&mut f, SIZE);
+    f.instruction(&Instruction::I32Store(mem_arg(4)));
+
+    // block.next = *FREE_LIST
+    f.instruction(&Instruction::LocalGet(ADDRESS));
+    f.instruction(&Instruction::I32Const(FREE_LIST as i32));
+    f.instruction(&Instruction::I32Load(mem_arg(0)));
+    f.instruction(&Instruction::I32Store(mem_arg(0)));
+
+    // *FREE_LIST = block
+    f.instruction(&Instruction::I32Const(FREE_LIST as i32));
+    f.instruction(&Instruction::LocalGet(ADDRESS));
+    f.instruction(&Instruction::I32Store(mem_arg(0)));
+
+    f.instruction(&Instruction::End);
+    f
+}
+
+/// The [`MemArg`] to access an `i32` at `offset` bytes from some address.
+fn mem_arg(offset: u64) -> MemArg {
+    MemArg {
+        offset,
+        align: 2,
+        memory_index: 0,
+    }
+}
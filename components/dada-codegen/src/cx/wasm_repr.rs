@@ -1,11 +1,15 @@
 use dada_ir_sym::{
     ir::{
-        classes::{SymAggregate, SymAggregateStyle},
+        classes::{SymAggregate, SymAggregateStyle, SymField},
         primitive::SymPrimitiveKind,
-        types::{SymGenericTerm, SymPerm, SymPermKind, SymPlace, SymTy, SymTyKind, SymTyName},
+        types::{
+            SymGenericTerm, SymPerm, SymPermKind, SymPlace, SymPlaceKind, SymTy, SymTyKind,
+            SymTyName,
+        },
         variables::SymVariable,
     },
     prelude::CheckedFieldTy,
+    well_known,
 };
 use dada_util::Map;
 use wasm_encoder::ValType;
@@ -15,12 +19,12 @@ use wasm_encoder::ValType;
 /// but rather enough information to determine how to represent
 /// an instance of this value in any of the places it could appear:
 ///
-/// * On the WebAssembly stack or memory, in which case all the
-///   [flattened values](`WasmRepr::flatten`) would be pushed/stored one after the other.
-/// * In WebAssembly local variables, in which case a [subset of the values](`WasmRepr::local_val_tys`)
-///   would be stored in subsequent variables. Note that the data for classes
-///   never appears in locals, and so a single value can be partially stored in locals and
-///   partially in memory.
+/// * On the WebAssembly stack, in WebAssembly local variables, or in memory,
+///   in which case all the [flattened values](`WasmRepr::flatten`) would be
+///   pushed/stored one after the other.
+/// * Class objects are different: they always live in WebAssembly memory
+///   and the value that gets pushed or stored is a pointer to the object.
+///   The object itself is laid out as described in [`WasmRepr::Class`].
 ///
 /// # See also
 ///
@@ -28,7 +32,7 @@ use wasm_encoder::ValType;
 /// particular Dada place (which in turn has an associated Dada type).
 ///
 /// [`WasmPlaceRepr`]: `crate::cx::generate_expr::wasm_place_repr::WasmPlaceRepr`
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub(crate) enum WasmRepr {
     /// Indicates a single primitive value. This may appear on the WASM stack,
     /// a local value, or the memory, depending on the context in which it appears.
@@ -38,25 +42,86 @@ pub(crate) enum WasmRepr {
     /// are found in the `Vec<WasmRepr>` argument.
     Struct(Vec<WasmRepr>),
 
-    /// A class. The data for classes is always stored in WASM memory.
-    /// It begins with an (implicit) I32 flag word and then contains
+    /// A class. The data for classes is always stored in WASM memory
+    /// (allocated from the heap) and the value itself is a pointer to that data.
+    /// The object begins with an (implicit) I32 flag word and then contains
     /// whatever values are needed to represent the fields, stored as a `Vec<WasmRepr>`.
+    ///
+    /// The flag word is [`FLAG_MY`] when the object is created and becomes [`FLAG_OUR`]
+    /// once it is shared. Giving a value away nulls out the pointer it was given from.
+    /// Leasing leaves the flag word alone: leased values are never dropped,
+    /// so only the owner of a [`FLAG_MY`] object frees it.
     Class(Vec<WasmRepr>),
 
     /// No data at all (something zero-sized).
     Nothing,
 }
 
+/// Flag word of a class object with a unique owner, which frees the object when it is dropped.
+pub(crate) const FLAG_MY: i32 = 1;
+
+/// Flag word of a class object that has been shared. Shared objects are never freed
+/// (see the drop glue module for what else leaks).
+pub(crate) const FLAG_OUR: i32 = 2;
+
+/// Index of the field of a future object storing the table index of its poll function.
+/// Once the future has completed, this is 0.
+pub(crate) const FUTURE_POLL: usize = 0;
+
+/// Index of the field of a future object storing a pointer to the object that holds
+/// the arguments to the async function, until they are taken by the poll function,
+/// and then, whenever the future is suspended, the state saved by the poll function.
+pub(crate) const FUTURE_ENV: usize = 1;
+
+/// Index of the field of a future object storing the state of its poll function:
+/// 0 before it is first polled and then the id of the `.await` it is suspended at
+/// (or, for host functions, the number of times it has been polled).
+pub(crate) const FUTURE_STATE: usize = 2;
+
+/// Index of the field of a future object storing its result once it has completed.
+pub(crate) const FUTURE_RESULT: usize = 3;
+
+/// Index of the value of a function value storing the table index of the function to call.
+pub(crate) const FUNCTION_TABLE_INDEX: usize = 0;
+
+/// Index of the value of a function value storing a pointer to the "environment" object
+/// that holds the captured values, which is passed to the function when it is called.
+/// Like a class object, the environment begins with a flag word, so that it is freed
+/// only by its unique owner.
+pub(crate) const FUNCTION_ENV: usize = 1;
+
+/// Index of the field of an environment object storing its size in bytes.
+/// The captured values follow. The type of a function value does not say what it captures,
+/// so this is how the drop glue knows how much to free.
+pub(crate) const FUNCTION_ENV_SIZE: usize = 0;
+
 type Generics<'db> = Map<SymVariable<'db>, SymGenericTerm<'db>>;
 
+type VariableTys<'db> = Map<SymVariable<'db>, SymTy<'db>>;
+
 pub(super) struct WasmReprCx<'g, 'db> {
     db: &'db dyn crate::Db,
     generics: &'g Generics<'db>,
+
+    /// Types of the variables that `given[..]` permissions may name, if known.
+    variable_tys: Option<&'g VariableTys<'db>>,
 }
 
 impl<'g, 'db> WasmReprCx<'g, 'db> {
     pub(super) fn new(db: &'db dyn crate::Db, generics: &'g Generics<'db>) -> Self {
-        Self { db, generics }
+        Self {
+            db,
+            generics,
+            variable_tys: None,
+        }
+    }
+
+    /// Uses `variable_tys` to reduce `given[..]` permissions (see [`Self::given_perm`]).
+    pub(super) fn with_variable_tys(self, variable_tys: &'g VariableTys<'db>) -> Self {
+        Self {
+            variable_tys: Some(variable_tys),
+            ..self
+        }
     }
 
     /// Returns the [`WasmRepr`][] that describes how `of_type` will be represented in WASM.
@@ -85,10 +150,24 @@ impl<'g, 'db> WasmReprCx<'g, 'db> {
     fn wasm_repr_of_perm_type(&mut self, sym_perm: SymPerm<'db>, sym_ty: SymTy<'db>) -> WasmRepr {
         let db = self.db;
         match *sym_perm.kind(db) {
-            SymPermKind::Mutable(_) => self.wasm_pointer(),
+            SymPermKind::Mutable(_) => match self.wasm_repr_of_type(sym_ty) {
+                // A lease of a class is a pointer to the leased object,
+                // which is exactly how the class itself is represented.
+                repr @ WasmRepr::Class(_) => repr,
+                _ => self.wasm_pointer(),
+            },
+
+            // Given and shared values are the value itself:
+            // for classes, a pointer to the (shared) object, and otherwise a copy.
             SymPermKind::My | SymPermKind::Our | SymPermKind::Referenced(_) => {
                 self.wasm_repr_of_type(sym_ty)
             }
+
+            SymPermKind::Given(ref places) => {
+                let perm = self.given_perm(places);
+                self.wasm_repr_of_perm_type(perm, sym_ty)
+            }
+
             SymPermKind::Var(sym_variable) => {
                 let result = self
                     .generics
@@ -107,6 +186,166 @@ impl<'g, 'db> WasmReprCx<'g, 'db> {
         }
     }
 
+    /// True if values of type `sym_perm sym_ty` are represented as a pointer to the leased
+    /// `sym_ty` value, i.e., `sym_perm` is a lease and `sym_ty` is not a class
+    /// (see [`Self::wasm_repr_of_perm_type`]).
+    pub(super) fn is_lease_pointer(&mut self, sym_perm: SymPerm<'db>, sym_ty: SymTy<'db>) -> bool {
+        self.is_lease(sym_perm) && !matches!(self.wasm_repr_of_type(sym_ty), WasmRepr::Class(_))
+    }
+
+    /// True if `sym_perm` is a lease, following the same rules as [`Self::wasm_repr_of_perm_type`].
+    fn is_lease(&self, sym_perm: SymPerm<'db>) -> bool {
+        let db = self.db;
+        match *sym_perm.kind(db) {
+            SymPermKind::Mutable(_) => true,
+            SymPermKind::Var(sym_variable) => self.is_lease(
+                self.generics
+                    .get(&sym_variable)
+                    .expect("expected value for each generic type")
+                    .assert_perm(db),
+            ),
+            SymPermKind::Apply(left, _) | SymPermKind::Or(left, _) => self.is_lease(left),
+            SymPermKind::Given(ref places) => self.is_lease(self.given_perm(places)),
+            SymPermKind::My
+            | SymPermKind::Our
+            | SymPermKind::Referenced(_)
+            | SymPermKind::Error(_) => false,
+            SymPermKind::Infer(_) => unreachable!(),
+        }
+    }
+
+    /// The permission that `given[places]` stands for: as in the type checker,
+    /// the union of the permissions of the places' types.
+    /// A place whose type is not known here (e.g., an erased place in a field type)
+    /// is assumed to be owned.
+    pub(super) fn given_perm(&self, places: &[SymPlace<'db>]) -> SymPerm<'db> {
+        let db = self.db;
+        places
+            .iter()
+            .map(|&place| match self.place_ty(place) {
+                Some(ty) => self.perm_of_type(ty),
+                None => SymPerm::my(db),
+            })
+            .reduce(|perm1, perm2| SymPerm::or(db, perm1, perm2))
+            .unwrap_or_else(|| SymPerm::my(db))
+    }
+
+    /// Replaces each `given[..]` permission in `ty` with the permission it stands for.
+    /// Drop glue is generated for a type alone, so types are reduced before it sees them.
+    pub(super) fn reduce_given_perms(&self, ty: SymTy<'db>) -> SymTy<'db> {
+        let db = self.db;
+        match *ty.kind(db) {
+            SymTyKind::Named(ty_name, ref ty_args) => SymTy::named(
+                db,
+                ty_name,
+                ty_args
+                    .iter()
+                    .map(|&ty_arg| match ty_arg {
+                        SymGenericTerm::Type(ty) => self.reduce_given_perms(ty).into(),
+                        SymGenericTerm::Perm(perm) => self.reduce_given_perm(perm).into(),
+                        SymGenericTerm::Place(_) | SymGenericTerm::Error(_) => ty_arg,
+                    })
+                    .collect(),
+            ),
+            SymTyKind::Perm(sym_perm, sym_ty) => SymTy::perm(
+                db,
+                self.reduce_given_perm(sym_perm),
+                self.reduce_given_perms(sym_ty),
+            ),
+            SymTyKind::Var(_) | SymTyKind::Infer(_) | SymTyKind::Never | SymTyKind::Error(_) => ty,
+        }
+    }
+
+    fn reduce_given_perm(&self, sym_perm: SymPerm<'db>) -> SymPerm<'db> {
+        let db = self.db;
+        match *sym_perm.kind(db) {
+            SymPermKind::Given(ref places) => self.reduce_given_perm(self.given_perm(places)),
+            SymPermKind::Apply(left, right) => SymPerm::apply(
+                db,
+                self.reduce_given_perm(left),
+                self.reduce_given_perm(right),
+            ),
+            SymPermKind::Or(left, right) => SymPerm::or(
+                db,
+                self.reduce_given_perm(left),
+                self.reduce_given_perm(right),
+            ),
+            SymPermKind::My
+            | SymPermKind::Our
+            | SymPermKind::Referenced(_)
+            | SymPermKind::Mutable(_)
+            | SymPermKind::Var(_)
+            | SymPermKind::Infer(_)
+            | SymPermKind::Error(_) => sym_perm,
+        }
+    }
+
+    /// The type of `place`, if the type of the variable it starts from is known.
+    fn place_ty(&self, place: SymPlace<'db>) -> Option<SymTy<'db>> {
+        let db = self.db;
+        match *place.kind(db) {
+            SymPlaceKind::Var(sym_variable) => self.variable_tys?.get(&sym_variable).copied(),
+            SymPlaceKind::Field(owner_place, sym_field) => {
+                let owner_ty = self.place_ty(owner_place)?;
+                let SymTyKind::Named(SymTyName::Aggregate(_), ty_args) =
+                    self.named_type(owner_ty).kind(db)
+                else {
+                    return None;
+                };
+                let field_ty = sym_field
+                    .checked_field_ty(db)
+                    .substitute(db, ty_args)
+                    .substitute(db, &[SymGenericTerm::Place(owner_place)]);
+                Some(self.perm_of_type(owner_ty).apply_to(db, field_ty))
+            }
+            SymPlaceKind::TupleElement(owner_place, index) => {
+                let owner_ty = self.place_ty(owner_place)?;
+                let SymTyKind::Named(SymTyName::Tuple { .. }, ty_args) =
+                    self.named_type(owner_ty).kind(db)
+                else {
+                    return None;
+                };
+                let element_ty = ty_args.get(index)?.assert_type(db);
+                Some(self.perm_of_type(owner_ty).apply_to(db, element_ty))
+            }
+            SymPlaceKind::Index(_) | SymPlaceKind::Error(_) | SymPlaceKind::Erased => None,
+        }
+    }
+
+    /// The permission of a value of type `ty`, e.g., `mut[p]` for `mut[p] String`.
+    fn perm_of_type(&self, ty: SymTy<'db>) -> SymPerm<'db> {
+        let db = self.db;
+        match *ty.kind(db) {
+            SymTyKind::Perm(sym_perm, sym_ty) => sym_perm.apply_to(db, self.perm_of_type(sym_ty)),
+            SymTyKind::Var(sym_variable) => self.perm_of_type(
+                self.generics
+                    .get(&sym_variable)
+                    .expect("expected value for each generic type")
+                    .assert_type(db),
+            ),
+            SymTyKind::Named(..) | SymTyKind::Infer(_) | SymTyKind::Never | SymTyKind::Error(_) => {
+                SymPerm::my(db)
+            }
+        }
+    }
+
+    /// `ty` without its permissions and with generic variables replaced by their values.
+    fn named_type(&self, ty: SymTy<'db>) -> SymTy<'db> {
+        let db = self.db;
+        match *ty.kind(db) {
+            SymTyKind::Perm(_, sym_ty) => self.named_type(sym_ty),
+            SymTyKind::Var(sym_variable) => self.named_type(
+                self.generics
+                    .get(&sym_variable)
+                    .expect("expected value for each generic type")
+                    .assert_type(db),
+            ),
+            SymTyKind::Named(..) | SymTyKind::Infer(_) | SymTyKind::Never | SymTyKind::Error(_) => {
+                ty
+            }
+        }
+    }
+
     /// Returns the [`WasmRepr`][] for a Dada named type.
     fn wasm_repr_of_named_type(
         &mut self,
@@ -118,9 +357,14 @@ impl<'g, 'db> WasmReprCx<'g, 'db> {
             SymTyName::Primitive(sym_primitive) => {
                 WasmRepr::Val(self.wasm_valtype_for_primitive_kind(sym_primitive.kind(db)))
             }
+            // The prelude's `Pointer[T]` is a raw address in WASM memory.
+            SymTyName::Aggregate(aggr) if well_known::pointer_struct(db) == Ok(aggr) => {
+                self.wasm_pointer()
+            }
             SymTyName::Aggregate(aggr) => match aggr.style(db) {
-                // structs  have the fields inlined
-                SymAggregateStyle::Struct => {
+                // structs  have the fields inlined;
+                // enums are inlined too, as a tag followed by the fields of every variant
+                SymAggregateStyle::Struct | SymAggregateStyle::Enum => {
                     WasmRepr::Struct(self.wasm_repr_of_aggr_fields(aggr, ty_args))
                 }
 
@@ -128,10 +372,24 @@ impl<'g, 'db> WasmReprCx<'g, 'db> {
                     WasmRepr::Class(self.wasm_repr_of_aggr_fields(aggr, ty_args))
                 }
             },
+            // futures are objects whose fields are described by `FUTURE_POLL` and friends
             SymTyName::Future => {
                 assert_eq!(ty_args.len(), 1);
                 let ty_arg = ty_args[0].assert_type(db);
-                WasmRepr::Class(vec![self.wasm_repr_of_type(ty_arg)])
+                WasmRepr::Class(vec![
+                    WasmRepr::Val(ValType::I32),
+                    self.wasm_pointer(),
+                    WasmRepr::Val(ValType::I32),
+                    self.wasm_repr_of_type(ty_arg),
+                ])
+            }
+            // function values are pairs described by `FUNCTION_TABLE_INDEX` and `FUNCTION_ENV`
+            SymTyName::Function { arity } => {
+                assert_eq!(ty_args.len(), arity + 1);
+                WasmRepr::Struct(vec![
+                    WasmRepr::Val(ValType::I32),
+                    WasmRepr::Class(vec![WasmRepr::Val(ValType::I32)]),
+                ])
             }
             SymTyName::Tuple { arity } => {
                 assert_eq!(ty_args.len(), arity);
@@ -179,18 +437,28 @@ impl<'g, 'db> WasmReprCx<'g, 'db> {
     }
 
     /// The types of each field of some aggregate type given the values `ty_args` for its generic arguments.
-    fn aggr_field_tys<'a>(
+    ///
+    /// For an enum, these are a `u32` tag (the [index](`dada_ir_sym::ir::classes::SymVariant::index`) of the active variant)
+    /// followed by the fields of each variant in turn. The fields of the inactive variants are zeroed.
+    pub(super) fn aggr_field_tys<'a>(
         &self,
         aggr: SymAggregate<'db>,
         ty_args: &'a Vec<SymGenericTerm<'db>>,
     ) -> Vec<SymTy<'db>> {
         let db = self.db;
-        aggr.fields(db)
-            .map(|f| f.checked_field_ty(db))
-            .map(|ty| {
-                let ty = ty.substitute(db, ty_args);
+        let fields: Vec<SymField<'db>> = if aggr.is_enum(db) {
+            aggr.variants(db)
+                .flat_map(|v| v.fields(db).iter().copied())
+                .collect()
+        } else {
+            aggr.fields(db).collect()
+        };
+        let tag = aggr.is_enum(db).then(|| SymTy::u32(db));
+        tag.into_iter()
+            .chain(fields.into_iter().map(|f| {
+                let ty = f.checked_field_ty(db).substitute(db, ty_args);
                 ty.substitute(db, &[SymGenericTerm::Place(SymPlace::erased(db))])
-            })
+            }))
             .collect()
     }
 
@@ -7,6 +7,11 @@ use dada_ir_sym::{
 
 mod cx;
 
+/// Name of the WASM module from which host-provided functions are imported.
+/// Body-less functions in `libdada` (e.g., `print`) are imported from here
+/// under their mangled names.
+pub const HOST_MODULE: &str = "dada";
+
 #[salsa::tracked(return_ref)]
 pub fn codegen_main_fn<'db>(db: &'db dyn Db, source_file: SourceFile) -> Option<Vec<u8>> {
     let main = Identifier::main(db);
@@ -23,7 +28,7 @@ pub fn codegen_main_fn<'db>(db: &'db dyn Db, source_file: SourceFile) -> Option<
         return None;
     }
 
-    Some(codegen(db, main_fn, vec![]).clone())
+    Some(cx::Cx::generate_from_module(db, module, main_fn).finish())
 }
 
 /// Generate a self-contained wasm module from a starting function.
@@ -33,7 +38,5 @@ pub fn codegen<'db>(
     function: SymFunction<'db>,
     generics: Vec<SymGenericTerm<'db>>,
 ) -> Vec<u8> {
-    cx::Cx::new(db)
-        .generate_from_fn(function, generics)
-        .finish()
+    cx::Cx::generate_from_fn(db, function, generics).finish()
 }
@@ -8,7 +8,7 @@ use dada_ir_ast::{
     ast::{AstFunction, AstItem, AstMember, Identifier},
     diagnostic::Diagnostic,
     inputs::{CompilationRoot, Krate, SourceFile},
-    span::AbsoluteSpan,
+    span::{AbsoluteOffset, AbsoluteSpan},
 };
 use dada_util::{Fallible, FromImpls, Map, Set, bail, debug};
 use salsa::{Database as _, Durability, Event, EventKind, Setter};
@@ -174,6 +174,251 @@ impl Compiler {
         self.attach(|db| dada_probe::probe_expression_type(db, span))
     }
 
+    /// Return the location (`file:line:column`) where the name found at the given `span` was defined
+    /// or `None` if there is no name there or it has no definition.
+    pub fn probe_definition(&self, span: AbsoluteSpan) -> Option<String> {
+        self.attach(|db| {
+            let definition = dada_probe::probe_definition(db, span)?;
+            Some(self.location_display(definition))
+        })
+    }
+
+    /// Return the locations (`file:line:column`, separated by commas) of all references
+    /// within the crate to the name found at the given `span`, including its definition.
+    pub fn probe_references(&self, span: AbsoluteSpan) -> String {
+        let source_files = self.crate_source_files(span.source_file);
+        self.attach(|db| {
+            dada_probe::probe_references(db, span, &source_files)
+                .into_iter()
+                .map(|reference| self.location_display(reference))
+                .collect::<Vec<_>>()
+                .join(", ")
+        })
+    }
+
+    /// Return the declaration whose name is at the given `span` as it would appear in an outline
+    /// of the file, like `struct Point { field x, method size }`, or `None` if no name is declared there.
+    pub fn probe_declaration(&self, span: AbsoluteSpan) -> Option<String> {
+        fn find(
+            declarations: Vec<dada_probe::Declaration>,
+            span: AbsoluteSpan,
+        ) -> Option<dada_probe::Declaration> {
+            declarations.into_iter().find_map(|declaration| {
+                if declaration.name_span.contains(span) {
+                    Some(declaration)
+                } else {
+                    find(declaration.members, span)
+                }
+            })
+        }
+
+        fn display(declaration: &dada_probe::Declaration) -> String {
+            let kind = match declaration.kind {
+                dada_probe::DeclarationKind::Class => "class",
+                dada_probe::DeclarationKind::Struct => "struct",
+                dada_probe::DeclarationKind::Enum => "enum",
+                dada_probe::DeclarationKind::Trait => "trait",
+                dada_probe::DeclarationKind::Function => "fn",
+                dada_probe::DeclarationKind::Field => "field",
+                dada_probe::DeclarationKind::Method => "method",
+                dada_probe::DeclarationKind::Variant => "variant",
+            };
+            if declaration.members.is_empty() {
+                return format!("{kind} {}", declaration.name);
+            }
+
+            let members = declaration
+                .members
+                .iter()
+                .map(display)
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("{kind} {} {{ {members} }}", declaration.name)
+        }
+
+        self.attach(|db| {
+            let declarations = dada_probe::probe_declarations(db, span.source_file);
+            Some(display(&find(declarations, span)?))
+        })
+    }
+
+    /// Return the spans to replace with `new_name` in order to rename the name found at the given `span`
+    /// throughout its crate, or an error if it cannot be renamed.
+    pub fn rename(&self, span: AbsoluteSpan, new_name: &str) -> Fallible<Vec<AbsoluteSpan>> {
+        if !new_name.starts_with(|ch: char| ch.is_alphabetic() || ch == '_')
+            || !new_name.chars().all(|ch| ch.is_alphanumeric() || ch == '_')
+        {
+            bail!("`{new_name}` is not a valid identifier");
+        }
+
+        let source_files = self.crate_source_files(span.source_file);
+        self.attach(|db| dada_probe::probe_rename(db, span, &source_files))
+    }
+
+    /// Return the locations (`file:line:column`, separated by commas) that renaming the name
+    /// found at the given `span` to `new_name` would replace, or the error explaining why it cannot be renamed.
+    pub fn probe_rename(&self, span: AbsoluteSpan, new_name: &str) -> String {
+        match self.rename(span, new_name) {
+            Ok(spans) => spans
+                .into_iter()
+                .map(|span| self.location_display(span))
+                .collect::<Vec<_>>()
+                .join(", "),
+            Err(error) => error.to_string(),
+        }
+    }
+
+    /// Return the names that could be typed at the given `span`, sorted by label:
+    /// the fields and methods of the receiver after a `.`, and otherwise the names in scope.
+    /// Takes `&mut self` since the receiver is checked in a scratch copy of the file
+    /// where the partial member name (which does not type check) has been removed.
+    pub fn probe_completions(&mut self, span: AbsoluteSpan) -> Vec<dada_probe::Completion> {
+        let text = span.source_file.contents_if_ok(self);
+        let mut completions = match dada_probe::strip_member_access(text, span.start.as_usize()) {
+            Some((contents, receiver_end)) => {
+                let scratch_file = self.scratch_source_file(span.source_file, contents);
+                let receiver_end = AbsoluteOffset::from(receiver_end);
+                let receiver_span = AbsoluteSpan {
+                    source_file: scratch_file,
+                    start: receiver_end,
+                    end: receiver_end,
+                };
+                self.attach(|db| dada_probe::probe_member_completions(db, receiver_span))
+            }
+            None => self.attach(|db| dada_probe::probe_name_completions(db, span)),
+        };
+        completions.sort_by(|a, b| a.label.cmp(&b.label));
+        completions
+    }
+
+    /// Returns a scratch copy of `source_file` with the given contents,
+    /// used to check edits to the file without disturbing the file itself.
+    /// The same copy is reused for each call.
+    fn scratch_source_file(&mut self, source_file: SourceFile, contents: String) -> SourceFile {
+        let scratch_file = self
+            .inputs
+            .lock()
+            .unwrap()
+            .scratch_source_files
+            .get(&source_file)
+            .copied();
+        if let Some(scratch_file) = scratch_file {
+            let _ = scratch_file.set_contents(self).to(Ok(contents));
+            return scratch_file;
+        }
+
+        let url = source_file.url(self).clone();
+        let scratch_file = SourceFile::new(self, url, Ok(contents));
+        self.inputs
+            .lock()
+            .unwrap()
+            .scratch_source_files
+            .insert(source_file, scratch_file);
+        scratch_file
+    }
+
+    fn location_display(&self, span: AbsoluteSpan) -> String {
+        let (line, column) = span.source_file.line_col(self, span.start);
+        format!(
+            "{}:{}:{}",
+            span.source_file.url_display(self),
+            line.as_u32() + 1,
+            column.as_u32() + 1,
+        )
+    }
+
+    /// Returns the source files of every module of the crate that `source_file` belongs to
+    /// (loading them if needed, like [`Self::workspace_source_files`]), along with any other
+    /// source files of that crate loaded so far, ordered by URL. This includes `source_file` itself.
+    pub fn crate_source_files(&self, source_file: SourceFile) -> Vec<SourceFile> {
+        let url = source_file.url(self);
+
+        // A crate rooted at `a/b.dada` has its submodules in `a/b/...`.
+        let in_krate = |directory: &Url, url: &Url| {
+            UrlPath::from(directory.clone()).dada_url() == *url
+                || url.as_str().starts_with(&format!("{directory}/"))
+        };
+        let krate_directory = self.inputs.lock().unwrap().directories.iter().find_map(
+            |(&krate, source)| match source {
+                KrateSource::Url(directory) if in_krate(directory, url) => {
+                    Some((krate, directory.clone()))
+                }
+                _ => None,
+            },
+        );
+        let Some((krate, directory)) = krate_directory else {
+            return vec![source_file];
+        };
+
+        let mut source_files = vec![dada_ir_ast::Db::source_file(self, krate, &[])];
+        self.push_submodule_source_files(&directory, &mut source_files);
+
+        let inputs = self.inputs.lock().unwrap();
+        source_files.extend(
+            inputs
+                .source_files
+                .iter()
+                .filter(|(url, _)| in_krate(&directory, url))
+                .map(|(_, &source_file)| source_file),
+        );
+        drop(inputs);
+
+        source_files.sort_by(|a, b| a.url(self).cmp(b.url(self)));
+        source_files.dedup();
+        source_files
+    }
+
+    /// Returns the source files of every module of all the crates in the [`CompilationRoot`]
+    /// (loading them if needed), along with any other source files loaded so far, ordered by URL.
+    pub fn workspace_source_files(&self) -> Vec<SourceFile> {
+        let mut source_files = vec![];
+        for &krate in self.root().crates(self) {
+            let source = self.inputs.lock().unwrap().directories[&krate].clone();
+            match source {
+                KrateSource::Url(directory) => {
+                    source_files.push(dada_ir_ast::Db::source_file(self, krate, &[]));
+                    self.push_submodule_source_files(&directory, &mut source_files);
+                }
+                KrateSource::Libdada => {
+                    for path in LibDadaAsset::iter() {
+                        let Some(path) = path.strip_suffix(".dada") else {
+                            continue;
+                        };
+                        let modules = path
+                            .split('/')
+                            .map(|module| Identifier::new(self, module.to_string()))
+                            .collect::<Vec<_>>();
+                        source_files.push(dada_ir_ast::Db::source_file(self, krate, &modules));
+                    }
+                }
+            }
+        }
+
+        let inputs = self.inputs.lock().unwrap();
+        source_files.extend(inputs.source_files.values().copied());
+        source_files.extend(inputs.libdada_source_files.values().copied());
+        drop(inputs);
+
+        source_files.sort_by(|a, b| a.url(self).cmp(b.url(self)));
+        source_files.dedup();
+        source_files
+    }
+
+    /// Pushes the source files of the modules in `directory` and its subdirectories
+    /// onto `source_files`. Directories that cannot be read are skipped.
+    fn push_submodule_source_files(&self, directory: &Url, source_files: &mut Vec<SourceFile>) {
+        let Ok(entries) = self.vfs.read_dir(directory) else {
+            return;
+        };
+        for url in entries {
+            if UrlPath::from(url.clone()).is_dada_file() {
+                source_files.push(self.get_or_create_source_file(&url));
+            } else {
+                self.push_submodule_source_files(&url, source_files);
+            }
+        }
+    }
+
     fn deduplicated(mut diagnostics: Vec<&Diagnostic>) -> Vec<&Diagnostic> {
         let mut new = Set::default();
         diagnostics.retain(|&d| new.insert(d));
@@ -388,6 +633,7 @@ struct Inputs {
     source_files: Map<Url, SourceFile>,
     libdada_source_files: Map<String, SourceFile>,
     directories: Map<Krate, KrateSource>,
+    scratch_source_files: Map<SourceFile, SourceFile>,
 }
 
 #[derive(FromImpls, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
@@ -429,6 +675,7 @@ fn fn_asts(db: &dyn Db, source_file: SourceFile) -> String {
                 for member in class_item.members(db) {
                     match member {
                         AstMember::Field(_field_decl) => (),
+                        AstMember::Variant(_variant) => (),
                         AstMember::Function(function) => {
                             writeln!(output, "### fn `{}`", function.name(db).id).unwrap();
                             writeln!(output).unwrap();
@@ -437,6 +684,14 @@ fn fn_asts(db: &dyn Db, source_file: SourceFile) -> String {
                     }
                 }
             }
+            AstItem::Trait(trait_item) => {
+                writeln!(output, "## trait `{}`", trait_item.name(db)).unwrap();
+                for function in trait_item.methods(db) {
+                    writeln!(output, "### fn `{}`", function.name(db).id).unwrap();
+                    writeln!(output).unwrap();
+                    writeln!(output, "{}", fn_asts_fn(db, *function)).unwrap();
+                }
+            }
             AstItem::Function(function) => {
                 writeln!(output, "## fn `{}`", function.name(db).id).unwrap();
                 writeln!(output).unwrap();
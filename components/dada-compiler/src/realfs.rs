@@ -43,6 +43,17 @@ impl VirtualFileSystem for RealFs {
         }
     }
 
+    fn read_dir(&self, url: &Url) -> Fallible<Vec<Url>> {
+        let path = Self::validate_scheme(url)?;
+        std::fs::read_dir(&path)?
+            .map(|entry| {
+                let path = entry?.path();
+                Url::from_file_path(&path)
+                    .map_err(|()| anyhow!("unable to construct URL from `{}`", path.display()))
+            })
+            .collect()
+    }
+
     fn path_url(&self, path: &Path) -> Fallible<Url> {
         let path = if let Some(base_dir) = &self.base_dir {
             base_dir.join(path)
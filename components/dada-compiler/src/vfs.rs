@@ -10,6 +10,10 @@ pub trait VirtualFileSystem: Send + Sync + 'static {
     /// True if the given URL exists.
     fn exists(&self, url: &Url) -> bool;
 
+    /// Lists the URLs of the entries (files and directories) in the directory at the given URL
+    /// (or fail if it is not a directory).
+    fn read_dir(&self, url: &Url) -> Fallible<Vec<Url>>;
+
     /// (Try to) convert a path on the local file system to a URL
     fn path_url(&self, path: &Path) -> Fallible<Url>;
 
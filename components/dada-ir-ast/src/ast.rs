@@ -14,6 +14,8 @@ mod use_item;
 pub use use_item::*;
 mod class_item;
 pub use class_item::*;
+mod trait_item;
+pub use trait_item::*;
 mod member;
 pub use member::*;
 mod function;
@@ -89,6 +91,7 @@ pub enum AstItem<'db> {
     SourceFile(SourceFile),
     Use(AstUse<'db>),
     Aggregate(AstAggregate<'db>),
+    Trait(AstTrait<'db>),
     Function(AstFunction<'db>),
 }
 
@@ -6,11 +6,15 @@ use crate::{
     span::{Span, Spanned},
 };
 
-use super::{AstGenericDecl, AstWhereClauses, Identifier, SpanVec};
+use super::{AstGenericDecl, AstPath, AstWhereClauses, Identifier, SpanVec};
 
 /// Some kind of aggregate, like a class, struct, etc.
 ///
 /// `class $name[$generics] { ... }` or `class $name[$generics](...) { ... }`
+///
+/// Either form may list the traits the class implements: `class $name[$generics](...) is $trait + ... { ... }`
+///
+/// For an `enum`, the contents are a list of variants: `enum $name[$generics] { $variant, ... }`
 #[derive(SalsaSerialize)]
 #[salsa::tracked(debug)]
 pub struct AstAggregate<'db> {
@@ -32,6 +36,11 @@ pub struct AstAggregate<'db> {
     #[return_ref]
     pub inputs: Option<SpanVec<'db, AstFieldDecl<'db>>>,
 
+    /// If an `is` section is present, the traits implemented by the class
+    /// (e.g., `class Foo is Show + Eq { ... }`).
+    #[return_ref]
+    pub traits: Option<SpanVec<'db, AstPath<'db>>>,
+
     #[return_ref]
     pub where_clauses: Option<AstWhereClauses<'db>>,
 
@@ -52,4 +61,5 @@ impl<'db> Spanned<'db> for AstAggregate<'db> {
 pub enum AstAggregateKind {
     Class,
     Struct,
+    Enum,
 }
@@ -4,7 +4,9 @@ use serde::Serialize;
 
 use crate::span::Span;
 
-use super::{AstGenericTerm, AstPath, AstTy, DeferredParse, SpanVec, SpannedIdentifier};
+use super::{
+    AstGenericTerm, AstPath, AstTy, DeferredParse, SpanVec, SpannedIdentifier, VariableDecl,
+};
 
 #[derive(SalsaSerialize)]
 #[salsa::tracked(debug)]
@@ -60,6 +62,7 @@ pub enum AstExprKind<'db> {
     ///
     /// Note that this is not necessarily a field.
     /// Interpretation is needed.
+    /// For tuple element access (`E.0`), the identifier is the index.
     DotId(AstExpr<'db>, SpannedIdentifier<'db>),
 
     /// `E[..]`
@@ -75,9 +78,9 @@ pub enum AstExprKind<'db> {
     /// as well as a `SquareBracketsOp`.
     ParenthesisOp(AstExpr<'db>, SpanVec<'db, AstExpr<'db>>),
 
-    /// `(a, b, c)`
+    /// `(a, b, c)`, `(a,)`, or `()`
     ///
-    /// Could also be `(a)`.
+    /// `(a)` is not a tuple; the parser yields `a` for it.
     Tuple(SpanVec<'db, AstExpr<'db>>),
 
     /// `a { field: value }`
@@ -106,6 +109,40 @@ pub enum AstExprKind<'db> {
 
     /// If/else-if chain
     If(Vec<IfArm<'db>>),
+
+    /// `match E { P => E, ... }`
+    Match(AstExpr<'db>, SpanVec<'db, AstMatchArm<'db>>),
+
+    /// `loop { ... }`
+    Loop(AstBlock<'db>),
+
+    /// `while E { ... }`
+    While(AstExpr<'db>, AstBlock<'db>),
+
+    /// `for x in E0..E1 { ... }`
+    ///
+    /// For now, we can only iterate over a range of integers.
+    For {
+        binding: SpannedIdentifier<'db>,
+        start: AstExpr<'db>,
+        end: AstExpr<'db>,
+        body: AstBlock<'db>,
+    },
+
+    /// `break` or `break E`
+    Break(Option<AstExpr<'db>>),
+
+    /// `continue`
+    Continue,
+
+    /// `fn(x: T, ...) -> R { ... }`
+    ///
+    /// If the output type is omitted, it is `()`.
+    Closure {
+        inputs: SpanVec<'db, VariableDecl<'db>>,
+        output: Option<AstTy<'db>>,
+        body: AstBlock<'db>,
+    },
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Update, Debug, Serialize)]
@@ -125,6 +162,42 @@ pub struct IfArm<'db> {
     pub result: AstBlock<'db>,
 }
 
+/// `P => E`
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Update, Debug, Serialize)]
+pub struct AstMatchArm<'db> {
+    pub pattern: AstPattern<'db>,
+
+    /// the value if the pattern matches
+    pub body: AstExpr<'db>,
+}
+
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Update, Debug, Serialize)]
+pub struct AstPattern<'db> {
+    pub span: Span<'db>,
+    pub kind: Box<AstPatternKind<'db>>,
+}
+
+impl<'db> AstPattern<'db> {
+    pub fn new(span: Span<'db>, kind: AstPatternKind<'db>) -> Self {
+        Self {
+            span,
+            kind: Box::new(kind),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Update, Debug, Serialize)]
+pub enum AstPatternKind<'db> {
+    /// `_`
+    Wildcard,
+
+    /// `x`, binds the entire value
+    Binding(SpannedIdentifier<'db>),
+
+    /// `Option.Some(p0, ..., pN)` or `Option.None`
+    Variant(AstPath<'db>, Option<SpanVec<'db, AstPattern<'db>>>),
+}
+
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Update, Debug, Serialize)]
 pub struct SpannedBinaryOp<'db> {
     pub span: Span<'db>,
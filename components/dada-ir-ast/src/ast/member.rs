@@ -7,7 +7,7 @@ use crate::{
     span::{Span, Spanned},
 };
 
-use super::{AstFunction, VariableDecl};
+use super::{AstFunction, Identifier, SpanVec, VariableDecl};
 
 #[derive(
     Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Update, Debug, FromImpls, Serialize,
@@ -15,6 +15,7 @@ use super::{AstFunction, VariableDecl};
 pub enum AstMember<'db> {
     Field(AstFieldDecl<'db>),
     Function(AstFunction<'db>),
+    Variant(AstVariant<'db>),
 }
 
 impl<'db> Spanned<'db> for AstMember<'db> {
@@ -22,6 +23,7 @@ impl<'db> Spanned<'db> for AstMember<'db> {
         match self {
             AstMember::Field(field) => field.span(db),
             AstMember::Function(function) => function.span(db),
+            AstMember::Variant(variant) => variant.span(db),
         }
     }
 }
@@ -39,3 +41,22 @@ impl<'db> Spanned<'db> for AstFieldDecl<'db> {
         AstFieldDecl::span(*self, db)
     }
 }
+
+/// A variant of an enum, e.g., `Some(value: T)` or `None`.
+#[derive(SalsaSerialize)]
+#[salsa::tracked(debug)]
+pub struct AstVariant<'db> {
+    pub span: Span<'db>,
+    pub name: Identifier<'db>,
+    pub name_span: Span<'db>,
+
+    /// The payload of the variant, if a `()` section is present.
+    #[return_ref]
+    pub fields: Option<SpanVec<'db, AstFieldDecl<'db>>>,
+}
+
+impl<'db> Spanned<'db> for AstVariant<'db> {
+    fn span(&self, db: &'db dyn crate::Db) -> Span<'db> {
+        AstVariant::span(*self, db)
+    }
+}
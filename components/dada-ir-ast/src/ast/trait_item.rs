@@ -0,0 +1,39 @@
+use dada_util::SalsaSerialize;
+
+use crate::{
+    ast::{AstVisibility, DeferredParse},
+    span::{Span, Spanned},
+};
+
+use super::{AstWhereClauses, Identifier};
+
+/// A trait, which declares method signatures that aggregates can implement.
+///
+/// `trait $name { fn $method(self, ...) -> $ty ... }`
+#[derive(SalsaSerialize)]
+#[salsa::tracked(debug)]
+pub struct AstTrait<'db> {
+    pub span: Span<'db>,
+
+    /// Visibility of the trait
+    pub visibility: Option<AstVisibility<'db>>,
+
+    pub name: Identifier<'db>,
+
+    pub name_span: Span<'db>,
+
+    #[return_ref]
+    pub where_clauses: Option<AstWhereClauses<'db>>,
+
+    /// The unparsed contents of the trait.
+    /// This can be parsed via the `methods`
+    /// method defined in `dada_parser::prelude`.
+    #[return_ref]
+    pub contents: Option<DeferredParse<'db>>,
+}
+
+impl<'db> Spanned<'db> for AstTrait<'db> {
+    fn span(&self, db: &'db dyn crate::Db) -> Span<'db> {
+        AstTrait::span(*self, db)
+    }
+}
@@ -29,6 +29,16 @@ pub enum AstTyKind<'db> {
 
     /// `type T`
     GenericDecl(AstGenericDecl<'db>),
+
+    /// `(A, B, C)`, `(A,)`, or `()` for the unit type
+    ///
+    /// `(A)` is not a tuple; the parser yields `A` for it.
+    Tuple(SpanVec<'db, AstTy<'db>>),
+
+    /// `fn(A, B) -> C`
+    ///
+    /// If the output type is omitted, it is `()`.
+    Function(SpanVec<'db, AstTy<'db>>, Option<AstTy<'db>>),
 }
 
 #[derive(SalsaSerialize)]
@@ -169,4 +179,7 @@ pub enum AstWhereClauseKind<'db> {
 
     /// `lent`
     Lent(Span<'db>),
+
+    /// `Trait`, the name of a trait that the subject must implement
+    Trait(AstPath<'db>),
 }
@@ -85,6 +85,20 @@ impl serde::Serialize for SourceFile {
     }
 }
 
+/// The start indices of each (0-based) line of `text` plus its length
+/// (see [`SourceFile::line_starts`]).
+pub fn line_starts(text: &str) -> Vec<AbsoluteOffset> {
+    std::iter::once(0)
+        .chain(
+            text.char_indices()
+                .filter(|&(_, ch)| ch == '\n')
+                .map(|(index, _)| index + 1),
+        )
+        .chain(std::iter::once(text.len()))
+        .map(AbsoluteOffset::from)
+        .collect()
+}
+
 impl<'db> Spanned<'db> for SourceFile {
     fn span(&self, db: &'db dyn crate::Db) -> crate::span::Span<'db> {
         Span {
@@ -132,16 +146,7 @@ impl SourceFile {
     /// (effectively an imaginary N+1 line that starts, and ends, at the end).
     #[salsa::tracked(return_ref)]
     pub fn line_starts(self, db: &dyn crate::Db) -> Vec<AbsoluteOffset> {
-        std::iter::once(0)
-            .chain(
-                self.contents_if_ok(db)
-                    .char_indices()
-                    .filter(|&(_, ch)| ch == '\n')
-                    .map(|(index, _)| index + 1),
-            )
-            .chain(std::iter::once(self.contents_if_ok(db).len()))
-            .map(AbsoluteOffset::from)
-            .collect()
+        line_starts(self.contents_if_ok(db))
     }
 
     pub fn line_range(self, db: &dyn crate::Db, line: ZeroLine) -> Range<AbsoluteOffset> {
@@ -3,7 +3,7 @@ use salsa::Update;
 use serde::Serialize;
 
 use crate::{
-    ast::{AstAggregate, AstFunction},
+    ast::{AstAggregate, AstFunction, AstTrait},
     inputs::SourceFile,
 };
 
@@ -13,6 +13,7 @@ use crate::{
 pub enum Anchor<'db> {
     SourceFile(SourceFile),
     Class(AstAggregate<'db>),
+    Trait(AstTrait<'db>),
     Function(AstFunction<'db>),
 }
 
@@ -25,6 +26,7 @@ impl<'db> Anchor<'db> {
                 end: Offset::from(source_file.contents_if_ok(db).len()),
             },
             Anchor::Class(data) => data.span(db),
+            Anchor::Trait(data) => data.span(db),
             Anchor::Function(data) => data.span(db),
         }
     }
@@ -36,6 +38,7 @@ impl<'db> Anchor<'db> {
 
             // For most anchors, we have to skip past the `{}` or `()` in the delimiters by invoking `narrow`.
             Anchor::Class(data) => data.span(db).absolute_span(db).narrow(),
+            Anchor::Trait(data) => data.span(db).absolute_span(db).narrow(),
             Anchor::Function(data) => data.span(db).absolute_span(db).narrow(),
         }
     }
@@ -44,6 +47,7 @@ impl<'db> Anchor<'db> {
         match self {
             Anchor::SourceFile(source_file) => *source_file,
             Anchor::Class(ast_class_item) => ast_class_item.name_span(db).source_file(db),
+            Anchor::Trait(ast_trait) => ast_trait.name_span(db).source_file(db),
             Anchor::Function(ast_function) => ast_function.name(db).span.source_file(db),
         }
     }
@@ -16,7 +16,7 @@ pub(crate) mod functions;
 mod generics;
 pub(crate) mod inference;
 mod live_places;
-mod member_lookup;
+pub(crate) mod member_lookup;
 mod modules;
 mod places;
 pub(crate) mod predicates;
@@ -35,6 +35,9 @@ mod temporaries;
 mod to_red;
 mod types;
 mod universe;
+pub(crate) mod variances;
+
+pub use scope::{NameResolutionSym, Scope};
 
 /// Check an expression in a full environment.
 /// This is an async operation -- it may block if insufficient inference data is available.
@@ -557,6 +557,9 @@ pub enum TaskDescription<'db> {
     IfNotNever,
     Misc,
     CheckArg(usize),
+    CheckMatchArm(usize),
+    CheckLoopBody,
+    CheckClosureBody,
     ReconcileTyBounds(InferVarIndex),
     RelateInferBounds,
 }
@@ -1,4 +1,4 @@
-use std::{cell::Cell, ops::AsyncFnOnce, panic::Location, sync::Arc};
+use std::{cell::Cell, ops::AsyncFnOnce, panic::Location, rc::Rc, sync::Arc};
 
 use crate::{
     check::{
@@ -8,10 +8,11 @@ use crate::{
     },
     ir::{
         binder::BoundTerm,
-        generics::SymWhereClause,
+        generics::{SymWhereClause, SymWhereClauseKind},
         indices::{FromInfer, InferVarIndex},
         populate::variable_decl_requires_default_perm,
         subst::SubstWith,
+        traits::SymTrait,
         types::{
             AnonymousPermSymbol, Assumption, AssumptionKind, SymGenericKind, SymGenericTerm,
             SymPerm, SymTy, SymTyName, Variance,
@@ -71,8 +72,17 @@ pub(crate) struct Env<'db> {
     /// If `None`, not type checking a function or method.
     pub return_ty: Option<SymTy<'db>>,
 
+    /// If `None`, not type checking the body of a loop.
+    pub enclosing_loop: Option<EnclosingLoop<'db>>,
+
+    /// Variables of the enclosing closure that hold captured references (see `check_closure`).
+    pub captures: Arc<Vec<SymVariable<'db>>>,
+
     /// Assumptions declared
     assumptions: Arc<Vec<Assumption<'db>>>,
+
+    /// Trait bounds declared on generic types, like `T is Show`
+    trait_bounds: Arc<Vec<(SymVariable<'db>, SymTrait<'db>)>>,
 }
 
 impl<'db> Env<'db> {
@@ -86,7 +96,10 @@ impl<'db> Env<'db> {
             variable_tys: Default::default(),
             variable_universes: Default::default(),
             return_ty: Default::default(),
-            assumptions: Arc::new(vec![]), // FIXME
+            enclosing_loop: Default::default(),
+            captures: Default::default(),
+            assumptions: Default::default(),
+            trait_bounds: Default::default(),
         }
     }
 
@@ -126,7 +139,10 @@ impl<'db> Env<'db> {
             variable_universes: self.variable_universes.clone(),
             variable_tys: self.variable_tys.clone(),
             return_ty: self.return_ty,
+            enclosing_loop: self.enclosing_loop.clone(),
+            captures: self.captures.clone(),
             assumptions: self.assumptions.clone(),
+            trait_bounds: self.trait_bounds.clone(),
         }
     }
 
@@ -170,6 +186,39 @@ impl<'db> Env<'db> {
             .any(|a| a.var(self.db()) == var && kind(a.kind(self.db())))
     }
 
+    /// Record the where-clauses declared on the item being checked and its enclosing items,
+    /// so that they can be assumed to hold.
+    /// Permission predicates on generic variables (e.g., `P is shared`) become [`Assumption`]s;
+    /// trait bounds on generic types (e.g., `T is Show`) are used to find the methods of `T`.
+    /// Where-clauses whose subject is not a generic variable are ignored.
+    pub fn declare_where_clauses(&mut self, where_clauses: &[SymWhereClause<'db>]) {
+        let db = self.db();
+        for &where_clause in where_clauses {
+            let Some(var) = where_clause.subject(db).as_var(db) else {
+                continue;
+            };
+            let kind = match where_clause.kind(db) {
+                SymWhereClauseKind::Unique => AssumptionKind::Unique,
+                SymWhereClauseKind::Shared => AssumptionKind::Shared,
+                SymWhereClauseKind::Owned => AssumptionKind::Owned,
+                SymWhereClauseKind::Lent => AssumptionKind::Lent,
+                SymWhereClauseKind::Trait(sym_trait) => {
+                    Arc::make_mut(&mut self.trait_bounds).push((var, sym_trait));
+                    continue;
+                }
+            };
+            Arc::make_mut(&mut self.assumptions).push(Assumption::new(db, kind, var));
+        }
+    }
+
+    /// The traits that the generic type `var` is declared to implement.
+    pub fn declared_traits(&self, var: SymVariable<'db>) -> impl Iterator<Item = SymTrait<'db>> {
+        self.trait_bounds
+            .iter()
+            .filter(move |&&(v, _)| v == var)
+            .map(|&(_, sym_trait)| sym_trait)
+    }
+
     /// Open the given symbols as universally quantified.
     /// Creates a new universe.
     #[allow(dead_code)]
@@ -521,7 +570,8 @@ impl<'db> Env<'db> {
             SymTyName::Primitive(_) => vec![],
             SymTyName::Future => vec![Variance::covariant()],
             SymTyName::Tuple { arity } => vec![Variance::covariant(); arity],
-            SymTyName::Aggregate(aggr) => aggr.variances(self.db()),
+            SymTyName::Function { arity } => Variance::function(arity),
+            SymTyName::Aggregate(aggr) => aggr.variances(self.db()).clone(),
         }
     }
 
@@ -587,7 +637,7 @@ impl<'db> Env<'db> {
         self.scope.all_binders().into_iter().flatten().collect()
     }
 
-    /// Record that `lower <: upper` must hold,
+    /// Record that `lower <: upper` must hold when `live_after` are live afterwards,
     /// returning true if this is the first time that this has been recorded
     /// or false if it has been recorded before.
     ///
@@ -599,10 +649,11 @@ impl<'db> Env<'db> {
         &mut self,
         lower: InferVarIndex,
         upper: InferVarIndex,
+        live_after: LivePlaces,
     ) -> bool {
         assert_ne!(lower, upper);
         self.runtime
-            .insert_sub_infer_var_pair(lower, upper, &self.log)
+            .insert_sub_infer_var_pair(lower, upper, live_after, &self.log)
     }
 
     /// Check if `infer` is required to meet the given predicate.
@@ -710,6 +761,30 @@ impl<'env, 'db> RedBound<'env, 'db> {
     }
 }
 
+/// The innermost loop enclosing the expression being type checked.
+/// Used to check the `break` and `continue` expressions within it.
+#[derive(Clone)]
+pub(crate) struct EnclosingLoop<'db> {
+    /// Type of the value that the loop produces, which is given by `break`.
+    pub break_ty: SymTy<'db>,
+
+    /// Places live once the loop completes, and hence after a `break`.
+    pub live_after: LivePlaces,
+
+    /// Set once we find a `break` that exits this loop.
+    pub has_break: Rc<Cell<bool>>,
+}
+
+impl<'db> EnclosingLoop<'db> {
+    pub fn new(break_ty: SymTy<'db>, live_after: LivePlaces) -> Self {
+        Self {
+            break_ty,
+            live_after,
+            has_break: Default::default(),
+        }
+    }
+}
+
 #[derive(Clone)]
 struct VariableTypeCell<'db> {
     lv: SymVariable<'db>,
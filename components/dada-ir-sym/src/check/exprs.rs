@@ -1,42 +1,44 @@
-use std::panic::Location;
+use std::{panic::Location, sync::Arc};
 
 use crate::{
     check::{
-        env::Env,
-        member_lookup::MemberLookup,
-        scope::{NameResolution, NameResolutionSym},
+        env::{EnclosingLoop, Env},
+        member_lookup::{MemberLookup, non_infer_lower_bound},
+        red::RedTy,
+        scope::{NameResolution, NameResolutionSym, Resolve},
         scope_tree::ScopeTreeNode,
     },
     ir::{
         binder::Binder,
-        classes::SymAggregate,
+        classes::{SymAggregate, SymField, SymVariant},
         exprs::{
-            SymBinaryOp, SymByteLiteral, SymByteLiteralData, SymExpr, SymExprKind, SymLiteral,
-            SymMatchArm, SymPlaceExpr, SymPlaceExprKind,
+            SymBinaryOp, SymByteLiteral, SymByteLiteralData, SymCapture, SymExpr, SymExprKind,
+            SymLiteral, SymMatchArm, SymPlaceExpr, SymPlaceExprKind,
         },
         functions::{SymFunction, SymInputOutput},
-        types::{SymGenericKind, SymGenericTerm, SymTy, SymTyKind, SymTyName},
+        types::{SymGenericKind, SymGenericTerm, SymPerm, SymPlace, SymTy, SymTyKind, SymTyName},
         variables::{FromVar, SymVariable},
     },
-    prelude::CheckedSignature,
+    prelude::{CheckedFieldTy, CheckedSignature, Symbol},
     well_known,
 };
 use dada_ir_ast::{
     ast::{
-        AstBinaryOp, AstExpr, AstExprKind, AstGenericTerm, Identifier, LiteralKind, PermissionOp,
-        SpanVec, SpannedBinaryOp, SpannedIdentifier, UnaryOp,
+        AstBinaryOp, AstBlock, AstConstructorField, AstExpr, AstExprKind, AstGenericTerm,
+        AstMatchArm, AstPath, AstPatternKind, AstTy, Identifier, LiteralKind, PermissionOp,
+        SpanVec, SpannedBinaryOp, SpannedIdentifier, UnaryOp, VariableDecl,
     },
     diagnostic::{Diagnostic, Err, Level, Reported},
     span::{Span, Spanned},
 };
 use dada_parser::prelude::*;
-use dada_util::{FromImpls, boxed_async_fn};
+use dada_util::{FromImpls, Set, boxed_async_fn};
 use serde::Serialize;
 
 use super::{
     CheckExprInEnv, CheckTyInEnv,
     debug::TaskDescription,
-    live_places::LivePlaces,
+    live_places::{LivePlaces, MentionsNames},
     report::{
         AwaitNonFuture, BadSubtermError, InvalidAssignmentType, InvalidReturnValue,
         NumericTypeExpected, OperatorArgumentsMustHaveSameType, OperatorRequiresNumericType,
@@ -112,7 +114,7 @@ async fn check_expr<'db>(
                         SymExprKind::Primitive(SymLiteral::Integral { bits }),
                     );
                     env.spawn_require_my_numeric_type(
-                        LivePlaces::fixme(),
+                        live_after,
                         ty,
                         &NumericTypeExpected::new(sym_expr, ty),
                     );
@@ -205,10 +207,12 @@ async fn check_expr<'db>(
             AstExprKind::Tuple(span_vec) => {
                 let mut temporaries = vec![];
                 let mut exprs = vec![];
-                for element in &span_vec.values {
+                let live_after_elements = live_after.after_each(env, &span_vec.values);
+                for (element, live_after_element) in span_vec.values.iter().zip(live_after_elements)
+                {
                     exprs.push(
                         element
-                            .check_in_env(env, LivePlaces::fixme())
+                            .check_in_env(env, live_after_element)
                             .await
                             .into_expr(env, &mut temporaries),
                     );
@@ -239,12 +243,13 @@ async fn check_expr<'db>(
                 match span_op.op {
                     AstBinaryOp::Add | AstBinaryOp::Sub | AstBinaryOp::Mul | AstBinaryOp::Div => {
                         let mut temporaries: Vec<Temporary<'db>> = vec![];
+                        let live_after_lhs = live_after.before(env, rhs);
                         let lhs: SymExpr<'db> = lhs
-                            .check_in_env(env, LivePlaces::fixme())
+                            .check_in_env(env, live_after_lhs)
                             .await
                             .into_expr(env, &mut temporaries);
                         let rhs: SymExpr<'db> = rhs
-                            .check_in_env(env, LivePlaces::fixme())
+                            .check_in_env(env, live_after)
                             .await
                             .into_expr(env, &mut temporaries);
 
@@ -291,15 +296,16 @@ async fn check_expr<'db>(
 
                     AstBinaryOp::AndAnd => {
                         let mut temporaries: Vec<Temporary<'db>> = vec![];
+                        let live_after_lhs = live_after.before(env, rhs);
                         let lhs: SymExpr<'db> = lhs
-                            .check_in_env(env, LivePlaces::fixme())
+                            .check_in_env(env, live_after_lhs)
                             .await
                             .into_expr(env, &mut temporaries);
                         let rhs: SymExpr<'db> = rhs
                             .check_in_env(env, live_after)
                             .await
                             .into_expr(env, &mut temporaries);
-                        env.require_expr_has_bool_ty(LivePlaces::fixme(), lhs);
+                        env.require_expr_has_bool_ty(live_after_lhs, lhs);
                         env.require_expr_has_bool_ty(live_after, rhs);
 
                         // construct an expression like
@@ -325,8 +331,9 @@ async fn check_expr<'db>(
 
                     AstBinaryOp::OrOr => {
                         let mut temporaries: Vec<Temporary<'db>> = vec![];
+                        let live_after_lhs = live_after.before(env, rhs);
                         let lhs: SymExpr<'db> = lhs
-                            .check_in_env(env, LivePlaces::fixme())
+                            .check_in_env(env, live_after_lhs)
                             .await
                             .into_expr(env, &mut temporaries);
                         let rhs: SymExpr<'db> = rhs
@@ -334,7 +341,7 @@ async fn check_expr<'db>(
                             .await
                             .into_expr(env, &mut temporaries);
 
-                        env.require_expr_has_bool_ty(LivePlaces::fixme(), lhs);
+                        env.require_expr_has_bool_ty(live_after_lhs, lhs);
                         env.require_expr_has_bool_ty(live_after, rhs);
 
                         // construct an expression like
@@ -364,12 +371,13 @@ async fn check_expr<'db>(
                     | AstBinaryOp::LessEqual
                     | AstBinaryOp::EqualEqual => {
                         let mut temporaries: Vec<Temporary<'db>> = vec![];
+                        let live_after_lhs = live_after.before(env, rhs);
                         let lhs: SymExpr<'db> = lhs
-                            .check_in_env(env, LivePlaces::fixme())
+                            .check_in_env(env, live_after_lhs)
                             .await
                             .into_expr(env, &mut temporaries);
                         let rhs: SymExpr<'db> = rhs
-                            .check_in_env(env, LivePlaces::fixme())
+                            .check_in_env(env, live_after)
                             .await
                             .into_expr(env, &mut temporaries);
 
@@ -386,7 +394,7 @@ async fn check_expr<'db>(
                         );
                         env.spawn_if_not_never(&[lhs.ty(db), rhs.ty(db)], async move |env| {
                             env.spawn_require_equal_types(
-                                LivePlaces::fixme(),
+                                live_after,
                                 lhs.ty(db),
                                 rhs.ty(db),
                                 &OperatorArgumentsMustHaveSameType::new(span_op, lhs, rhs),
@@ -416,12 +424,22 @@ async fn check_expr<'db>(
 
                     AstBinaryOp::Assign => {
                         let mut temporaries: Vec<Temporary<'db>> = vec![];
+                        let live_after_place = live_after.before(env, rhs);
                         let place: SymPlaceExpr<'db> = lhs
-                            .check_in_env(env, LivePlaces::fixme())
+                            .check_in_env(env, live_after_place)
                             .await
                             .into_place_expr(env, &mut temporaries);
+                        if let Some(capture) = captured_root(env, place) {
+                            return ExprResult::err(
+                                db,
+                                report_assign_to_capture(env, place.span(db), capture),
+                            );
+                        }
+
+                        // The place being assigned is used once the value is computed.
+                        let live_after_value = live_after.before(env, lhs);
                         let value: SymExpr<'db> = rhs
-                            .check_in_env(env, LivePlaces::fixme())
+                            .check_in_env(env, live_after_value)
                             .await
                             .into_expr(env, &mut temporaries);
 
@@ -429,7 +447,7 @@ async fn check_expr<'db>(
                         // of the same primitive (and scalar) type.
 
                         env.spawn_require_assignable_type(
-                            LivePlaces::fixme(),
+                            live_after_value,
                             value.ty(db),
                             place.ty(db),
                             &InvalidAssignmentType::new(place, value),
@@ -495,7 +513,9 @@ async fn check_expr<'db>(
             }
 
             AstExprKind::SquareBracketOp(owner, square_bracket_args) => {
-                let owner_result = owner.check_in_env(env, LivePlaces::fixme()).await;
+                let live_after_owner =
+                    live_after.before(env, &square_bracket_args.parse_as_generics(db));
+                let owner_result = owner.check_in_env(env, live_after_owner).await;
                 match owner_result.kind {
                     ExprResultKind::Method {
                         self_expr: owner,
@@ -553,7 +573,8 @@ async fn check_expr<'db>(
             }
 
             AstExprKind::ParenthesisOp(owner, ast_args) => {
-                let owner_result = owner.check_in_env(env, live_after).await;
+                let live_after_owner = live_after.before(env, ast_args);
+                let owner_result = owner.check_in_env(env, live_after_owner).await;
                 match owner_result {
                     ExprResult {
                         temporaries,
@@ -629,16 +650,48 @@ async fn check_expr<'db>(
                         .await
                     }
 
+                    // Calling a variant like `Option.Some(x)` constructs the enum.
                     ExprResult {
-                        span: owner_span, ..
+                        temporaries,
+                        span: variant_span,
+                        kind:
+                            ExprResultKind::Other(NameResolution {
+                                generics,
+                                sym: NameResolutionSym::SymVariant(variant),
+                            }),
+                    } => {
+                        check_variant_call(
+                            env,
+                            live_after,
+                            variant_span,
+                            expr_span,
+                            variant,
+                            generics,
+                            ast_args,
+                            temporaries,
+                        )
+                        .await
+                    }
+
+                    // Calling a value like `f(a, b)`, which must have function type.
+                    callee @ ExprResult {
+                        kind: ExprResultKind::PlaceExpr(_) | ExprResultKind::Expr(_),
+                        ..
                     } => {
-                        // FIXME: we probably want to support functions and function typed values?
-                        ExprResult::err(db, report_not_callable(db, owner_span))
+                        // The callee is used after the arguments are evaluated.
+                        let live_after_args = live_after.before(env, owner);
+                        check_indirect_call(env, live_after_args, expr_span, callee, ast_args).await
                     }
+
+                    ExprResult {
+                        span: owner_span, ..
+                    } => ExprResult::err(db, report_not_callable(db, owner_span)),
                 }
             }
 
-            AstExprKind::Constructor(_ast_path, _span_vec) => todo!(),
+            AstExprKind::Constructor(ast_path, ast_fields) => {
+                check_constructor(env, live_after, expr_span, *ast_path, ast_fields).await
+            }
             AstExprKind::Return(ast_expr) => {
                 let mut temporaries = vec![];
 
@@ -765,13 +818,17 @@ async fn check_expr<'db>(
             AstExprKind::If(ast_arms) => {
                 let mut arms = vec![];
                 let mut has_else = false;
-                for arm in ast_arms {
+                for (arm, later_arms) in ast_arms.iter().zip(1..) {
                     let condition = if let Some(c) = &arm.condition {
+                        // If the condition is false, we go on to the later arms.
+                        let live_after_condition = live_after
+                            .before(env, &ast_arms[later_arms..])
+                            .before(env, &arm.result);
                         let expr = c
-                            .check_in_env(env, LivePlaces::fixme())
+                            .check_in_env(env, live_after_condition)
                             .await
                             .into_expr_with_enclosed_temporaries(env);
-                        env.require_expr_has_bool_ty(LivePlaces::fixme(), expr);
+                        env.require_expr_has_bool_ty(live_after_condition, expr);
                         Some(expr)
                     } else {
                         has_else = true;
@@ -805,6 +862,123 @@ async fn check_expr<'db>(
                 }
             }
 
+            AstExprKind::Match(ast_scrutinee, ast_arms) => {
+                check_match(env, live_after, expr_span, ast_scrutinee, ast_arms).await
+            }
+
+            AstExprKind::Loop(ast_body) => {
+                // A later iteration may use anything mentioned in the loop.
+                let live_in_loop = live_after.before(env, expr);
+
+                let break_ty = env.fresh_ty_inference_var(expr_span);
+                let enclosing_loop = EnclosingLoop::new(break_ty, live_after);
+                let has_break = enclosing_loop.has_break.clone();
+
+                let mut loop_env =
+                    env.fork(|log| log.spawn(Location::caller(), TaskDescription::CheckLoopBody));
+                loop_env.enclosing_loop = Some(enclosing_loop);
+                let body = ast_body.check_in_env(&mut loop_env, live_in_loop).await;
+
+                // A loop without a `break` never completes.
+                let loop_ty = if has_break.get() {
+                    break_ty
+                } else {
+                    SymTy::never(db)
+                };
+
+                ExprResult {
+                    temporaries: vec![],
+                    span: expr_span,
+                    kind: SymExpr::new(db, expr_span, loop_ty, SymExprKind::Loop { body }).into(),
+                }
+            }
+
+            AstExprKind::While(ast_condition, ast_body) => {
+                // A later iteration may use anything mentioned in the loop.
+                let live_in_loop = live_after.before(env, expr);
+
+                let mut loop_env =
+                    env.fork(|log| log.spawn(Location::caller(), TaskDescription::CheckLoopBody));
+                loop_env.enclosing_loop = Some(EnclosingLoop::new(SymTy::unit(db), live_after));
+
+                let condition = ast_condition
+                    .check_in_env(&mut loop_env, live_in_loop)
+                    .await
+                    .into_expr_with_enclosed_temporaries(&mut loop_env);
+                loop_env.require_expr_has_bool_ty(live_in_loop, condition);
+
+                let body = ast_body.check_in_env(&mut loop_env, live_in_loop).await;
+
+                ExprResult {
+                    temporaries: vec![],
+                    span: expr_span,
+                    kind: SymExpr::while_loop(db, expr_span, condition, body).into(),
+                }
+            }
+
+            AstExprKind::For {
+                binding,
+                start: ast_start,
+                end: ast_end,
+                body: ast_body,
+            } => {
+                check_for_range(
+                    env, live_after, expr, *binding, ast_start, ast_end, ast_body,
+                )
+                .await
+            }
+
+            AstExprKind::Break(ast_value) => {
+                let Some(enclosing_loop) = env.enclosing_loop.clone() else {
+                    return ExprResult::err(db, report_outside_loop(env, expr_span, "break"));
+                };
+                enclosing_loop.has_break.set(true);
+
+                let mut temporaries = vec![];
+                let value = if let Some(ast_value) = ast_value {
+                    ast_value
+                        .check_in_env(env, enclosing_loop.live_after)
+                        .await
+                        .into_expr(env, &mut temporaries)
+                } else {
+                    // the default is `break ()`
+                    SymExpr::unit(db, expr_span)
+                };
+
+                env.spawn_require_assignable_type(
+                    enclosing_loop.live_after,
+                    value.ty(db),
+                    enclosing_loop.break_ty,
+                    &BadSubtermError::new(value.span(db), value.ty(db), enclosing_loop.break_ty),
+                );
+
+                ExprResult {
+                    temporaries,
+                    span: expr_span,
+                    kind: SymExpr::new(db, expr_span, SymTy::never(db), SymExprKind::Break(value))
+                        .into(),
+                }
+            }
+
+            AstExprKind::Continue => {
+                if env.enclosing_loop.is_none() {
+                    return ExprResult::err(db, report_outside_loop(env, expr_span, "continue"));
+                }
+
+                ExprResult {
+                    temporaries: vec![],
+                    span: expr_span,
+                    kind: SymExpr::new(db, expr_span, SymTy::never(db), SymExprKind::Continue)
+                        .into(),
+                }
+            }
+
+            AstExprKind::Closure {
+                inputs,
+                output,
+                body,
+            } => check_closure(env, expr_span, inputs, *output, *body).await,
+
             AstExprKind::PermissionOp { value, op } => {
                 let mut temporaries = vec![];
                 let value_result = value.check_in_env(env, live_after).await;
@@ -872,177 +1046,901 @@ async fn check_class_call<'db>(
     .await
 }
 
-fn report_no_new_method<'db>(
-    db: &'db dyn crate::Db,
-    class_span: Span<'db>,
-    class_sym: SymAggregate<'db>,
-    resolution: Result<NameResolution<'db>, NameResolution<'db>>,
-) -> Reported {
-    let mut diag = Diagnostic::error(
-        db,
-        class_span,
-        format!("the class `{class_sym}` has no `new` method"),
-    )
-    .label(
-        db,
-        Level::Error,
-        class_span,
-        format!("I could not find a `new` method on the class `{class_sym}`"),
-    );
-
-    match resolution {
-        Ok(name_resolution) => {
-            diag = diag.child(
-                Diagnostic::new(
-                    db,
-                    Level::Note,
-                    class_sym.name_span(db),
-                    "calling a class is equivalent to calling `new`, but `new` is not a method"
-                        .to_string(),
-                )
-                .label(
-                    db,
-                    Level::Note,
-                    name_resolution.span(db).unwrap(),
-                    format!(
-                        "I found a class member named `new` but it is {}, not a method",
-                        name_resolution.categorize(db)
-                    ),
-                ),
-            );
-        }
-        Err(_) => {
-            diag = diag.child(
-                Diagnostic::new(
-                    db,
-                    Level::Note,
-                    class_sym.name_span(db),
-                    format!(
-                        "calling a class is equivalent to calling `new`, but `{class_sym}` does not define a `new` method"
-                    ),
-                )
-                .label(
-                    db,
-                    Level::Note,
-                    class_sym.name_span(db),
-                    "I could not find any class member named `new`".to_string(),
-                ),
-            );
-        }
-    }
-
-    diag.report(db)
-}
-
+/// Check a constructor expression like `Point { x: 22, y: 44 }`.
+///
+/// The field values are evaluated in the order they are written,
+/// so each is stored in a temporary before being gathered into an aggregate
+/// in the order the fields are declared.
 #[boxed_async_fn]
-async fn check_function_call<'db>(
+async fn check_constructor<'db>(
     env: &mut Env<'db>,
     live_after: LivePlaces,
-    function_span: Span<'db>,
     expr_span: Span<'db>,
-    function: SymFunction<'db>,
-    ast_args: &SpanVec<'db, AstExpr<'db>>,
-    generics: Vec<SymGenericTerm<'db>>,
-    temporaries: Vec<Temporary<'db>>,
+    ast_path: AstPath<'db>,
+    ast_fields: &SpanVec<'db, AstConstructorField<'db>>,
 ) -> ExprResult<'db> {
     let db = env.db();
 
-    env.log("check_function_call", &[&function]);
-    env.log("generics", &[&generics]);
-
-    // Get the signature.
-    let signature = match function.checked_signature(db) {
-        Ok(signature) => signature,
+    let resolution = match ast_path.resolve_in(env).await {
+        Ok(NameResolution {
+            generics,
+            sym: NameResolutionSym::SymAggregate(sym_aggregate),
+        }) if !sym_aggregate.is_enum(db) => Ok((sym_aggregate, generics)),
+        Ok(name_resolution) => Err(report_not_constructible(
+            db,
+            ast_path.span(db),
+            &name_resolution,
+        )),
+        Err(reported) => Err(reported),
+    };
+    let (sym_aggregate, mut generics) = match resolution {
+        Ok(r) => r,
         Err(reported) => {
-            for ast_arg in ast_args {
-                let _ = ast_arg.check_in_env(env, LivePlaces::fixme()).await;
+            for ast_field in ast_fields {
+                let _ = ast_field.value.check_in_env(env, LivePlaces::fixme()).await;
             }
             return ExprResult::err(db, reported);
         }
     };
-    let input_output = signature.input_output(db);
-
-    env.log("input_output", &[&input_output]);
 
     // Create inference variables for any generic arguments not provided.
-    let expected_generics = function.transitive_generic_parameters(db);
-    env.log("expected_generics", &[&expected_generics]);
-    let mut substitution = generics.clone();
-    substitution.extend(
-        expected_generics[generics.len()..]
-            .iter()
-            .map(|&var| env.fresh_inference_var_term(var.kind(db), function_span)),
+    generics.extend(
+        sym_aggregate
+            .generic_kinds(db)
+            .skip(generics.len())
+            .map(|kind| env.fresh_inference_var_term(kind, ast_path.span(db))),
     );
 
-    check_call_common(
-        env,
-        live_after,
-        function,
-        expr_span,
-        function_span,
-        input_output,
-        substitution,
-        ast_args,
-        None,
+    // Field types may refer to `self`, which here is the value being constructed.
+    let self_place = SymPlace::var(
+        db,
+        SymVariable::new(db, SymGenericKind::Place, None, expr_span),
+    );
+
+    let fields = sym_aggregate.fields(db).collect::<Vec<_>>();
+    let mut values: Vec<Option<(SpannedIdentifier<'db>, SymExpr<'db>)>> = vec![None; fields.len()];
+    let mut temporaries = vec![];
+    let live_after_values = live_after.after_each(env, &ast_fields.values);
+    for (ast_field, live_after_value) in ast_fields.iter().zip(live_after_values) {
+        let value = ast_field
+            .value
+            .check_in_env(env, live_after_value)
+            .await
+            .into_expr(env, &mut temporaries);
+
+        let Some(index) = fields.iter().position(|f| f.name(db) == ast_field.name.id) else {
+            report_unknown_field(db, sym_aggregate, ast_field.name);
+            continue;
+        };
+
+        if let Some((previous, _)) = values[index] {
+            report_duplicate_field(db, previous, ast_field.name);
+            continue;
+        }
+
+        let field_ty = fields[index]
+            .checked_field_ty(db)
+            .substitute(db, &generics)
+            .substitute(db, &[self_place]);
+        env.spawn_require_assignable_type(
+            live_after_value,
+            value.ty(db),
+            field_ty,
+            &BadSubtermError::new(value.span(db), value.ty(db), field_ty),
+        );
+
+        let value = value.into_temporary(db, &mut temporaries).give(db);
+        values[index] = Some((ast_field.name, value));
+    }
+
+    let labels = values
+        .iter()
+        .map(|value| value.map(|(name, _)| name.span))
+        .collect();
+    let field_exprs = fields
+        .iter()
+        .zip(values)
+        .map(|(&field, value)| match value {
+            Some((_, value)) => value,
+            None => SymExpr::err(
+                db,
+                report_missing_field(db, expr_span, sym_aggregate, field),
+            ),
+        })
+        .collect();
+
+    let ty = SymTy::named(db, sym_aggregate.into(), generics);
+    ExprResult::from_expr(
+        db,
+        SymExpr::new(
+            db,
+            expr_span,
+            ty,
+            SymExprKind::Aggregate {
+                ty,
+                fields: field_exprs,
+                labels,
+            },
+        ),
         temporaries,
     )
-    .await
 }
 
-/// Check a call like `a.b()` where `b` is a method.
-/// These are somewhat different than calls like `b(a)` because of how
-/// type arguments are handled.
-#[allow(clippy::too_many_arguments)]
+/// Check a loop like `for x in start..end { body }` over a range of integers.
+/// This is lowered to
+///
+/// ```text
+/// let next = start
+/// let end = end
+/// while next < end {
+///     let x = next
+///     next = next + 1
+///     body
+/// }
+/// ```
+///
+/// where `next` and `end` are temporaries.
 #[boxed_async_fn]
-async fn check_method_call<'db>(
+async fn check_for_range<'db>(
     env: &mut Env<'db>,
     live_after: LivePlaces,
-    id_span: Span<'db>,
-    expr_span: Span<'db>,
-    function: SymFunction<'db>,
-    self_expr: Option<SymExpr<'db>>,
-    ast_args: &[AstExpr<'db>],
-    generics: Option<SpanVec<'db, AstGenericTerm<'db>>>,
-    temporaries: Vec<Temporary<'db>>,
+    expr: &AstExpr<'db>,
+    binding: SpannedIdentifier<'db>,
+    ast_start: &AstExpr<'db>,
+    ast_end: &AstExpr<'db>,
+    ast_body: &AstBlock<'db>,
 ) -> ExprResult<'db> {
     let db = env.db();
+    let span = expr.span;
+    let mut temporaries = vec![];
+
+    // A later iteration may use anything mentioned in the loop.
+    let live_in_loop = live_after.before(env, expr);
+
+    let start = ast_start
+        .check_in_env(env, live_in_loop)
+        .await
+        .into_expr(env, &mut temporaries);
+    let end = ast_end
+        .check_in_env(env, live_in_loop)
+        .await
+        .into_expr(env, &mut temporaries);
+
+    // The loop variable has the type of `start`.
+    let elem_ty = start.ty(db);
+    env.spawn_require_numeric_type(elem_ty, &NumericTypeExpected::new(start, elem_ty));
+    env.spawn_require_assignable_type(
+        live_in_loop,
+        end.ty(db),
+        elem_ty,
+        &BadSubtermError::new(end.span(db), end.ty(db), elem_ty),
+    );
 
-    // Get the signature.
-    let signature = match function.checked_signature(db) {
-        Ok(signature) => signature,
-        Err(reported) => {
-            for &generic in generics.iter().flatten() {
-                let _ = generic.check_in_env(env).await;
-            }
-            for ast_arg in ast_args {
-                let _ = ast_arg.check_in_env(env, LivePlaces::fixme()).await;
-            }
-            return ExprResult::err(db, reported);
-        }
-    };
-    let input_output = signature.input_output(db);
-
-    // Prepare the substitution for the function.
-    let substitution = match generics {
-        None => {
-            // Easy case: nothing provided by user, just create inference variables for everything.
-            env.existential_substitution(id_span, &input_output.variables)
-        }
+    let next = start.into_temporary(db, &mut temporaries);
+    let end = end.into_temporary(db, &mut temporaries);
 
-        Some(generics) => {
-            // Harder case: user provided generics. Given that we are parsing a call like `a.b()`,
-            // then generic arguments would be `a.b[x, y]()` and therefore correspond to the generics declared on
-            // the function itself. But the `input_output` binder can contain additional parameters from
-            // the class or surrounding scope. Those add'l parameters are instantiated with inference
-            // variables-- and then the user-provided generics are added afterwards.
+    let mut loop_env =
+        env.fork(|log| log.spawn(Location::caller(), TaskDescription::CheckLoopBody));
+    loop_env.enclosing_loop = Some(EnclosingLoop::new(SymTy::unit(db), live_after));
 
-            // Create existential substitution for any other generics.
-            let function_generics = &signature.symbols(db).generic_variables;
-            assert!(input_output.variables.ends_with(function_generics));
-            let outer_variables =
-                &input_output.variables[0..input_output.variables.len() - function_generics.len()];
-            let mut substitution: Vec<SymGenericTerm<'_>> =
-                env.existential_substitution(id_span, outer_variables);
+    let lv = SymVariable::new_local(db, binding.id, binding.span);
+    loop_env.push_program_variable_with_ty(lv, elem_ty);
+    let body = ast_body.check_in_env(&mut loop_env, live_in_loop).await;
+
+    let condition = SymExpr::new(
+        db,
+        span,
+        SymTy::boolean(db),
+        SymExprKind::BinaryOp(SymBinaryOp::LessThan, next.give(db), end.give(db)),
+    );
+    let one = SymExpr::new(
+        db,
+        span,
+        elem_ty,
+        SymExprKind::Primitive(SymLiteral::Integral { bits: 1 }),
+    );
+    let increment = SymExpr::new(
+        db,
+        span,
+        SymTy::unit(db),
+        SymExprKind::Assign {
+            place: next,
+            value: SymExpr::new(
+                db,
+                span,
+                elem_ty,
+                SymExprKind::BinaryOp(SymBinaryOp::Add, next.give(db), one),
+            ),
+        },
+    );
+    let body = SymExpr::new(
+        db,
+        span,
+        body.ty(db),
+        SymExprKind::LetIn {
+            lv,
+            ty: elem_ty,
+            initializer: Some(next.give(db)),
+            body: SymExpr::new(db, span, body.ty(db), SymExprKind::Semi(increment, body)),
+        },
+    );
+
+    ExprResult {
+        temporaries,
+        span,
+        kind: SymExpr::while_loop(db, span, condition, body).into(),
+    }
+}
+
+fn report_outside_loop<'db>(env: &Env<'db>, span: Span<'db>, keyword: &str) -> Reported {
+    let db = env.db();
+    env.report(
+        Diagnostic::error(db, span, format!("`{keyword}` outside of a loop")).label(
+            db,
+            Level::Error,
+            span,
+            format!("I expected `{keyword}` to appear inside of a `loop`, `while`, or `for` loop"),
+        ),
+    )
+}
+
+/// The type of the enum that declares `variant`, given the generic arguments
+/// provided so far. Inference variables are created for any that are missing.
+fn variant_enum_ty<'db>(
+    env: &mut Env<'db>,
+    variant: SymVariant<'db>,
+    mut generics: Vec<SymGenericTerm<'db>>,
+    span: Span<'db>,
+) -> (SymTy<'db>, Vec<SymGenericTerm<'db>>) {
+    let db = env.db();
+    let sym_enum = variant.owner(db);
+    generics.extend(
+        sym_enum
+            .generic_kinds(db)
+            .skip(generics.len())
+            .map(|kind| env.fresh_inference_var_term(kind, span)),
+    );
+    (
+        SymTy::named(db, sym_enum.into(), generics.clone()),
+        generics,
+    )
+}
+
+/// Check a call to an enum variant like `Option.Some(22)`.
+/// The arguments are the fields of the variant's payload, in order.
+#[boxed_async_fn]
+async fn check_variant_call<'db>(
+    env: &mut Env<'db>,
+    live_after: LivePlaces,
+    variant_span: Span<'db>,
+    expr_span: Span<'db>,
+    variant: SymVariant<'db>,
+    generics: Vec<SymGenericTerm<'db>>,
+    ast_args: &SpanVec<'db, AstExpr<'db>>,
+    mut temporaries: Vec<Temporary<'db>>,
+) -> ExprResult<'db> {
+    let db = env.db();
+
+    let (ty, generics) = variant_enum_ty(env, variant, generics, variant_span);
+
+    let fields = variant.fields(db);
+    if fields.len() != ast_args.len() {
+        return ExprResult::err(
+            db,
+            report_variant_arity(db, variant_span, variant, ast_args.len()),
+        );
+    }
+
+    // Field types may refer to `self`, which here is the value being constructed.
+    let self_place = SymPlace::var(
+        db,
+        SymVariable::new(db, SymGenericKind::Place, None, expr_span),
+    );
+
+    let mut field_exprs = vec![];
+    let live_after_args = live_after.after_each(env, &ast_args.values);
+    for ((ast_arg, field), live_after_arg) in ast_args.iter().zip(fields).zip(live_after_args) {
+        let value = ast_arg
+            .check_in_env(env, live_after_arg)
+            .await
+            .into_expr(env, &mut temporaries);
+
+        let field_ty = field
+            .checked_field_ty(db)
+            .substitute(db, &generics)
+            .substitute(db, &[self_place]);
+        env.spawn_require_assignable_type(
+            live_after_arg,
+            value.ty(db),
+            field_ty,
+            &BadSubtermError::new(value.span(db), value.ty(db), field_ty),
+        );
+
+        field_exprs.push(value.into_temporary(db, &mut temporaries).give(db));
+    }
+
+    ExprResult::from_expr(
+        db,
+        SymExpr::new(
+            db,
+            expr_span,
+            ty,
+            SymExprKind::Variant {
+                ty,
+                variant,
+                fields: field_exprs,
+            },
+        ),
+        temporaries,
+    )
+}
+
+/// Check a match expression like `match x { Option.Some(v) => v, Option.None => 0 }`.
+///
+/// The scrutinee is evaluated like any other expression (so a place like `x` is referenced,
+/// not given away) and stored in a temporary. Each arm tests the variant of that temporary
+/// and then gives the fields of the payload to the variables bound by the pattern.
+/// The bound variables therefore carry the permission of the scrutinee.
+#[boxed_async_fn]
+async fn check_match<'db>(
+    env: &mut Env<'db>,
+    live_after: LivePlaces,
+    expr_span: Span<'db>,
+    ast_scrutinee: &AstExpr<'db>,
+    ast_arms: &SpanVec<'db, AstMatchArm<'db>>,
+) -> ExprResult<'db> {
+    let db = env.db();
+    let mut temporaries = vec![];
+
+    let live_after_scrutinee = live_after.before(env, ast_arms);
+    let scrutinee = ast_scrutinee
+        .check_in_env(env, live_after_scrutinee)
+        .await
+        .into_expr(env, &mut temporaries);
+    let scrutinee_ty = scrutinee.ty(db);
+    let scrutinee_place = scrutinee.into_temporary(db, &mut temporaries);
+
+    // Wait until we know which enum is being matched.
+    let (red_ty, owner_perm) = non_infer_lower_bound(env, scrutinee_ty).await;
+    let (sym_enum, generics) = match red_ty {
+        RedTy::Named(SymTyName::Aggregate(aggr), generics) if aggr.is_enum(db) => (aggr, generics),
+        RedTy::Error(reported) => return ExprResult::err(db, reported),
+        _ => {
+            return ExprResult::err(
+                db,
+                report_match_non_enum(env, ast_scrutinee.span, scrutinee_ty),
+            );
+        }
+    };
+
+    let match_ty = if ast_arms.is_empty() {
+        SymTy::never(db)
+    } else {
+        env.fresh_ty_inference_var(expr_span)
+    };
+
+    let mut arms = vec![];
+    let mut covered = vec![];
+    let mut has_catch_all = false;
+    for (index, ast_arm) in ast_arms.iter().enumerate() {
+        // Bindings are only in scope for the body of their arm.
+        let mut arm_env =
+            env.fork(|log| log.spawn(Location::caller(), TaskDescription::CheckMatchArm(index)));
+
+        let pattern = &ast_arm.pattern;
+        let mut bindings = vec![];
+        let condition = match &*pattern.kind {
+            AstPatternKind::Wildcard => {
+                has_catch_all = true;
+                None
+            }
+
+            // A binding like `x` matches anything and gives it the entire value.
+            AstPatternKind::Binding(id) => {
+                has_catch_all = true;
+                let lv = SymVariable::new_local(db, id.id, id.span);
+                bindings.push((lv, scrutinee_ty, Some(scrutinee_place.give(db))));
+                None
+            }
+
+            AstPatternKind::Variant(ast_path, subpatterns) => {
+                let variant = match ast_path.resolve_in(&mut arm_env).await {
+                    Ok(NameResolution {
+                        sym: NameResolutionSym::SymVariant(variant),
+                        ..
+                    }) if variant.owner(db) == sym_enum => Ok(variant),
+                    Ok(name_resolution) => Err(report_not_a_variant_of(
+                        db,
+                        ast_path.span(db),
+                        sym_enum,
+                        &name_resolution,
+                    )),
+                    Err(reported) => Err(reported),
+                };
+
+                let fields = match variant {
+                    Ok(variant) => {
+                        covered.push(variant);
+                        variant.fields(db).as_slice()
+                    }
+                    Err(_) => &[],
+                };
+
+                let subpatterns = subpatterns
+                    .iter()
+                    .flat_map(|s| s.iter())
+                    .collect::<Vec<_>>();
+                let arity_error = match variant {
+                    Ok(variant) if !subpatterns.is_empty() && subpatterns.len() != fields.len() => {
+                        Some(report_variant_arity(
+                            db,
+                            pattern.span,
+                            variant,
+                            subpatterns.len(),
+                        ))
+                    }
+                    _ => None,
+                };
+
+                for (subpattern, index) in subpatterns.into_iter().zip(0..) {
+                    match &*subpattern.kind {
+                        AstPatternKind::Wildcard => (),
+
+                        AstPatternKind::Binding(id) => {
+                            let lv = SymVariable::new_local(db, id.id, id.span);
+                            match (fields.get(index), arity_error) {
+                                (Some(&field), None) => {
+                                    // The field's type with the permission of the scrutinee applied.
+                                    let field_ty = owner_perm.apply_to(
+                                        db,
+                                        field
+                                            .checked_field_ty(db)
+                                            .substitute(db, &generics)
+                                            .substitute(db, &[scrutinee_place.into_sym_place(db)]),
+                                    );
+                                    let field_place = SymPlaceExpr::new(
+                                        db,
+                                        id.span,
+                                        field_ty,
+                                        SymPlaceExprKind::Field(scrutinee_place, field),
+                                    );
+                                    bindings.push((lv, field_ty, Some(field_place.give(db))));
+                                }
+
+                                // Still bind the name so that the body can be checked.
+                                _ => {
+                                    let reported = arity_error.or_else(|| variant.err()).unwrap();
+                                    bindings.push((lv, SymTy::err(db, reported), None));
+                                }
+                            }
+                        }
+
+                        AstPatternKind::Variant(..) => {
+                            report_not_implemented(db, subpattern.span, "nested patterns");
+                        }
+                    }
+                }
+
+                Some(match variant {
+                    Ok(variant) => SymExpr::new(
+                        db,
+                        pattern.span,
+                        SymTy::boolean(db),
+                        SymExprKind::IsVariant {
+                            place: scrutinee_place,
+                            variant,
+                        },
+                    ),
+                    Err(reported) => SymExpr::err(db, reported),
+                })
+            }
+        };
+
+        for &(lv, ty, _) in &bindings {
+            arm_env.push_program_variable_with_ty(lv, ty);
+        }
+
+        let mut body = ast_arm
+            .body
+            .check_in_env(&mut arm_env, live_after)
+            .await
+            .into_expr_with_enclosed_temporaries(&mut arm_env);
+
+        env.spawn_require_assignable_type(
+            live_after,
+            body.ty(db),
+            match_ty,
+            &BadSubtermError::new(body.span(db), body.ty(db), match_ty),
+        );
+
+        for (lv, ty, initializer) in bindings.into_iter().rev() {
+            body = SymExpr::new(
+                db,
+                body.span(db),
+                body.ty(db),
+                SymExprKind::LetIn {
+                    lv,
+                    ty,
+                    initializer,
+                    body,
+                },
+            );
+        }
+
+        arms.push(SymMatchArm { condition, body });
+    }
+
+    if !has_catch_all {
+        let missing = sym_enum
+            .variants(db)
+            .filter(|variant| !covered.contains(variant))
+            .collect::<Vec<_>>();
+        if missing.is_empty() {
+            // Every variant is covered, so if we reach the last arm, it must match.
+            if let Some(last_arm) = arms.last_mut() {
+                last_arm.condition = None;
+            }
+        } else {
+            report_non_exhaustive_match(db, expr_span, sym_enum, &missing);
+        }
+    }
+
+    ExprResult {
+        temporaries,
+        span: expr_span,
+        kind: SymExpr::new(db, expr_span, match_ty, SymExprKind::Match { arms }).into(),
+    }
+}
+
+fn report_variant_arity<'db>(
+    db: &'db dyn crate::Db,
+    span: Span<'db>,
+    variant: SymVariant<'db>,
+    found: usize,
+) -> Reported {
+    let expected = variant.fields(db).len();
+    Diagnostic::error(
+        db,
+        span,
+        format!("expected {expected} fields, found {found}"),
+    )
+    .label(
+        db,
+        Level::Error,
+        span,
+        format!("the variant `{variant}` has {expected} fields but I found {found}"),
+    )
+    .label(
+        db,
+        Level::Info,
+        variant.name_span(db),
+        format!("`{variant}` is declared here"),
+    )
+    .report(db)
+}
+
+fn report_match_non_enum<'db>(
+    env: &Env<'db>,
+    scrutinee_span: Span<'db>,
+    scrutinee_ty: SymTy<'db>,
+) -> Reported {
+    let db = env.db();
+    Diagnostic::error(db, scrutinee_span, "expected an enum".to_string())
+        .label(
+            db,
+            Level::Error,
+            scrutinee_span,
+            format!(
+                "I can only match on enums, but this has type `{}`",
+                env.describe_ty(scrutinee_ty)
+            ),
+        )
+        .report(db)
+}
+
+fn report_not_a_variant_of<'db>(
+    db: &'db dyn crate::Db,
+    path_span: Span<'db>,
+    sym_enum: SymAggregate<'db>,
+    name_resolution: &NameResolution<'db>,
+) -> Reported {
+    Diagnostic::error(db, path_span, format!("expected a variant of `{sym_enum}`"))
+        .label(
+            db,
+            Level::Error,
+            path_span,
+            format!(
+                "I expected to find a variant of `{sym_enum}` but I found {}",
+                name_resolution.describe(db),
+            ),
+        )
+        .report(db)
+}
+
+fn report_non_exhaustive_match<'db>(
+    db: &'db dyn crate::Db,
+    match_span: Span<'db>,
+    sym_enum: SymAggregate<'db>,
+    missing: &[SymVariant<'db>],
+) -> Reported {
+    let missing_names = missing
+        .iter()
+        .map(|variant| format!("`{sym_enum}.{variant}`"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let mut diag = Diagnostic::error(db, match_span, "match is not exhaustive".to_string()).label(
+        db,
+        Level::Error,
+        match_span,
+        format!("I found no arm for {missing_names}"),
+    );
+    for variant in missing {
+        diag = diag.label(
+            db,
+            Level::Info,
+            variant.name_span(db),
+            format!("`{variant}` is declared here"),
+        );
+    }
+    diag.report(db)
+}
+
+fn report_not_constructible<'db>(
+    db: &'db dyn crate::Db,
+    path_span: Span<'db>,
+    name_resolution: &NameResolution<'db>,
+) -> Reported {
+    Diagnostic::error(db, path_span, "expected a struct or class".to_string())
+        .label(
+            db,
+            Level::Error,
+            path_span,
+            format!(
+                "I expected to find a struct or class to construct but I found {}",
+                name_resolution.categorize(db),
+            ),
+        )
+        .report(db)
+}
+
+fn report_unknown_field<'db>(
+    db: &'db dyn crate::Db,
+    sym_aggregate: SymAggregate<'db>,
+    name: SpannedIdentifier<'db>,
+) -> Reported {
+    let id = name.id;
+    Diagnostic::error(db, name.span, format!("no field named `{id}`"))
+        .label(
+            db,
+            Level::Error,
+            name.span,
+            format!("`{sym_aggregate}` does not have a field named `{id}`"),
+        )
+        .label(
+            db,
+            Level::Info,
+            sym_aggregate.name_span(db),
+            format!("`{sym_aggregate}` is declared here"),
+        )
+        .report(db)
+}
+
+fn report_duplicate_field<'db>(
+    db: &'db dyn crate::Db,
+    previous: SpannedIdentifier<'db>,
+    name: SpannedIdentifier<'db>,
+) -> Reported {
+    let id = name.id;
+    Diagnostic::error(db, name.span, format!("field `{id}` given more than once"))
+        .label(
+            db,
+            Level::Error,
+            name.span,
+            format!("I already found a value for the field `{id}`"),
+        )
+        .label(
+            db,
+            Level::Info,
+            previous.span,
+            "the first value is given here".to_string(),
+        )
+        .report(db)
+}
+
+fn report_missing_field<'db>(
+    db: &'db dyn crate::Db,
+    expr_span: Span<'db>,
+    sym_aggregate: SymAggregate<'db>,
+    field: SymField<'db>,
+) -> Reported {
+    let id = field.name(db);
+    Diagnostic::error(db, expr_span, format!("missing field `{id}`"))
+        .label(
+            db,
+            Level::Error,
+            expr_span,
+            format!("I expected a value for the field `{id}` of `{sym_aggregate}`"),
+        )
+        .label(
+            db,
+            Level::Info,
+            field.name_span(db),
+            format!("`{id}` is declared here"),
+        )
+        .report(db)
+}
+
+fn report_no_new_method<'db>(
+    db: &'db dyn crate::Db,
+    class_span: Span<'db>,
+    class_sym: SymAggregate<'db>,
+    resolution: Result<NameResolution<'db>, NameResolution<'db>>,
+) -> Reported {
+    let mut diag = Diagnostic::error(
+        db,
+        class_span,
+        format!("the class `{class_sym}` has no `new` method"),
+    )
+    .label(
+        db,
+        Level::Error,
+        class_span,
+        format!("I could not find a `new` method on the class `{class_sym}`"),
+    );
+
+    match resolution {
+        Ok(name_resolution) => {
+            diag = diag.child(
+                Diagnostic::new(
+                    db,
+                    Level::Note,
+                    class_sym.name_span(db),
+                    "calling a class is equivalent to calling `new`, but `new` is not a method"
+                        .to_string(),
+                )
+                .label(
+                    db,
+                    Level::Note,
+                    name_resolution.span(db).unwrap(),
+                    format!(
+                        "I found a class member named `new` but it is {}, not a method",
+                        name_resolution.categorize(db)
+                    ),
+                ),
+            );
+        }
+        Err(_) => {
+            diag = diag.child(
+                Diagnostic::new(
+                    db,
+                    Level::Note,
+                    class_sym.name_span(db),
+                    format!(
+                        "calling a class is equivalent to calling `new`, but `{class_sym}` does not define a `new` method"
+                    ),
+                )
+                .label(
+                    db,
+                    Level::Note,
+                    class_sym.name_span(db),
+                    "I could not find any class member named `new`".to_string(),
+                ),
+            );
+        }
+    }
+
+    diag.report(db)
+}
+
+#[boxed_async_fn]
+async fn check_function_call<'db>(
+    env: &mut Env<'db>,
+    live_after: LivePlaces,
+    function_span: Span<'db>,
+    expr_span: Span<'db>,
+    function: SymFunction<'db>,
+    ast_args: &SpanVec<'db, AstExpr<'db>>,
+    generics: Vec<SymGenericTerm<'db>>,
+    temporaries: Vec<Temporary<'db>>,
+) -> ExprResult<'db> {
+    let db = env.db();
+
+    env.log("check_function_call", &[&function]);
+    env.log("generics", &[&generics]);
+
+    // Get the signature.
+    let signature = match function.checked_signature(db) {
+        Ok(signature) => signature,
+        Err(reported) => {
+            for ast_arg in ast_args {
+                let _ = ast_arg.check_in_env(env, LivePlaces::fixme()).await;
+            }
+            return ExprResult::err(db, reported);
+        }
+    };
+    let input_output = signature.input_output(db);
+
+    env.log("input_output", &[&input_output]);
+
+    // Create inference variables for any generic arguments not provided.
+    let expected_generics = function.transitive_generic_parameters(db);
+    env.log("expected_generics", &[&expected_generics]);
+    let mut substitution = generics.clone();
+    substitution.extend(
+        expected_generics[generics.len()..]
+            .iter()
+            .map(|&var| env.fresh_inference_var_term(var.kind(db), function_span)),
+    );
+
+    check_call_common(
+        env,
+        live_after,
+        function,
+        expr_span,
+        function_span,
+        input_output,
+        substitution,
+        ast_args,
+        None,
+        temporaries,
+    )
+    .await
+}
+
+/// Check a call like `a.b()` where `b` is a method.
+/// These are somewhat different than calls like `b(a)` because of how
+/// type arguments are handled.
+#[allow(clippy::too_many_arguments)]
+#[boxed_async_fn]
+async fn check_method_call<'db>(
+    env: &mut Env<'db>,
+    live_after: LivePlaces,
+    id_span: Span<'db>,
+    expr_span: Span<'db>,
+    function: SymFunction<'db>,
+    self_expr: Option<SymExpr<'db>>,
+    ast_args: &[AstExpr<'db>],
+    generics: Option<SpanVec<'db, AstGenericTerm<'db>>>,
+    temporaries: Vec<Temporary<'db>>,
+) -> ExprResult<'db> {
+    let db = env.db();
+
+    // Get the signature.
+    let signature = match function.checked_signature(db) {
+        Ok(signature) => signature,
+        Err(reported) => {
+            for &generic in generics.iter().flatten() {
+                let _ = generic.check_in_env(env).await;
+            }
+            for ast_arg in ast_args {
+                let _ = ast_arg.check_in_env(env, LivePlaces::fixme()).await;
+            }
+            return ExprResult::err(db, reported);
+        }
+    };
+    let input_output = signature.input_output(db);
+
+    // Prepare the substitution for the function.
+    let substitution = match generics {
+        None => {
+            // Easy case: nothing provided by user, just create inference variables for everything.
+            env.existential_substitution(id_span, &input_output.variables)
+        }
+
+        Some(generics) => {
+            // Harder case: user provided generics. Given that we are parsing a call like `a.b()`,
+            // then generic arguments would be `a.b[x, y]()` and therefore correspond to the generics declared on
+            // the function itself. But the `input_output` binder can contain additional parameters from
+            // the class or surrounding scope. Those add'l parameters are instantiated with inference
+            // variables-- and then the user-provided generics are added afterwards.
+
+            // Create existential substitution for any other generics.
+            let function_generics = &signature.symbols(db).generic_variables;
+            assert!(input_output.variables.ends_with(function_generics));
+            let outer_variables =
+                &input_output.variables[0..input_output.variables.len() - function_generics.len()];
+            let mut substitution: Vec<SymGenericTerm<'_>> =
+                env.existential_substitution(id_span, outer_variables);
 
             // Check the user gave the expected number of arguments.
             if function_generics.len() != generics.len() {
@@ -1141,11 +2039,365 @@ async fn check_method_call<'db>(
     .await
 }
 
+/// Check a closure like `fn(x: T) -> R { ... }`.
+///
+/// The closure captures each local variable `c` mentioned in its body by reference:
+/// within the body, `c` refers to a new variable of type `ref[c] T` (where `c: T`),
+/// and the closure has type `ref[c, ...] fn(T, ...) -> R`.
+/// A closure that captures nothing has type `fn(T, ...) -> R` and so owns its environment.
+///
+/// Variables cannot yet be captured by `mut` or `given`.
+/// Assigning to a captured variable is an error (see [`captured_root`]);
+/// giving it away gives a copy of the reference.
+#[boxed_async_fn]
+async fn check_closure<'db>(
+    env: &mut Env<'db>,
+    expr_span: Span<'db>,
+    ast_inputs: &SpanVec<'db, VariableDecl<'db>>,
+    ast_output: Option<AstTy<'db>>,
+    ast_body: AstBlock<'db>,
+) -> ExprResult<'db> {
+    let db = env.db();
+
+    // The input and output types are resolved in the enclosing scope.
+    // Unlike parameters of functions, the inputs get no default permission.
+    let mut input_tys = vec![];
+    for decl in ast_inputs.iter() {
+        let base_ty = decl.base_ty(db).check_in_env(env).await;
+        input_tys.push(match decl.perm(db) {
+            Some(ast_perm) => SymTy::perm(db, ast_perm.check_in_env(env).await, base_ty),
+            None => base_ty,
+        });
+    }
+    let output_ty = match ast_output {
+        Some(ast_output) => ast_output.check_in_env(env).await,
+        None => SymTy::unit(db),
+    };
+
+    // Find the local variables mentioned in the body (other than the inputs).
+    // This is conservative: a name bound within the body may also be captured.
+    let mut names = Set::default();
+    ast_body.mentioned_names(env, &mut names);
+    for decl in ast_inputs.iter() {
+        names.remove(&decl.name(db).id);
+    }
+    let mut captured_vars = vec![];
+    for name in names {
+        if let Some(NameResolution {
+            sym: NameResolutionSym::SymVariable(var),
+            ..
+        }) = env.scope.lookup_name(db, name)
+        {
+            if var.kind(db) == SymGenericKind::Place {
+                captured_vars.push(var);
+            }
+        }
+    }
+    captured_vars.sort();
+
+    let mut body_env =
+        env.fork(|log| log.spawn(Location::caller(), TaskDescription::CheckClosureBody));
+    body_env.set_return_ty(output_ty);
+    body_env.enclosing_loop = None;
+    body_env.captures = Default::default();
+
+    let mut captures = vec![];
+    let mut captured_places = vec![];
+    for var in captured_vars {
+        let ty = env.variable_ty(var).await;
+        let place_expr = SymPlaceExpr::new(db, expr_span, ty, SymPlaceExprKind::Var(var));
+        let place = place_expr.into_sym_place(db);
+        let value = SymExpr::new(
+            db,
+            expr_span,
+            ty.referenced(db, place),
+            SymExprKind::PermissionOp(PermissionOp::Reference, place_expr),
+        );
+        let lv = SymVariable::new(db, SymGenericKind::Place, var.name(db), expr_span);
+        body_env.push_program_variable_with_ty(lv, value.ty(db));
+        captures.push(SymCapture { lv, value });
+        captured_places.push(place);
+    }
+    body_env.captures = Arc::new(captures.iter().map(|capture| capture.lv).collect());
+
+    let mut inputs = vec![];
+    for (decl, &input_ty) in ast_inputs.iter().zip(&input_tys) {
+        let lv = decl.symbol(db);
+        body_env.push_program_variable_with_ty(lv, input_ty);
+        inputs.push(lv);
+    }
+
+    let live_after_body = LivePlaces::none(&body_env);
+    let body = ast_body.check_in_env(&mut body_env, live_after_body).await;
+    body_env.spawn_require_assignable_type(
+        live_after_body,
+        body.ty(db),
+        output_ty,
+        &InvalidReturnValue::new(body, output_ty),
+    );
+
+    let function_ty = SymTy::function(db, input_tys.clone(), output_ty);
+    let closure_ty = if captured_places.is_empty() {
+        function_ty
+    } else {
+        SymTy::perm(db, SymPerm::referenced(db, captured_places), function_ty)
+    };
+
+    let closure_expr = SymExpr::new(
+        db,
+        expr_span,
+        closure_ty,
+        SymExprKind::Closure {
+            captures,
+            inputs,
+            input_tys,
+            body,
+        },
+    );
+    ExprResult::from_expr(db, closure_expr, vec![])
+}
+
+/// If `place` is (a field of) a variable holding a value captured by the enclosing closure,
+/// returns that variable.
+fn captured_root<'db>(env: &Env<'db>, mut place: SymPlaceExpr<'db>) -> Option<SymVariable<'db>> {
+    let db = env.db();
+    loop {
+        match *place.kind(db) {
+            SymPlaceExprKind::Var(var) => return env.captures.contains(&var).then_some(var),
+            SymPlaceExprKind::Field(owner, _) | SymPlaceExprKind::TupleElement(owner, _) => {
+                place = owner
+            }
+            SymPlaceExprKind::Error(_) => return None,
+        }
+    }
+}
+
+fn report_assign_to_capture<'db>(
+    env: &Env<'db>,
+    span: Span<'db>,
+    capture: SymVariable<'db>,
+) -> Reported {
+    let db = env.db();
+    env.report(
+        Diagnostic::error(
+            db,
+            span,
+            format!("cannot assign to captured variable `{capture}`"),
+        )
+        .label(
+            db,
+            Level::Error,
+            span,
+            format!("closures capture variables by `ref`, so I cannot assign to `{capture}` here"),
+        )
+        .label(
+            db,
+            Level::Info,
+            capture.span(db),
+            "captured by this closure",
+        ),
+    )
+}
+
+/// Make a function value out of `function`, as when writing `apply(double, 1)`.
+/// This is the same as a closure `fn(x, ...) { function(x, ...) }`, which captures nothing.
+/// Inference variables are created for any generic arguments not provided.
+fn check_function_value<'db>(
+    env: &mut Env<'db>,
+    span: Span<'db>,
+    function: SymFunction<'db>,
+    generics: Vec<SymGenericTerm<'db>>,
+) -> SymExpr<'db> {
+    let db = env.db();
+
+    let signature = match function.checked_signature(db) {
+        Ok(signature) => signature,
+        Err(reported) => return SymExpr::err(db, reported),
+    };
+
+    let expected_generics = function.transitive_generic_parameters(db);
+    let mut substitution = generics;
+    substitution.extend(
+        expected_generics[substitution.len()..]
+            .iter()
+            .map(|&var| env.fresh_inference_var_term(var.kind(db), span)),
+    );
+    let input_output = signature.input_output(db).substitute(db, &substitution);
+
+    // The inputs of the closure are passed along as the arguments.
+    // A function type cannot express types that refer to other parameters,
+    // like `fn f(s: String, t: ref[s] String)`.
+    let inputs: Vec<SymVariable<'db>> = input_output
+        .variables
+        .iter()
+        .map(|var| SymVariable::new(db, SymGenericKind::Place, var.name(db), span))
+        .collect();
+    let mut refers_to_inputs = false;
+    let input_output: SymInputOutput<'db> = input_output.open(db, |index| {
+        refers_to_inputs = true;
+        SymGenericTerm::var(db, inputs[index])
+    });
+    if refers_to_inputs {
+        return SymExpr::err(db, report_dependent_function_value(env, span, function));
+    }
+
+    for where_clause in input_output.where_clauses {
+        env.spawn_require_where_clause(where_clause, &WhereClauseError::new(span, where_clause));
+    }
+
+    let body = SymExpr::new(
+        db,
+        span,
+        input_output.output_ty,
+        SymExprKind::Call {
+            function,
+            substitution,
+            arg_temps: inputs.clone(),
+        },
+    );
+    SymExpr::new(
+        db,
+        span,
+        SymTy::function(db, input_output.input_tys.clone(), input_output.output_ty),
+        SymExprKind::Closure {
+            captures: vec![],
+            inputs,
+            input_tys: input_output.input_tys,
+            body,
+        },
+    )
+}
+
+fn report_dependent_function_value<'db>(
+    env: &Env<'db>,
+    span: Span<'db>,
+    function: SymFunction<'db>,
+) -> Reported {
+    let db = env.db();
+    let name = function.name(db);
+    env.report(
+        Diagnostic::error(db, span, format!("cannot use `{name}` as a value"))
+            .label(
+                db,
+                Level::Error,
+                span,
+                format!(
+                    "the signature of `{name}` refers to its own parameters, which a function type cannot express"
+                ),
+            )
+            .label(db, Level::Info, function.name_span(db), format!("`{name}` defined here")),
+    )
+}
+
+/// Check a call like `f(a, b)` where `f` is a value of function type.
+#[boxed_async_fn]
+async fn check_indirect_call<'db>(
+    env: &mut Env<'db>,
+    live_after_args: LivePlaces,
+    expr_span: Span<'db>,
+    callee: ExprResult<'db>,
+    ast_args: &SpanVec<'db, AstExpr<'db>>,
+) -> ExprResult<'db> {
+    let db = env.db();
+    let callee_span = callee.span;
+
+    let mut temporaries = vec![];
+    let callee = callee.into_place_expr(env, &mut temporaries);
+    let callee_ty = callee.ty(db);
+
+    // Wait until we know the type of function being called.
+    // The inputs and output are relative, so the permission of the callee does not affect them.
+    let (red_ty, _callee_perm) = non_infer_lower_bound(env, callee_ty).await;
+    let (input_tys, output_ty) = match red_ty {
+        RedTy::Named(SymTyName::Function { arity }, generics) => {
+            let tys: Vec<SymTy<'db>> = generics.iter().map(|g| g.assert_type(db)).collect();
+            (tys[..arity].to_vec(), tys[arity])
+        }
+        RedTy::Error(reported) => return ExprResult::err(db, reported),
+        _ => return ExprResult::err(db, report_not_callable(db, callee_span)),
+    };
+
+    // Check the arity of the actual arguments.
+    let expected_inputs = input_tys.len();
+    let found_inputs = ast_args.len();
+    if found_inputs != expected_inputs {
+        return ExprResult::err(
+            db,
+            env.report(
+                Diagnostic::error(
+                    db,
+                    callee_span,
+                    format!("expected {expected_inputs} arguments, found {found_inputs}"),
+                )
+                .label(
+                    db,
+                    Level::Error,
+                    callee_span,
+                    format!(
+                        "I expected this function to take {expected_inputs} arguments but I found {found_inputs}"
+                    ),
+                )
+                .label(
+                    db,
+                    Level::Info,
+                    callee_span,
+                    format!("this has type `{}`", env.describe_ty(callee_ty)),
+                ),
+            ),
+        );
+    }
+
+    // Function to type check a single argument and check it has the correct type.
+    let check_arg = async |i: usize| -> ExprResult<'db> {
+        let mut env = env.fork(|log| log.spawn(Location::caller(), TaskDescription::CheckArg(i)));
+
+        // The arguments after this one are evaluated before the call.
+        let live_after_arg = live_after_args.before(&env, &ast_args[i + 1..]);
+
+        let mut arg_temporaries = vec![];
+        let expr = ast_args[i]
+            .check_in_env(&mut env, live_after_arg)
+            .await
+            .into_expr(&mut env, &mut arg_temporaries);
+        env.spawn_require_assignable_type(
+            live_after_arg,
+            expr.ty(db),
+            input_tys[i],
+            &BadSubtermError::new(expr.span(db), expr.ty(db), input_tys[i]),
+        );
+        ExprResult::from_expr(env.db(), expr, arg_temporaries)
+    };
+
+    // Type check the arguments; these can proceed concurrently.
+    let mut arg_exprs = vec![];
+    for arg_result in futures::future::join_all((0..found_inputs).map(check_arg)).await {
+        arg_exprs.push(arg_result.into_expr(env, &mut temporaries));
+    }
+
+    // Like other calls, this becomes `let tmp1 = arg1 in ... call(tmp1, ...)`.
+    let arg_temp_symbols = ast_args
+        .iter()
+        .map(|ast_arg| SymVariable::new(db, SymGenericKind::Place, None, ast_arg.span))
+        .collect::<Vec<_>>();
+    let call_expr = SymExpr::new(
+        db,
+        expr_span,
+        output_ty,
+        SymExprKind::CallIndirect {
+            callee,
+            arg_temps: arg_temp_symbols.clone(),
+        },
+    );
+    let call_expr = bind_arg_temps(db, call_expr, arg_temp_symbols, arg_exprs);
+
+    ExprResult::from_expr(env.db(), call_expr, temporaries)
+}
+
 #[allow(clippy::too_many_arguments)]
 #[boxed_async_fn]
 async fn check_call_common<'db>(
     env: &mut Env<'db>,
-    _live_after: LivePlaces,
+    live_after: LivePlaces,
     function: SymFunction<'db>,
     expr_span: Span<'db>,
     callee_span: Span<'db>,
@@ -1222,18 +2474,23 @@ async fn check_call_common<'db>(
     // Function to type check a single argument and check it has the correct type.
     let check_arg = async |i: usize| -> ExprResult<'db> {
         let mut env = env.fork(|log| log.spawn(Location::caller(), TaskDescription::CheckArg(i)));
+
+        // The arguments after this one are evaluated before the call.
+        let live_after_arg =
+            live_after.before(&env, &ast_args[(i + 1).saturating_sub(self_args)..]);
+
         let mut arg_temporaries = vec![];
         let expr = if i < self_args {
             self_expr.unwrap()
         } else {
             let ast_arg = &ast_args[i - self_args];
             ast_arg
-                .check_in_env(&mut env, LivePlaces::fixme())
+                .check_in_env(&mut env, live_after_arg)
                 .await
                 .into_expr(&mut env, &mut arg_temporaries)
         };
         env.spawn_require_assignable_type(
-            LivePlaces::fixme(),
+            live_after_arg,
             expr.ty(db),
             input_output.input_tys[i],
             &BadSubtermError::new(expr.span(db), expr.ty(db), input_output.input_tys[i]),
@@ -1256,13 +2513,8 @@ async fn check_call_common<'db>(
         arg_exprs.push(arg_result.into_expr(env, &mut temporaries));
     }
 
-    // Create the resulting call, which always looks like
-    //
-    //     let tmp1 = arg1 in
-    //     let tmp2 = arg2 in
-    //     ...
-    //     call(tmp1, tmp2, ...)
-    let mut call_expr = SymExpr::new(
+    // Create the resulting call.
+    let call_expr = SymExpr::new(
         db,
         expr_span,
         input_output.output_ty,
@@ -1272,6 +2524,26 @@ async fn check_call_common<'db>(
             arg_temps: arg_temp_symbols.clone(),
         },
     );
+    let call_expr = bind_arg_temps(db, call_expr, arg_temp_symbols, arg_exprs);
+
+    // Create the final result.
+    ExprResult::from_expr(env.db(), call_expr, temporaries)
+}
+
+/// Wrap `call_expr` so that each argument is stored into its temporary first:
+///
+/// ```text
+/// let tmp1 = arg1 in
+/// let tmp2 = arg2 in
+/// ...
+/// call(tmp1, tmp2, ...)
+/// ```
+fn bind_arg_temps<'db>(
+    db: &'db dyn crate::Db,
+    mut call_expr: SymExpr<'db>,
+    arg_temp_symbols: Vec<SymVariable<'db>>,
+    arg_exprs: Vec<SymExpr<'db>>,
+) -> SymExpr<'db> {
     for (arg_temp_symbol, arg_expr) in arg_temp_symbols
         .into_iter()
         .rev()
@@ -1289,9 +2561,7 @@ async fn check_call_common<'db>(
             },
         );
     }
-
-    // Create the final result.
-    ExprResult::from_expr(env.db(), call_expr, temporaries)
+    call_expr
 }
 
 impl<'db> Err<'db> for ExprResult<'db> {
@@ -1323,12 +2593,31 @@ impl<'db> ExprResult<'db> {
                 }
             }
 
-            // FIXME: Should functions be expressions?
+            // A variant without a payload, like `Option.None`, is a value of the enum.
+            NameResolutionSym::SymVariant(variant) if !variant.has_payload(db) => {
+                let (ty, _) = variant_enum_ty(env, variant, res.generics, span);
+                let expr = SymExpr::new(
+                    db,
+                    span,
+                    ty,
+                    SymExprKind::Variant {
+                        ty,
+                        variant,
+                        fields: vec![],
+                    },
+                );
+                Self::from_expr(db, expr, vec![])
+            }
+
+            // A function is called directly or becomes a function value
+            // once it is used as an expression (see `check_function_value`).
             NameResolutionSym::SymFunction(_)
             | NameResolutionSym::SymModule(_)
             | NameResolutionSym::SymAggregate(_)
+            | NameResolutionSym::SymTrait(_)
             | NameResolutionSym::SymPrimitive(_)
-            | NameResolutionSym::SymVariable(..) => Self {
+            | NameResolutionSym::SymVariable(..)
+            | NameResolutionSym::SymVariant(_) => Self {
                 temporaries: vec![],
                 span,
                 kind: ExprResultKind::Other(res),
@@ -1416,6 +2705,12 @@ impl<'db> ExprResult<'db> {
             // This is a value that needs to be stored in a temporary.
             ExprResultKind::Expr(expr) => expr.into_temporary(db, temporaries),
 
+            ExprResultKind::Other(NameResolution {
+                generics,
+                sym: NameResolutionSym::SymFunction(function),
+            }) => check_function_value(env, self.span, function, generics)
+                .into_temporary(db, temporaries),
+
             ExprResultKind::Other(name_resolution) => {
                 let reported = report_non_expr(db, self.span, &name_resolution);
                 SymPlaceExpr::err(db, reported)
@@ -1451,6 +2746,12 @@ impl<'db> ExprResult<'db> {
                 )
             }
 
+            // A function like `double` in `apply(double, 1)` is a function value.
+            ExprResultKind::Other(NameResolution {
+                generics,
+                sym: NameResolutionSym::SymFunction(function),
+            }) => check_function_value(env, self.span, function, generics),
+
             ExprResultKind::Other(name_resolution) => {
                 SymExpr::err(db, report_non_expr(db, self.span, &name_resolution))
             }
@@ -101,6 +101,7 @@ fn check_function_body_class_constructor<'db>(
                 SymExprKind::Aggregate {
                     ty: self_ty,
                     fields: parameter_exprs.chain(other_exprs).collect(),
+                    labels: vec![None; fields.len()],
                 },
             )
         },
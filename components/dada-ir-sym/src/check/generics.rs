@@ -1,8 +1,19 @@
-use dada_ir_ast::ast::{AstWhereClause, AstWhereClauseKind};
+use dada_ir_ast::{
+    ast::{AstPath, AstWhereClause, AstWhereClauseKind},
+    diagnostic::Errors,
+    span::Spanned,
+};
 
-use crate::ir::generics::{SymWhereClause, SymWhereClauseKind};
+use crate::ir::{
+    generics::{SymWhereClause, SymWhereClauseKind},
+    traits::{SymTrait, report_not_a_trait},
+};
 
-use super::{CheckTyInEnv, env::Env};
+use super::{
+    CheckTyInEnv,
+    env::Env,
+    scope::{NameResolution, NameResolutionSym, Resolve},
+};
 
 pub async fn symbolify_ast_where_clause<'db>(
     env: &mut Env<'db>,
@@ -12,7 +23,7 @@ pub async fn symbolify_ast_where_clause<'db>(
     let db = env.db();
     let subject = ast_where_clause.subject(db).check_in_env(env).await;
     let mut push_kind =
-        |kind: SymWhereClauseKind| output.push(SymWhereClause::new(db, subject, kind));
+        |kind: SymWhereClauseKind<'db>| output.push(SymWhereClause::new(db, subject, kind));
 
     for kind in ast_where_clause.kinds(db) {
         match kind {
@@ -36,6 +47,24 @@ pub async fn symbolify_ast_where_clause<'db>(
             AstWhereClauseKind::Unique(_) => {
                 push_kind(SymWhereClauseKind::Unique);
             }
+            AstWhereClauseKind::Trait(path) => {
+                // Errors are reported during resolution; the clause is then dropped.
+                if let Ok(sym_trait) = resolve_trait(env, *path).await {
+                    push_kind(SymWhereClauseKind::Trait(sym_trait));
+                }
+            }
         }
     }
 }
+
+/// Resolve `path`, which must name a trait.
+async fn resolve_trait<'db>(env: &mut Env<'db>, path: AstPath<'db>) -> Errors<SymTrait<'db>> {
+    let db = env.db();
+    match path.resolve_in(env).await? {
+        NameResolution {
+            sym: NameResolutionSym::SymTrait(sym_trait),
+            ..
+        } => Ok(sym_trait),
+        name_resolution => Err(report_not_a_trait(db, path.span(db), name_resolution.sym)),
+    }
+}
@@ -1,31 +1,345 @@
-use crate::ir::types::SymPlace;
+use std::collections::BTreeSet;
+
+use dada_ir_ast::ast::{
+    AstBlock, AstConstructorField, AstExpr, AstExprKind, AstGenericTerm, AstMatchArm, AstPath,
+    AstPathKind, AstPerm, AstPermKind, AstStatement, AstTy, AstTyKind, Identifier, IfArm, SpanVec,
+    VariableDecl,
+};
+use dada_parser::prelude::SquareBracketArgs;
+use dada_util::Set;
+use serde::Serialize;
+
+use crate::ir::types::{SymPlace, SymPlaceKind};
 
 use super::env::Env;
 
-/// Placeholder for the liveness computation we will be doing
-#[derive(Copy, Clone)]
-pub struct LivePlaces {}
+/// The places that may be used after some point in a function body.
+///
+/// Liveness is computed by looking at the code that may execute afterwards:
+/// a local variable is live if that code mentions its name.
+/// Because we compare names and not variables, this is conservative:
+/// a variable is considered live if some other variable with the same name is used later.
+///
+/// The names mentioned by each expression are computed once per function body
+/// and cached in the runtime (see [`MentionsNames`]).
+/// The sets of live names are interned in the runtime
+/// so that `LivePlaces` can be freely copied
+/// and two `LivePlaces` are equal exactly when they have the same live names.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize)]
+pub struct LivePlaces {
+    /// Index of the set of live names in the runtime.
+    /// If `None`, all places are considered live.
+    names: Option<usize>,
+}
+
+/// A set of live names, as interned in the runtime.
+pub type LiveNames<'db> = BTreeSet<Identifier<'db>>;
 
-#[expect(unused_variables)]
 impl LivePlaces {
     /// Assume no places are live.
     pub fn none<'db>(env: &Env<'db>) -> Self {
-        Self {}
+        Self {
+            names: Some(env.runtime().insert_live_names(LiveNames::default())),
+        }
     }
 
     /// Special placeholder for when we relate bounds on inference variables.
     /// For permissions, these bounds are [`RedPerm`](`crate::check::red::RedPerm`)
     /// values and already contain liveness information.
     pub fn infer_bounds() -> Self {
-        Self {}
+        Self { names: None }
     }
 
-    /// Used where we have to think about the right value
+    /// Used where we have to think about the right value.
+    /// Conservatively considers all places to be live.
     pub fn fixme() -> Self {
-        Self {}
+        Self { names: None }
+    }
+
+    /// The places live before executing `code`, given that `self` are live after it.
+    pub fn before<'db>(self, env: &Env<'db>, code: &(impl MentionsNames<'db> + ?Sized)) -> Self {
+        let Some(index) = self.names else {
+            return self;
+        };
+
+        let mut mentioned = Set::default();
+        code.mentioned_names(env, &mut mentioned);
+
+        let live = env.runtime().live_names(index);
+        if mentioned.iter().all(|name| live.contains(name)) {
+            return self;
+        }
+
+        let mut names = LiveNames::clone(&live);
+        names.extend(mentioned);
+        Self {
+            names: Some(env.runtime().insert_live_names(names)),
+        }
+    }
+
+    /// For each element of `code`, which execute in order, the places live after that element,
+    /// given that `self` are live after all of `code`.
+    pub fn after_each<'db, T: MentionsNames<'db>>(self, env: &Env<'db>, code: &[T]) -> Vec<Self> {
+        let mut live = self;
+        let mut live_after_each: Vec<Self> = code
+            .iter()
+            .rev()
+            .map(|element| {
+                let live_after = live;
+                live = live.before(env, element);
+                live_after
+            })
+            .collect();
+        live_after_each.reverse();
+        live_after_each
     }
 
     pub fn is_live<'db>(&self, env: &Env<'db>, place: SymPlace<'db>) -> bool {
-        true // FIXME
+        let Some(index) = self.names else {
+            return true;
+        };
+
+        match root_variable_name(env.db(), place) {
+            Some(name) => env.runtime().live_names(index).contains(&name),
+
+            // Temporaries have no name, so we cannot tell when they are used.
+            None => true,
+        }
+    }
+}
+
+/// The name of the local variable that `place` is found in, if any.
+fn root_variable_name<'db>(
+    db: &'db dyn crate::Db,
+    place: SymPlace<'db>,
+) -> Option<Identifier<'db>> {
+    match *place.kind(db) {
+        SymPlaceKind::Var(var) => var.name(db),
+        SymPlaceKind::Field(owner, _)
+        | SymPlaceKind::TupleElement(owner, _)
+        | SymPlaceKind::Index(owner) => root_variable_name(db, owner),
+        SymPlaceKind::Erased | SymPlaceKind::Error(_) => None,
+    }
+}
+
+/// Syntax that may refer to local variables by name.
+///
+/// The names mentioned by an expression are cached in the runtime, keyed by its span.
+/// Code with the same span has the same text, and so mentions the same names.
+pub trait MentionsNames<'db> {
+    /// Insert the name of each variable that `self` may refer to into `names`.
+    fn mentioned_names(&self, env: &Env<'db>, names: &mut Set<Identifier<'db>>);
+}
+
+impl<'db, T: MentionsNames<'db>> MentionsNames<'db> for [T] {
+    fn mentioned_names(&self, env: &Env<'db>, names: &mut Set<Identifier<'db>>) {
+        for element in self {
+            element.mentioned_names(env, names);
+        }
+    }
+}
+
+impl<'db, T: MentionsNames<'db> + salsa::Update> MentionsNames<'db> for SpanVec<'db, T> {
+    fn mentioned_names(&self, env: &Env<'db>, names: &mut Set<Identifier<'db>>) {
+        self.values.mentioned_names(env, names)
+    }
+}
+
+impl<'db, T: MentionsNames<'db>> MentionsNames<'db> for Option<T> {
+    fn mentioned_names(&self, env: &Env<'db>, names: &mut Set<Identifier<'db>>) {
+        if let Some(value) = self {
+            value.mentioned_names(env, names)
+        }
+    }
+}
+
+impl<'db> MentionsNames<'db> for AstBlock<'db> {
+    fn mentioned_names(&self, env: &Env<'db>, names: &mut Set<Identifier<'db>>) {
+        let db = env.db();
+        self.statements(db).mentioned_names(env, names)
+    }
+}
+
+impl<'db> MentionsNames<'db> for AstStatement<'db> {
+    fn mentioned_names(&self, env: &Env<'db>, names: &mut Set<Identifier<'db>>) {
+        let db = env.db();
+        match self {
+            AstStatement::Let(s) => {
+                s.ty(db).mentioned_names(env, names);
+                s.initializer(db).mentioned_names(env, names);
+            }
+            AstStatement::Expr(e) => e.mentioned_names(env, names),
+        }
+    }
+}
+
+impl<'db> MentionsNames<'db> for AstExpr<'db> {
+    fn mentioned_names(&self, env: &Env<'db>, names: &mut Set<Identifier<'db>>) {
+        let mentioned = env
+            .runtime()
+            .mentioned_names(self.span, |names| self.mentioned_names_uncached(env, names));
+        names.extend(mentioned.iter().copied());
+    }
+}
+
+impl<'db> AstExpr<'db> {
+    fn mentioned_names_uncached(&self, env: &Env<'db>, names: &mut Set<Identifier<'db>>) {
+        let db = env.db();
+        match &*self.kind {
+            AstExprKind::Block(block) => block.mentioned_names(env, names),
+            AstExprKind::Literal(_) => (),
+            AstExprKind::Id(id) => {
+                names.insert(id.id);
+            }
+            AstExprKind::DotId(owner, _) => owner.mentioned_names(env, names),
+            AstExprKind::SquareBracketOp(owner, args) => {
+                owner.mentioned_names(env, names);
+                args.parse_as_generics(db).mentioned_names(env, names);
+            }
+            AstExprKind::ParenthesisOp(owner, args) => {
+                owner.mentioned_names(env, names);
+                args.mentioned_names(env, names);
+            }
+            AstExprKind::Tuple(elements) => elements.mentioned_names(env, names),
+            AstExprKind::Constructor(path, fields) => {
+                path.mentioned_names(env, names);
+                fields.mentioned_names(env, names);
+            }
+            AstExprKind::Return(value) => value.mentioned_names(env, names),
+            AstExprKind::Await {
+                future,
+                await_keyword: _,
+            } => future.mentioned_names(env, names),
+            AstExprKind::PermissionOp { value, op: _ } => value.mentioned_names(env, names),
+            AstExprKind::BinaryOp(_, lhs, rhs) => {
+                lhs.mentioned_names(env, names);
+                rhs.mentioned_names(env, names);
+            }
+            AstExprKind::UnaryOp(_, operand) => operand.mentioned_names(env, names),
+            AstExprKind::If(arms) => arms.mentioned_names(env, names),
+            AstExprKind::Match(scrutinee, arms) => {
+                scrutinee.mentioned_names(env, names);
+                arms.mentioned_names(env, names);
+            }
+            AstExprKind::Loop(body) => body.mentioned_names(env, names),
+            AstExprKind::While(condition, body) => {
+                condition.mentioned_names(env, names);
+                body.mentioned_names(env, names);
+            }
+            AstExprKind::For {
+                binding: _,
+                start,
+                end,
+                body,
+            } => {
+                start.mentioned_names(env, names);
+                end.mentioned_names(env, names);
+                body.mentioned_names(env, names);
+            }
+            AstExprKind::Break(value) => value.mentioned_names(env, names),
+            AstExprKind::Continue => (),
+            AstExprKind::Closure {
+                inputs,
+                output,
+                body,
+            } => {
+                inputs.mentioned_names(env, names);
+                output.mentioned_names(env, names);
+                body.mentioned_names(env, names);
+            }
+        }
+    }
+}
+
+impl<'db> MentionsNames<'db> for AstConstructorField<'db> {
+    fn mentioned_names(&self, env: &Env<'db>, names: &mut Set<Identifier<'db>>) {
+        self.value.mentioned_names(env, names)
+    }
+}
+
+impl<'db> MentionsNames<'db> for IfArm<'db> {
+    fn mentioned_names(&self, env: &Env<'db>, names: &mut Set<Identifier<'db>>) {
+        self.condition.mentioned_names(env, names);
+        self.result.mentioned_names(env, names);
+    }
+}
+
+impl<'db> MentionsNames<'db> for AstMatchArm<'db> {
+    fn mentioned_names(&self, env: &Env<'db>, names: &mut Set<Identifier<'db>>) {
+        // Patterns only name variants and introduce new variables.
+        self.body.mentioned_names(env, names)
+    }
+}
+
+impl<'db> MentionsNames<'db> for AstTy<'db> {
+    fn mentioned_names(&self, env: &Env<'db>, names: &mut Set<Identifier<'db>>) {
+        let db = env.db();
+        match self.kind(db) {
+            AstTyKind::Perm(perm, ty) => {
+                perm.mentioned_names(env, names);
+                ty.mentioned_names(env, names);
+            }
+            AstTyKind::Named(path, args) => {
+                path.mentioned_names(env, names);
+                args.mentioned_names(env, names);
+            }
+            AstTyKind::GenericDecl(_) => (),
+            AstTyKind::Tuple(elements) => elements.mentioned_names(env, names),
+            AstTyKind::Function(inputs, output) => {
+                inputs.mentioned_names(env, names);
+                output.mentioned_names(env, names);
+            }
+        }
+    }
+}
+
+impl<'db> MentionsNames<'db> for VariableDecl<'db> {
+    fn mentioned_names(&self, env: &Env<'db>, names: &mut Set<Identifier<'db>>) {
+        let db = env.db();
+        self.perm(db).mentioned_names(env, names);
+        self.base_ty(db).mentioned_names(env, names);
+    }
+}
+
+impl<'db> MentionsNames<'db> for AstPerm<'db> {
+    fn mentioned_names(&self, env: &Env<'db>, names: &mut Set<Identifier<'db>>) {
+        let db = env.db();
+        match self.kind(db) {
+            AstPermKind::Referenced(places)
+            | AstPermKind::Mutable(places)
+            | AstPermKind::Given(places) => places.mentioned_names(env, names),
+            AstPermKind::Variable(id) => {
+                names.insert(id.id);
+            }
+            AstPermKind::My | AstPermKind::Our | AstPermKind::GenericDecl(_) => (),
+        }
+    }
+}
+
+impl<'db> MentionsNames<'db> for AstPath<'db> {
+    fn mentioned_names(&self, env: &Env<'db>, names: &mut Set<Identifier<'db>>) {
+        let db = env.db();
+        match self.kind(db) {
+            AstPathKind::Identifier(id) => {
+                names.insert(id.id);
+            }
+            AstPathKind::GenericArgs { path, args } => {
+                path.mentioned_names(env, names);
+                args.mentioned_names(env, names);
+            }
+            AstPathKind::Member { path, id: _ } => path.mentioned_names(env, names),
+        }
+    }
+}
+
+impl<'db> MentionsNames<'db> for AstGenericTerm<'db> {
+    fn mentioned_names(&self, env: &Env<'db>, names: &mut Set<Identifier<'db>>) {
+        match self {
+            AstGenericTerm::Ty(ty) => ty.mentioned_names(env, names),
+            AstGenericTerm::Perm(perm) => perm.mentioned_names(env, names),
+            AstGenericTerm::Id(id) => {
+                names.insert(id.id);
+            }
+        }
     }
 }
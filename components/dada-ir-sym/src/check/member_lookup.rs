@@ -1,8 +1,10 @@
 use crate::ir::{
     binder::Binder,
     classes::{SymAggregate, SymClassMember, SymField},
-    functions::SymFunction,
-    types::{SymGenericTerm, SymPerm, SymTy, SymTyName},
+    functions::{SymFunction, SymFunctionSource},
+    traits::SymTrait,
+    types::{SymGenericTerm, SymPerm, SymTy, SymTyKind, SymTyName},
+    variables::SymVariable,
 };
 use dada_ir_ast::{
     ast::{Identifier, SpannedIdentifier},
@@ -85,7 +87,24 @@ impl<'member, 'db> MemberLookup<'member, 'db> {
                 );
                 ExprResult::from_place_expr(db, place_expr, temporaries)
             }
-            SearchResult::Method { owner: _, method } => {
+            SearchResult::TupleElement { index, element_ty } => {
+                let mut temporaries = vec![];
+
+                // The type of the element is the type from the tuple
+                // with the permission from the owner applied
+                let owner_place_expr = owner.into_place_expr(self.env, &mut temporaries);
+                let element_ty_with_perm = owner_perm.apply_to(db, element_ty);
+
+                let place_expr = SymPlaceExpr::new(
+                    db,
+                    id.span,
+                    element_ty_with_perm,
+                    SymPlaceExprKind::TupleElement(owner_place_expr, index),
+                );
+                ExprResult::from_place_expr(db, place_expr, temporaries)
+            }
+            SearchResult::Method { owner: _, method }
+            | SearchResult::TraitMethod { owner: _, method } => {
                 let mut temporaries = vec![];
                 let owner = owner.into_expr(self.env, &mut temporaries);
                 ExprResult {
@@ -150,22 +169,49 @@ impl<'member, 'db> MemberLookup<'member, 'db> {
                 SymTyName::Primitive(_) => Ok(None),
 
                 // Tuples have indexed members, not named ones.
-                SymTyName::Tuple { arity: _ } => Ok(None),
+                SymTyName::Tuple { arity: _ } => Ok(self.search_tuple_for_member(generics, id)),
 
                 // Classes have members.
                 SymTyName::Aggregate(owner) => self.search_aggr_for_member(owner, generics, id),
 
-                // Future types have no members.
-                SymTyName::Future => Ok(None),
+                // Future and function types have no members.
+                SymTyName::Future | SymTyName::Function { arity: _ } => Ok(None),
             },
             RedTy::Error(reported) => Err(reported),
             RedTy::Never => Ok(None),
             RedTy::Infer(_) => panic!("did not expect inference variable"),
-            RedTy::Var(_) => Ok(None),
+            RedTy::Var(var) => Ok(self.search_var_for_member(var, id)),
             RedTy::Perm => panic!("did not expect permission red-ty"),
         }
     }
 
+    /// Tuple elements are named by their index (e.g., `pair.0`).
+    fn search_tuple_for_member(
+        &mut self,
+        generics: &[SymGenericTerm<'db>],
+        id: Identifier<'db>,
+    ) -> Option<SearchResult<'db>> {
+        let db = self.env.db();
+        let index = id.text(db).parse::<usize>().ok()?;
+        let element_ty = generics.get(index)?.assert_type(db);
+        Some(SearchResult::TupleElement { index, element_ty })
+    }
+
+    /// Generic types have the methods of the traits they are declared to implement
+    /// (e.g., `where T is Show`).
+    fn search_var_for_member(
+        &mut self,
+        var: SymVariable<'db>,
+        id: Identifier<'db>,
+    ) -> Option<SearchResult<'db>> {
+        let db = self.env.db();
+        self.env.declared_traits(var).find_map(|owner| {
+            let method = owner.method_named(db, id)?;
+            debug!("found trait method", owner, method);
+            Some(SearchResult::TraitMethod { owner, method })
+        })
+    }
+
     fn search_aggr_for_member(
         &mut self,
         owner: SymAggregate<'db>,
@@ -198,6 +244,9 @@ impl<'member, 'db> MemberLookup<'member, 'db> {
                         debug!("found method with wrong name", method.name(db));
                     }
                 }
+
+                // Variants are named through the enum (`Option.Some`), not through values.
+                SymClassMember::SymVariant(_) => {}
             }
         }
 
@@ -205,6 +254,38 @@ impl<'member, 'db> MemberLookup<'member, 'db> {
     }
 }
 
+/// Returns the fields and methods that [`MemberLookup`] can find on a value of type `ty`
+/// (e.g., to offer as completions); constructors and variants are omitted.
+/// Like lookup, this looks through permissions.
+///
+/// Unlike lookup, this works without an environment, so it does not include
+/// the trait methods of generic types and `ty` must not contain inference variables.
+pub(crate) fn value_members<'db>(
+    db: &'db dyn crate::Db,
+    ty: SymTy<'db>,
+) -> Vec<SymClassMember<'db>> {
+    match *ty.kind(db) {
+        SymTyKind::Perm(_, ty) => value_members(db, ty),
+        SymTyKind::Named(SymTyName::Aggregate(owner), _) => owner
+            .members(db)
+            .iter()
+            .copied()
+            .filter(|member| match member {
+                SymClassMember::SymField(_) => true,
+                SymClassMember::SymFunction(function) => {
+                    !matches!(function.source(db), SymFunctionSource::Constructor(..))
+                }
+                SymClassMember::SymVariant(_) => false,
+            })
+            .collect(),
+        SymTyKind::Named(..)
+        | SymTyKind::Infer(_)
+        | SymTyKind::Var(_)
+        | SymTyKind::Never
+        | SymTyKind::Error(_) => vec![],
+    }
+}
+
 #[derive(Clone, PartialEq, Eq)]
 enum SearchResult<'db> {
     Field {
@@ -216,6 +297,14 @@ enum SearchResult<'db> {
         owner: SymAggregate<'db>,
         method: SymFunction<'db>,
     },
+    TraitMethod {
+        owner: SymTrait<'db>,
+        method: SymFunction<'db>,
+    },
+    TupleElement {
+        index: usize,
+        element_ty: SymTy<'db>,
+    },
 }
 
 /// Convert `ty` to a [`RedTy`][]; if the result is an inference variable,
@@ -224,7 +313,7 @@ enum SearchResult<'db> {
 /// # Returns
 ///
 /// A [`RedTy`][] that is a lower bound for `ty` and which is not an inference variable.
-async fn non_infer_lower_bound<'db>(
+pub(crate) async fn non_infer_lower_bound<'db>(
     env: &mut Env<'db>,
     ty: SymTy<'db>,
 ) -> (RedTy<'db>, SymPerm<'db>) {
@@ -5,7 +5,7 @@ use crate::{
     check::env::Env,
     ir::{
         classes::SymField,
-        types::{SymGenericTerm, SymPerm, SymPlace, SymPlaceKind, SymTy},
+        types::{SymGenericTerm, SymPerm, SymPlace, SymPlaceKind, SymTy, SymTyName},
     },
     prelude::CheckedFieldTy,
 };
@@ -26,6 +26,11 @@ impl<'db> PlaceTy<'db> for SymPlace<'db> {
                 let (owner_red_ty, owner_perm) = owner_ty.to_red_ty(env);
                 field_ty(env, owner_place, owner_perm, owner_red_ty, sym_field)
             }
+            SymPlaceKind::TupleElement(owner_place, index) => {
+                let owner_ty = owner_place.place_ty(env).await;
+                let (owner_red_ty, owner_perm) = owner_ty.to_red_ty(env);
+                tuple_element_ty(env, owner_perm, owner_red_ty, index)
+            }
             SymPlaceKind::Index(_sym_place) => {
                 todo!()
             }
@@ -35,6 +40,23 @@ impl<'db> PlaceTy<'db> for SymPlace<'db> {
     }
 }
 
+/// The permission that `given[places]` stands for.
+/// A value given from a place has the permission from the type of that place,
+/// so this is the union of those permissions.
+pub(crate) async fn given_perm<'db>(env: &mut Env<'db>, places: &[SymPlace<'db>]) -> SymPerm<'db> {
+    let db = env.db();
+    let mut perms = vec![];
+    for &place in places {
+        let ty = place.place_ty(env).await;
+        let (_, perm) = ty.to_red_ty(env);
+        perms.push(perm);
+    }
+    perms
+        .into_iter()
+        .reduce(|perm1, perm2| SymPerm::or(db, perm1, perm2))
+        .unwrap_or_else(|| SymPerm::my(db))
+}
+
 fn field_ty<'db>(
     env: &mut Env<'db>,
     owner_place: SymPlace<'db>,
@@ -70,3 +92,32 @@ fn field_ty<'db>(
         }
     }
 }
+
+fn tuple_element_ty<'db>(
+    env: &mut Env<'db>,
+    owner_perm: SymPerm<'db>,
+    owner_red_ty: RedTy<'db>,
+    index: usize,
+) -> SymTy<'db> {
+    let db = env.db();
+    match owner_red_ty {
+        RedTy::Error(reported) => SymTy::err(db, reported),
+
+        RedTy::Named(SymTyName::Tuple { arity: _ }, generics) => {
+            owner_perm.apply_to(db, generics[index].assert_type(db))
+        }
+
+        RedTy::Infer(infer) => {
+            // To have constructed this place there must have been a valid inference bound already
+            let (infer_red_ty, _) = env
+                .red_bound(infer, Direction::FromBelow)
+                .peek_ty()
+                .unwrap();
+            tuple_element_ty(env, owner_perm, infer_red_ty, index)
+        }
+
+        RedTy::Named(..) | RedTy::Perm | RedTy::Var(_) | RedTy::Never => {
+            unreachable!("no tuple elements on a {owner_red_ty:?}")
+        }
+    }
+}
@@ -8,6 +8,7 @@ pub mod is_provably_unique;
 pub mod require_lent;
 pub mod require_owned;
 pub mod require_shared;
+pub mod require_trait;
 pub mod require_unique;
 pub mod require_where_clause;
 pub mod var_infer;
@@ -4,7 +4,7 @@ use dada_util::boxed_async_fn;
 use crate::{
     check::{
         env::Env,
-        places::PlaceTy,
+        places::{PlaceTy, given_perm},
         predicates::{Predicate, var_infer::test_var_is_provably},
         red::RedTy,
         to_red::ToRedTy,
@@ -31,7 +31,7 @@ pub async fn term_is_provably_lent<'db>(
         RedTy::Named(name, generics) => match name {
             SymTyName::Primitive(_) => Ok(false),
             SymTyName::Aggregate(sym_aggregate) => match sym_aggregate.style(db) {
-                SymAggregateStyle::Struct => {
+                SymAggregateStyle::Struct | SymAggregateStyle::Enum => {
                     env.exists(generics, async |env, generic| {
                         term_is_provably_lent(env, perm.apply_to(db, generic)).await
                     })
@@ -39,7 +39,7 @@ pub async fn term_is_provably_lent<'db>(
                 }
                 SymAggregateStyle::Class => Ok(false),
             },
-            SymTyName::Future => Ok(false),
+            SymTyName::Future | SymTyName::Function { arity: _ } => Ok(false),
             SymTyName::Tuple { arity: _ } => {
                 env.exists(generics, async |env, generic| {
                     term_is_provably_lent(env, perm.apply_to(db, generic)).await
@@ -94,12 +94,22 @@ pub(crate) async fn perm_is_provably_lent<'db>(
             )
             .await
         }
+        SymPermKind::Given(ref places) => {
+            let perm = given_perm(env, places).await;
+            perm_is_provably_lent(env, perm).await
+        }
         SymPermKind::Apply(lhs, rhs) => {
             Ok(application_is_provably_lent(env, lhs.into(), rhs.into()).await?)
         }
         SymPermKind::Var(var) => Ok(test_var_is_provably(env, var, Predicate::Lent)),
         SymPermKind::Infer(infer) => infer_is_provably(env, perm, infer, Predicate::Lent).await,
-        SymPermKind::Or(_, _) => todo!(),
+        SymPermKind::Or(lhs, rhs) => {
+            env.both(
+                async |env| perm_is_provably_lent(env, lhs).await,
+                async |env| perm_is_provably_lent(env, rhs).await,
+            )
+            .await
+        }
     }
 }
 
@@ -4,7 +4,7 @@ use dada_util::boxed_async_fn;
 use crate::{
     check::{
         env::Env,
-        places::PlaceTy,
+        places::{PlaceTy, given_perm},
         predicates::{Predicate, var_infer::test_var_is_provably},
         red::RedTy,
         to_red::ToRedTy,
@@ -37,7 +37,7 @@ pub async fn term_is_provably_owned<'db>(
         RedTy::Named(sym_ty_name, ref generics) => match sym_ty_name {
             SymTyName::Primitive(_) => Ok(true),
             SymTyName::Aggregate(sym_aggregate) => match sym_aggregate.style(db) {
-                SymAggregateStyle::Struct => {
+                SymAggregateStyle::Struct | SymAggregateStyle::Enum => {
                     env.for_all(generics, async |env, &generic| {
                         term_is_provably_owned(env, perm.apply_to(db, generic)).await
                     })
@@ -45,7 +45,9 @@ pub async fn term_is_provably_owned<'db>(
                 }
                 SymAggregateStyle::Class => perm_is_provably_owned(env, perm).await,
             },
-            SymTyName::Future => perm_is_provably_owned(env, perm).await,
+            SymTyName::Future | SymTyName::Function { arity: _ } => {
+                perm_is_provably_owned(env, perm).await
+            }
             SymTyName::Tuple { arity: _ } => {
                 env.for_all(generics, async |env, &generic| {
                     term_is_provably_owned(env, perm.apply_to(db, generic)).await
@@ -86,6 +88,10 @@ pub(crate) async fn perm_is_provably_owned<'db>(
             .await
         }
 
+        SymPermKind::Given(ref places) => {
+            let perm = given_perm(env, places).await;
+            perm_is_provably_owned(env, perm).await
+        }
         SymPermKind::Apply(lhs, rhs) => {
             Ok(application_is_provably_owned(env, lhs.into(), rhs.into()).await?)
         }
@@ -96,7 +102,13 @@ pub(crate) async fn perm_is_provably_owned<'db>(
             infer_is_provably(env, SymPerm::my(db), infer, Predicate::Owned).await
         }
 
-        SymPermKind::Or(_, _) => todo!(),
+        SymPermKind::Or(lhs, rhs) => {
+            env.both(
+                async |env| perm_is_provably_owned(env, lhs).await,
+                async |env| perm_is_provably_owned(env, rhs).await,
+            )
+            .await
+        }
     }
 }
 
@@ -4,7 +4,7 @@ use dada_util::boxed_async_fn;
 use crate::{
     check::{
         env::Env,
-        places::PlaceTy,
+        places::{PlaceTy, given_perm},
         predicates::{Predicate, var_infer::test_var_is_provably},
         red::RedTy,
         to_red::ToRedTy,
@@ -28,7 +28,7 @@ pub async fn term_is_provably_shared<'db>(
         RedTy::Named(name, generics) => match name {
             SymTyName::Primitive(_) => Ok(true),
             SymTyName::Aggregate(aggr) => match aggr.style(db) {
-                SymAggregateStyle::Struct => {
+                SymAggregateStyle::Struct | SymAggregateStyle::Enum => {
                     env.for_all(generics, async |env, generic| {
                         term_is_provably_shared(env, perm.apply_to(db, generic)).await
                     })
@@ -36,7 +36,9 @@ pub async fn term_is_provably_shared<'db>(
                 }
                 SymAggregateStyle::Class => perm_is_provably_shared(env, perm).await,
             },
-            SymTyName::Future => perm_is_provably_shared(env, perm).await,
+            SymTyName::Future | SymTyName::Function { arity: _ } => {
+                perm_is_provably_shared(env, perm).await
+            }
             SymTyName::Tuple { arity: _ } => {
                 env.for_all(generics, async |env, generic| {
                     term_is_provably_shared(env, perm.apply_to(db, generic)).await
@@ -74,6 +76,10 @@ pub(crate) async fn perm_is_provably_shared<'db>(
         SymPermKind::My => Ok(false),
         SymPermKind::Our | SymPermKind::Referenced(_) => Ok(true),
         SymPermKind::Mutable(ref places) => places_are_provably_shared(env, places).await,
+        SymPermKind::Given(ref places) => {
+            let perm = given_perm(env, places).await;
+            perm_is_provably_shared(env, perm).await
+        }
         SymPermKind::Apply(lhs, rhs) => {
             Ok(application_is_provably_shared(env, lhs.into(), rhs.into()).await?)
         }
@@ -81,7 +87,13 @@ pub(crate) async fn perm_is_provably_shared<'db>(
         SymPermKind::Infer(infer) => {
             infer_is_provably(env, SymPerm::my(db), infer, Predicate::Shared).await
         }
-        SymPermKind::Or(_, _) => todo!(),
+        SymPermKind::Or(lhs, rhs) => {
+            env.both(
+                async |env| perm_is_provably_shared(env, lhs).await,
+                async |env| perm_is_provably_shared(env, rhs).await,
+            )
+            .await
+        }
     }
 }
 
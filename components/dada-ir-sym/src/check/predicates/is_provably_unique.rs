@@ -4,7 +4,7 @@ use dada_util::boxed_async_fn;
 use crate::{
     check::{
         env::Env,
-        places::PlaceTy,
+        places::{PlaceTy, given_perm},
         predicates::{Predicate, var_infer::test_var_is_provably},
         red::RedTy,
         to_red::ToRedTy,
@@ -37,7 +37,7 @@ pub async fn term_is_provably_unique<'db>(
         RedTy::Named(sym_ty_name, ref generics) => match sym_ty_name {
             SymTyName::Primitive(_) => Ok(false),
             SymTyName::Aggregate(sym_aggregate) => match sym_aggregate.style(db) {
-                SymAggregateStyle::Struct => {
+                SymAggregateStyle::Struct | SymAggregateStyle::Enum => {
                     env.exists(generics, async |env, &generic| {
                         term_is_provably_unique(env, generic).await
                     })
@@ -45,7 +45,7 @@ pub async fn term_is_provably_unique<'db>(
                 }
                 SymAggregateStyle::Class => Ok(true),
             },
-            SymTyName::Future => Ok(false),
+            SymTyName::Future | SymTyName::Function { arity: _ } => Ok(false),
             SymTyName::Tuple { arity: _ } => {
                 env.exists(generics, async |env, &generic| {
                     term_is_provably_unique(env, generic).await
@@ -86,6 +86,10 @@ pub(crate) async fn perm_is_provably_unique<'db>(
             .await
         }
 
+        SymPermKind::Given(ref places) => {
+            let perm = given_perm(env, places).await;
+            perm_is_provably_unique(env, perm).await
+        }
         SymPermKind::Apply(lhs, rhs) => {
             Ok(application_is_provably_unique(env, lhs.into(), rhs.into()).await?)
         }
@@ -96,7 +100,13 @@ pub(crate) async fn perm_is_provably_unique<'db>(
             infer_is_provably(env, SymPerm::my(db), infer, Predicate::Unique).await
         }
 
-        SymPermKind::Or(_, _) => todo!(),
+        SymPermKind::Or(lhs, rhs) => {
+            env.both(
+                async |env| perm_is_provably_unique(env, lhs).await,
+                async |env| perm_is_provably_unique(env, rhs).await,
+            )
+            .await
+        }
     }
 }
 
@@ -4,6 +4,7 @@ use dada_util::boxed_async_fn;
 use crate::{
     check::{
         env::Env,
+        places::given_perm,
         predicates::{
             Predicate,
             var_infer::{require_infer_is, require_var_is},
@@ -101,7 +102,7 @@ async fn require_ty_is_lent<'db>(
 
             // Named types
             RedTy::Named(sym_ty_name, _) => match sym_ty_name.style(db) {
-                SymAggregateStyle::Struct => {
+                SymAggregateStyle::Struct | SymAggregateStyle::Enum => {
                     // Structs are never *lent*.
                     //
                     // They can always have at least some content
@@ -167,6 +168,12 @@ async fn require_perm_is_lent<'db>(
                 .await
             }
 
+            // Given: the permission of the places
+            SymPermKind::Given(ref places) => {
+                let perm = given_perm(env, places).await;
+                require_perm_is_lent(env, perm, or_else).await
+            }
+
             // Apply
             SymPermKind::Apply(lhs, rhs) => {
                 require_application_is_lent(env, lhs.into(), rhs.into(), or_else).await
@@ -177,7 +184,15 @@ async fn require_perm_is_lent<'db>(
             SymPermKind::Infer(infer) => {
                 require_infer_is(env, SymPerm::my(db), infer, Predicate::Lent, or_else).await
             }
-            SymPermKind::Or(_, _) => todo!(),
+
+            // Union: either permission may be in effect
+            SymPermKind::Or(lhs, rhs) => {
+                env.require_both(
+                    async |env| require_perm_is_lent(env, lhs, or_else).await,
+                    async |env| require_perm_is_lent(env, rhs, or_else).await,
+                )
+                .await
+            }
         }
     })
     .await
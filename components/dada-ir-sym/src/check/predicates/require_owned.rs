@@ -4,7 +4,7 @@ use dada_util::boxed_async_fn;
 use crate::{
     check::{
         env::Env,
-        places::PlaceTy,
+        places::{PlaceTy, given_perm},
         predicates::{
             Predicate,
             var_infer::{require_infer_is, require_var_is},
@@ -91,7 +91,7 @@ async fn require_ty_is_owned<'db>(
 
             // Named types: owned if all their generics are owned
             RedTy::Named(sym_ty_name, ref generics) => match sym_ty_name.style(db) {
-                SymAggregateStyle::Struct => {
+                SymAggregateStyle::Struct | SymAggregateStyle::Enum => {
                     require_generics_are_owned(env, perm, generics, or_else).await
                 }
                 SymAggregateStyle::Class => {
@@ -158,6 +158,12 @@ async fn require_perm_is_owned<'db>(
                 .await
             }
 
+            // Given: the permission of the places
+            SymPermKind::Given(ref places) => {
+                let perm = given_perm(env, places).await;
+                require_perm_is_owned(env, perm, or_else).await
+            }
+
             // Apply
             SymPermKind::Apply(lhs, rhs) => {
                 require_both_are_owned(env, lhs.into(), rhs.into(), or_else).await
@@ -169,7 +175,14 @@ async fn require_perm_is_owned<'db>(
                 require_infer_is(env, perm, infer, Predicate::Owned, or_else).await
             }
 
-            SymPermKind::Or(_, _) => todo!(),
+            // Union: either permission may be in effect
+            SymPermKind::Or(lhs, rhs) => {
+                env.require_both(
+                    async |env| require_perm_is_owned(env, lhs, or_else).await,
+                    async |env| require_perm_is_owned(env, rhs, or_else).await,
+                )
+                .await
+            }
         }
     })
     .await
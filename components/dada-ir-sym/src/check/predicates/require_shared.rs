@@ -4,7 +4,7 @@ use dada_util::boxed_async_fn;
 use crate::{
     check::{
         env::Env,
-        places::PlaceTy,
+        places::{PlaceTy, given_perm},
         predicates::{
             Predicate,
             var_infer::{require_infer_is, require_var_is},
@@ -101,7 +101,7 @@ async fn require_ty_is_shared<'db>(
 
             // Named types
             RedTy::Named(sym_ty_name, ref generics) => match sym_ty_name.style(db) {
-                SymAggregateStyle::Struct => {
+                SymAggregateStyle::Struct | SymAggregateStyle::Enum => {
                     require_generics_are_shared(env, perm, generics, or_else).await
                 }
                 SymAggregateStyle::Class => {
@@ -160,6 +160,12 @@ async fn require_perm_is_shared<'db>(
                 .await
             }
 
+            // Given: the permission of the places
+            SymPermKind::Given(ref places) => {
+                let perm = given_perm(env, places).await;
+                require_perm_is_shared(env, perm, or_else).await
+            }
+
             // Apply
             SymPermKind::Apply(lhs, rhs) => {
                 require_either_is_shared(env, lhs.into(), rhs.into(), or_else).await
@@ -172,7 +178,14 @@ async fn require_perm_is_shared<'db>(
                 require_infer_is(env, SymPerm::my(db), infer, Predicate::Shared, or_else).await
             }
 
-            SymPermKind::Or(_, _) => todo!(),
+            // Union: either permission may be in effect
+            SymPermKind::Or(lhs, rhs) => {
+                env.require_both(
+                    async |env| require_perm_is_shared(env, lhs, or_else).await,
+                    async |env| require_perm_is_shared(env, rhs, or_else).await,
+                )
+                .await
+            }
         }
     })
     .await
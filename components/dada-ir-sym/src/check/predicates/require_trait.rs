@@ -0,0 +1,83 @@
+use dada_ir_ast::diagnostic::Errors;
+
+use crate::{
+    check::{
+        env::Env,
+        inference::Direction,
+        red::RedTy,
+        report::{Because, OrElse},
+        to_red::ToRedTy,
+    },
+    ir::{
+        traits::SymTrait,
+        types::{SymGenericTerm, SymTy, SymTyName},
+    },
+};
+
+/// Requires that `term` is a type implementing `sym_trait`.
+pub(crate) async fn require_term_implements<'db>(
+    env: &mut Env<'db>,
+    term: SymGenericTerm<'db>,
+    sym_trait: SymTrait<'db>,
+    or_else: &dyn OrElse<'db>,
+) -> Errors<()> {
+    match term {
+        SymGenericTerm::Type(sym_ty) => {
+            require_ty_implements(env, sym_ty, sym_trait, or_else).await
+        }
+
+        // Only types can implement traits.
+        SymGenericTerm::Perm(_) => Err(or_else.report(env, Because::JustSo)),
+
+        SymGenericTerm::Place(place) => panic!("unexpected place term: {place:?}"),
+        SymGenericTerm::Error(reported) => Err(reported),
+    }
+}
+
+async fn require_ty_implements<'db>(
+    env: &mut Env<'db>,
+    ty: SymTy<'db>,
+    sym_trait: SymTrait<'db>,
+    or_else: &dyn OrElse<'db>,
+) -> Errors<()> {
+    env.indent("require_ty_implements", &[&ty, &sym_trait], async |env| {
+        let db = env.db();
+
+        // The permission is irrelevant: `ref T` implements whatever `T` implements.
+        let (red_ty, _perm) = ty.to_red_ty(env);
+
+        // Traits are implemented by named types, so wait until inference finds one.
+        let red_ty = match red_ty {
+            RedTy::Infer(infer) => match env.red_bound(infer, Direction::FromBelow).ty().await {
+                Some((bound_red_ty, _)) => bound_red_ty,
+
+                // No lower bound: the unconstrained inference variable is reported elsewhere.
+                None => return Ok(()),
+            },
+            red_ty => red_ty,
+        };
+
+        match red_ty {
+            RedTy::Error(reported) => Err(reported),
+
+            // Universal variables must be declared to implement the trait.
+            RedTy::Var(var) => {
+                if env.declared_traits(var).any(|t| t == sym_trait) {
+                    Ok(())
+                } else {
+                    Err(or_else.report(env, Because::JustSo))
+                }
+            }
+
+            // Aggregates must list the trait in their declaration.
+            RedTy::Named(SymTyName::Aggregate(aggr), _) if aggr.implements(db, sym_trait) => Ok(()),
+
+            RedTy::Named(..) | RedTy::Never | RedTy::Perm => {
+                Err(or_else.report(env, Because::JustSo))
+            }
+
+            RedTy::Infer(_) => panic!("did not expect inference variable"),
+        }
+    })
+    .await
+}
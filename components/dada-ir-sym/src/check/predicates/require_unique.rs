@@ -4,6 +4,7 @@ use dada_util::boxed_async_fn;
 use crate::{
     check::{
         env::Env,
+        places::given_perm,
         predicates::{
             Predicate,
             var_infer::{require_infer_is, require_var_is},
@@ -65,7 +66,7 @@ async fn require_ty_is_unique<'db>(
 
             // Named types
             RedTy::Named(sym_ty_name, ref generics) => match sym_ty_name.style(db) {
-                SymAggregateStyle::Struct => {
+                SymAggregateStyle::Struct | SymAggregateStyle::Enum => {
                     require_some_generic_is_unique(env, perm, generics, or_else).await
                 }
                 SymAggregateStyle::Class => require_perm_is_unique(env, perm, or_else).await,
@@ -129,6 +130,12 @@ async fn require_perm_is_unique<'db>(
                 .await
             }
 
+            // Given: the permission of the places
+            SymPermKind::Given(ref places) => {
+                let perm = given_perm(env, places).await;
+                require_perm_is_unique(env, perm, or_else).await
+            }
+
             // Apply
             SymPermKind::Apply(lhs, rhs) => {
                 env.require_both(
@@ -144,7 +151,14 @@ async fn require_perm_is_unique<'db>(
                 require_infer_is(env, perm, infer, Predicate::Unique, or_else).await
             }
 
-            SymPermKind::Or(_, _) => todo!(),
+            // Union: either permission may be in effect
+            SymPermKind::Or(lhs, rhs) => {
+                env.require_both(
+                    async |env| require_perm_is_unique(env, lhs, or_else).await,
+                    async |env| require_perm_is_unique(env, rhs, or_else).await,
+                )
+                .await
+            }
         }
     })
     .await
@@ -7,7 +7,8 @@ use crate::{
 
 use super::{
     require_lent::require_term_is_lent, require_owned::require_term_is_owned,
-    require_shared::require_term_is_shared, require_unique::require_term_is_unique,
+    require_shared::require_term_is_shared, require_trait::require_term_implements,
+    require_unique::require_term_is_unique,
 };
 
 pub async fn require_where_clause<'db>(
@@ -22,5 +23,8 @@ pub async fn require_where_clause<'db>(
         SymWhereClauseKind::Shared => require_term_is_shared(env, subject, or_else).await,
         SymWhereClauseKind::Owned => require_term_is_owned(env, subject, or_else).await,
         SymWhereClauseKind::Lent => require_term_is_lent(env, subject, or_else).await,
+        SymWhereClauseKind::Trait(sym_trait) => {
+            require_term_implements(env, subject, sym_trait, or_else).await
+        }
     }
 }
@@ -22,6 +22,14 @@ pub mod sub;
 /// An empty lien chain corresponds to owned data (`my`, in surface Dada syntax).
 /// A lien chain like `ref[p] mut[q]` would correspond to data referencing a variable `p`
 /// which in turn had data mutable from `q` (which in turn owned the data).
+///
+/// A `RedPerm` with more than one chain is a union (`SymPermKind::Or`):
+/// the data may have been reached by any of the chains,
+/// so a predicate holds only if it holds for every chain.
+///
+/// `given[places]` (`SymPermKind::Given`) has no chain of its own: it reduces to the chains
+/// of the permissions of the places' types, so `given[x]` relates to other permissions
+/// exactly as the permission of `x` does.
 #[derive(SalsaSerialize)]
 #[salsa::interned(debug)]
 pub(crate) struct RedPerm<'db> {
@@ -138,7 +146,7 @@ impl<'db> RedLink<'db> {
 
     pub fn are_lent(env: &Env<'db>, links: &[Self]) -> Errors<bool> {
         for link in links {
-            if !link.is_lent(env)? {
+            if link.is_lent(env)? {
                 return Ok(true);
             }
         }
@@ -248,7 +248,12 @@ impl<'db> GreatestLowerBound<'db> for RedLink<'db> {
                         || !env.var_is_declared_to_be(v, Predicate::Owned)
                 );
 
-                Err(NoGlb)
+                // `our` is a subtype of every shared permission.
+                if env.var_is_declared_to_be(v, Predicate::Shared) {
+                    Ok(RedLink::Our)
+                } else {
+                    Err(NoGlb)
+                }
             }
 
             // No type is a subtype of both our/mut at same time.
@@ -2,7 +2,7 @@ use dada_ir_ast::diagnostic::Errors;
 
 use crate::check::env::Env;
 
-use super::{RedChain, RedLink};
+use super::{Live, RedChain, RedLink};
 
 pub fn chain_sub_chain<'db>(
     env: &Env<'db>,
@@ -78,6 +78,19 @@ fn links_sub_links<'db>(
             var_l == var_u && links_sub_links(env, tail_l, tail_u)?
         },
 
+        // A lease on a place that is no longer live can be cancelled:
+        // `mut[q] mut[p]` becomes `mut[p]` once `q` is dead...
+        ([RedLink::Mut(Live(false), _), tail_l @ ..], links_u) => {
+            matches!(tail_l.first(), Some(RedLink::Mut(..)))
+            && links_sub_links(env, tail_l, links_u)?
+        },
 
+        // ...and `ref[q] mut[p]` becomes `ref[p]`.
+        ([RedLink::Ref(Live(false), _), RedLink::Mut(live, place), tail_l @ ..], links_u) => {
+            let links_l: Vec<_> = std::iter::once(RedLink::Ref(*live, *place))
+                .chain(tail_l.iter().copied())
+                .collect();
+            links_sub_links(env, &links_l, links_u)?
+        },
     }
 }
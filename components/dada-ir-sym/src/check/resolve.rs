@@ -124,7 +124,7 @@ impl<'env, 'db> Resolver<'env, 'db> {
                 let args = self.resolve(args);
                 let ty = SymTy::new(db, SymTyKind::Named(name, args));
                 match name.style(db) {
-                    SymAggregateStyle::Struct => ty,
+                    SymAggregateStyle::Struct | SymAggregateStyle::Enum => ty,
                     SymAggregateStyle::Class => apply_perm(self, ty)?,
                 }
             }
@@ -15,13 +15,18 @@ use std::{
 use crate::ir::indices::InferVarIndex;
 use check_task::CheckTask;
 use dada_ir_ast::{
+    ast::Identifier,
     diagnostic::{Diagnostic, Err, Errors, Level},
     span::Span,
 };
 use dada_util::{Map, Set, vecext::VecExt};
 use serde::Serialize;
 
-use crate::{check::env::Env, check::inference::InferenceVarData};
+use crate::{
+    check::env::Env,
+    check::inference::InferenceVarData,
+    check::live_places::{LiveNames, LivePlaces},
+};
 
 use super::{
     debug::{LogHandle, RootTaskDescription, TaskDescription, event_argument},
@@ -39,10 +44,22 @@ pub(crate) struct RuntimeData<'db> {
     /// Stores the data for each inference variable created thus far.
     inference_vars: RwLock<Vec<InferenceVarData<'db>>>,
 
-    /// Pairs `(a, b)` of inference variables where `a <: b` is required.
+    /// Triples `(a, b, live)` of inference variables where `a <: b` is required
+    /// given that the places `live` are live afterwards.
     /// We insert into this set when we are relating two inference variables.
     /// If it is a new relation, then we know we must propagate bounds.
-    sub_inference_var_pairs: Mutex<Set<(InferVarIndex, InferVarIndex)>>,
+    /// Because live names are interned, `live` compares by its contents.
+    sub_inference_var_pairs: Mutex<Set<(InferVarIndex, InferVarIndex, LivePlaces)>>,
+
+    /// Sets of names that are live at some point in the function body,
+    /// indexed by [`LivePlaces`]. Each distinct set is stored once.
+    live_names: RwLock<Vec<Arc<LiveNames<'db>>>>,
+
+    /// Maps each set in `live_names` to its index.
+    live_name_indices: Mutex<Map<Arc<LiveNames<'db>>, usize>>,
+
+    /// The names mentioned by each expression in the function body, keyed by its span.
+    mentioned_names: RwLock<Map<Span<'db>, Arc<Set<Identifier<'db>>>>>,
 
     /// List of tasks that are ready to execute.
     ready_to_execute: Mutex<Vec<Arc<CheckTask>>>,
@@ -157,6 +174,9 @@ impl<'db> Runtime<'db> {
                 complete: Default::default(),
                 inference_vars: Default::default(),
                 sub_inference_var_pairs: Default::default(),
+                live_names: Default::default(),
+                live_name_indices: Default::default(),
+                mentioned_names: Default::default(),
                 ready_to_execute: Default::default(),
                 waiting_on_inference_var: Default::default(),
                 next_task_id: Default::default(),
@@ -326,7 +346,7 @@ impl<'db> Runtime<'db> {
         result
     }
 
-    /// Record that `lower <: upper` must hold,
+    /// Record that `lower <: upper` must hold when `live_after` are live afterwards,
     /// returning true if this is the first time that this has been recorded
     /// or false if it has been recorded before.
     #[track_caller]
@@ -334,17 +354,61 @@ impl<'db> Runtime<'db> {
         &self,
         lower: InferVarIndex,
         upper: InferVarIndex,
+        live_after: LivePlaces,
         log: &LogHandle,
     ) -> bool {
         log.log(
             Location::caller(),
             "insert_sub_infer_var_pair",
-            &[&lower, &upper],
+            &[&lower, &upper, &live_after],
         );
         self.sub_inference_var_pairs
             .lock()
             .unwrap()
-            .insert((lower, upper))
+            .insert((lower, upper, live_after))
+    }
+
+    /// Intern a set of live names, returning its index.
+    /// Equal sets always get the same index.
+    pub fn insert_live_names(&self, names: LiveNames<'db>) -> usize {
+        let mut live_name_indices = self.live_name_indices.lock().unwrap();
+        if let Some(&index) = live_name_indices.get(&names) {
+            return index;
+        }
+
+        let names = Arc::new(names);
+        let mut live_names = self.live_names.write().unwrap();
+        live_names.push(names.clone());
+        let index = live_names.len() - 1;
+        live_name_indices.insert(names, index);
+        index
+    }
+
+    /// Returns the set of live names with the given index.
+    pub fn live_names(&self, index: usize) -> Arc<LiveNames<'db>> {
+        self.live_names.read().unwrap()[index].clone()
+    }
+
+    /// Returns the names mentioned by the expression at `span`,
+    /// invoking `compute` to find them the first time.
+    pub fn mentioned_names(
+        &self,
+        span: Span<'db>,
+        compute: impl FnOnce(&mut Set<Identifier<'db>>),
+    ) -> Arc<Set<Identifier<'db>>> {
+        if let Some(names) = self.mentioned_names.read().unwrap().get(&span) {
+            return names.clone();
+        }
+
+        // Compute without holding the lock, as `compute` looks up nested expressions.
+        let mut names = Set::default();
+        compute(&mut names);
+        let names = Arc::new(names);
+        self.mentioned_names
+            .write()
+            .unwrap()
+            .insert(span, names.clone());
+        names
     }
 
     fn wake_tasks_monitoring_inference_var(&self, infer: InferVarIndex) {
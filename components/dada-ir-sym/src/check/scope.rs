@@ -14,10 +14,11 @@ use crate::{
     check::{CheckTyInEnv, scope_tree::ScopeTreeNode},
     ir::{
         binder::BoundTerm,
-        classes::{SymAggregate, SymAggregateStyle, SymClassMember},
+        classes::{SymAggregate, SymAggregateStyle, SymClassMember, SymVariant},
         functions::SymFunction,
         module::SymModule,
         primitive::{SymPrimitive, primitives},
+        traits::SymTrait,
         types::{SymGenericKind, SymGenericTerm},
         variables::SymVariable,
     },
@@ -117,6 +118,16 @@ impl<'scope, 'db> Scope<'scope, 'db> {
         None
     }
 
+    /// Return the innermost trait in scope (if any).
+    pub fn sym_trait(&self) -> Option<SymTrait<'db>> {
+        for link in self.chain.iter() {
+            if let ScopeChainKind::SymTrait(sym_trait) = &link.kind {
+                return Some(*sym_trait);
+            }
+        }
+        None
+    }
+
     /// Resolve identifier `id` (found at `span`) in the scope.
     /// Reports errors if nothing is found and returns `Err(Reported)`.
     pub(crate) fn resolve_name(
@@ -125,7 +136,7 @@ impl<'scope, 'db> Scope<'scope, 'db> {
         id: Identifier<'db>,
         span: Span<'db>,
     ) -> Errors<NameResolution<'db>> {
-        if let Some(resolution) = self.chain.iter().find_map(|link| link.resolve_name(db, id)) {
+        if let Some(resolution) = self.lookup_name(db, id) {
             return Ok(resolution);
         }
 
@@ -141,6 +152,39 @@ impl<'scope, 'db> Scope<'scope, 'db> {
         )
     }
 
+    /// Resolve identifier `id` in the scope, returning `None` if nothing is found.
+    pub(crate) fn lookup_name(
+        &self,
+        db: &'db dyn crate::Db,
+        id: Identifier<'db>,
+    ) -> Option<NameResolution<'db>> {
+        self.chain.iter().find_map(|link| link.resolve_name(db, id))
+    }
+
+    /// Returns the span where the symbol that `id` resolves to in this scope was declared.
+    /// Returns `None` if nothing is found or if the symbol has no declaration (e.g., a primitive).
+    pub fn lookup_name_span(
+        &self,
+        db: &'db dyn crate::Db,
+        id: Identifier<'db>,
+    ) -> Option<Span<'db>> {
+        self.lookup_name(db, id)?.span(db)
+    }
+
+    /// Returns every name that can be resolved in this scope along with what it resolves to,
+    /// innermost first. Names that are shadowed by an inner link of the scope are omitted.
+    pub fn names(&self, db: &'db dyn crate::Db) -> Vec<(Identifier<'db>, NameResolutionSym<'db>)> {
+        let mut names: Vec<(Identifier<'db>, NameResolutionSym<'db>)> = vec![];
+        for link in self.chain.iter() {
+            for (id, sym) in link.names(db) {
+                if !names.iter().any(|&(name, _)| name == id) {
+                    names.push((id, sym));
+                }
+            }
+        }
+        names
+    }
+
     /// True if `sym` is in scope.
     pub fn generic_sym_in_scope(&self, db: &'db dyn crate::Db, sym: SymVariable<'db>) -> bool {
         self.chain.iter().any(|link| link.binds_symbol(db, sym))
@@ -212,7 +256,8 @@ impl<'scope, 'db> Scope<'scope, 'db> {
             match &link.kind {
                 ScopeChainKind::Primitives
                 | ScopeChainKind::SymModule(_)
-                | ScopeChainKind::SymAggr(_) => {}
+                | ScopeChainKind::SymAggr(_)
+                | ScopeChainKind::SymTrait(_) => {}
                 ScopeChainKind::ForAll(cow) => {
                     vec.push(cow.iter().copied().collect());
                 }
@@ -248,6 +293,9 @@ pub enum ScopeChainKind<'scope, 'db> {
     /// Records that we are in the scope of a class
     SymAggr(SymAggregate<'db>),
 
+    /// Records that we are in the scope of a trait
+    SymTrait(SymTrait<'db>),
+
     /// Introduces the given symbols into scope.
     ForAll(Cow<'scope, [SymVariable<'db>]>),
 }
@@ -271,7 +319,6 @@ impl<'db> NameResolution<'db> {
     }
 
     /// Returns a string describing `self` that fits the mold "an X named `foo`".
-    #[expect(dead_code)]
     pub fn describe(&self, db: &'db dyn crate::Db) -> impl Display + 'db {
         self.sym.describe(db)
     }
@@ -354,9 +401,11 @@ impl<'db> NameResolution<'db> {
 pub enum NameResolutionSym<'db> {
     SymModule(SymModule<'db>),
     SymAggregate(SymAggregate<'db>),
+    SymTrait(SymTrait<'db>),
     SymFunction(SymFunction<'db>),
     SymPrimitive(SymPrimitive<'db>),
     SymVariable(SymVariable<'db>),
+    SymVariant(SymVariant<'db>),
 }
 
 impl<'db> NameResolutionSym<'db> {
@@ -364,7 +413,9 @@ impl<'db> NameResolutionSym<'db> {
     pub fn categorize(self, db: &'db dyn crate::Db) -> impl Display + 'db {
         match self {
             NameResolutionSym::SymModule(_) => Box::new("a module") as Box<dyn Display + 'db>,
+            NameResolutionSym::SymAggregate(aggr) if aggr.is_enum(db) => Box::new("an enum"),
             NameResolutionSym::SymAggregate(_) => Box::new("a class"),
+            NameResolutionSym::SymTrait(_) => Box::new("a trait"),
             NameResolutionSym::SymFunction(_) => Box::new("a function"),
             NameResolutionSym::SymVariable(var) => match var.kind(db) {
                 SymGenericKind::Type => Box::new("a generic type"),
@@ -372,6 +423,7 @@ impl<'db> NameResolutionSym<'db> {
                 SymGenericKind::Place => Box::new("a local variable"),
             },
             NameResolutionSym::SymPrimitive(p) => Box::new(format!("`{}`", p.name(db))),
+            NameResolutionSym::SymVariant(_) => Box::new("an enum variant"),
         }
     }
 
@@ -413,14 +465,14 @@ impl<'db> NameResolutionSym<'db> {
                 ),
             },
 
-            // FIXME: When we add traits, we have to decide how we want to manage trait member lookup.
-            // * Does this mean we have to merge name resolution plus type checking?
-            // * Do we not support `SomeClass.TraitMember` and instead prefer `SomeTrait.Member[SomeClass]`?
-            // * Do we only support `SomeClass.TraitMember` in expression contexts?
+            // A class implements a trait by declaring methods of the same name, so `SomeClass.TraitMember`
+            // finds the inherent method. Trait methods on generic types (`x.method()` where `T is SomeTrait`)
+            // are found by member lookup during type checking.
             NameResolutionSym::SymAggregate(sym_class) => {
                 match sym_class.inherent_member(db, id.id) {
                     Some(class_member) => match class_member {
                         SymClassMember::SymFunction(sym) => Ok(Ok(sym.into())),
+                        SymClassMember::SymVariant(sym) => Ok(Ok(sym.into())),
 
                         // FIXME: we should probably have a NameResolutionSym::Field?
                         SymClassMember::SymField(_) => Ok(Err(self)),
@@ -442,6 +494,9 @@ impl<'db> NameResolutionSym<'db> {
             NameResolutionSym::SymAggregate(sym_class) => {
                 format!("a class named `{}`", sym_class.name(db))
             }
+            NameResolutionSym::SymTrait(sym_trait) => {
+                format!("a trait named `{}`", sym_trait.name(db))
+            }
             NameResolutionSym::SymFunction(sym_function) => {
                 format!("a function named `{}`", sym_function.name(db))
             }
@@ -452,6 +507,13 @@ impl<'db> NameResolutionSym<'db> {
             NameResolutionSym::SymPrimitive(sym_primitive) => {
                 format!("the primitive type `{}`", sym_primitive.name(db))
             }
+            NameResolutionSym::SymVariant(sym_variant) => {
+                format!(
+                    "the variant `{}` of `{}`",
+                    sym_variant.name(db),
+                    sym_variant.owner(db).name(db)
+                )
+            }
         }
     }
 
@@ -460,8 +522,12 @@ impl<'db> NameResolutionSym<'db> {
             NameResolutionSym::SymModule(sym) => sym.expected_generic_parameters(db),
             NameResolutionSym::SymAggregate(sym) => sym.expected_generic_parameters(db),
             NameResolutionSym::SymFunction(sym) => sym.expected_generic_parameters(db),
+            NameResolutionSym::SymTrait(_) => 0,
             NameResolutionSym::SymPrimitive(_) => 0,
             NameResolutionSym::SymVariable(_) => 0,
+
+            // Variants take the generic arguments of their enum (e.g., `Option.Some[u32]`).
+            NameResolutionSym::SymVariant(sym) => sym.owner(db).expected_generic_parameters(db),
         }
     }
 
@@ -469,9 +535,11 @@ impl<'db> NameResolutionSym<'db> {
         match self {
             NameResolutionSym::SymModule(sym) => Some(sym.span(db)),
             NameResolutionSym::SymAggregate(sym) => Some(sym.span(db)),
+            NameResolutionSym::SymTrait(sym) => Some(sym.span(db)),
             NameResolutionSym::SymFunction(sym) => Some(sym.span(db)),
             NameResolutionSym::SymPrimitive(_) => None,
             NameResolutionSym::SymVariable(sym) => Some(sym.span(db)),
+            NameResolutionSym::SymVariant(sym) => Some(sym.span(db)),
         }
     }
 
@@ -481,9 +549,11 @@ impl<'db> NameResolutionSym<'db> {
         match self {
             NameResolutionSym::SymModule(_) => None,
             NameResolutionSym::SymAggregate(aggr) => Some(aggr.style(db)),
+            NameResolutionSym::SymTrait(_) => None,
             NameResolutionSym::SymFunction(_) => None,
             NameResolutionSym::SymPrimitive(_) => None,
             NameResolutionSym::SymVariable(_) => None,
+            NameResolutionSym::SymVariant(_) => None,
         }
     }
 }
@@ -634,7 +704,7 @@ impl<'scope, 'db> ScopeChain<'scope, 'db> {
                 })
                 .next(),
 
-            ScopeChainKind::SymAggr(_) => None,
+            ScopeChainKind::SymAggr(_) | ScopeChainKind::SymTrait(_) => None,
 
             ScopeChainKind::SymModule(sym) => {
                 // Somewhat subtle: we give definitions precedence over uses. If the same name appears
@@ -648,10 +718,15 @@ impl<'scope, 'db> ScopeChain<'scope, 'db> {
                         NameResolutionSym::SymAggregate(sym) => {
                             Some(self.internal_module_item(db, sym))
                         }
+                        NameResolutionSym::SymTrait(sym) => {
+                            Some(self.internal_module_item(db, sym))
+                        }
                         NameResolutionSym::SymFunction(sym) => {
                             Some(self.internal_module_item(db, sym))
                         }
-                        NameResolutionSym::SymPrimitive(_) | NameResolutionSym::SymVariable(_) => {
+                        NameResolutionSym::SymPrimitive(_)
+                        | NameResolutionSym::SymVariable(_)
+                        | NameResolutionSym::SymVariant(_) => {
                             // cannot be members of a module
                             unreachable!()
                         }
@@ -675,6 +750,37 @@ impl<'scope, 'db> ScopeChain<'scope, 'db> {
         }
     }
 
+    /// Returns the names that [`Self::resolve_name`] can resolve in this link.
+    fn names(&self, db: &'db dyn crate::Db) -> Vec<(Identifier<'db>, NameResolutionSym<'db>)> {
+        match &self.kind {
+            ScopeChainKind::Primitives => primitives(db)
+                .iter()
+                .map(|&p| (p.name(db), p.into()))
+                .collect(),
+
+            ScopeChainKind::SymAggr(_) | ScopeChainKind::SymTrait(_) => vec![],
+
+            ScopeChainKind::SymModule(sym) => {
+                let definitions = sym
+                    .class_map(db)
+                    .iter()
+                    .map(|(&id, &v)| (id, v.into()))
+                    .chain(sym.trait_map(db).iter().map(|(&id, &v)| (id, v.into())))
+                    .chain(sym.function_map(db).iter().map(|(&id, &v)| (id, v.into())));
+                let uses = sym.ast_use_map(db).keys().filter_map(|&id| {
+                    let resolution = sym.resolve_name_against_uses(db, id)?;
+                    Some((id, resolution.sym))
+                });
+                definitions.chain(uses).collect()
+            }
+
+            ScopeChainKind::ForAll(symbols) => symbols
+                .iter()
+                .filter_map(|&s| Some((s.name(db)?, s.into())))
+                .collect(),
+        }
+    }
+
     /// Resolve an identifier like `x` that we mapped to some item in a module.
     fn internal_module_item(
         &self,
@@ -691,6 +797,7 @@ impl<'scope, 'db> ScopeChain<'scope, 'db> {
     fn binds_symbol(&self, _db: &'db dyn crate::Db, sym: SymVariable<'db>) -> bool {
         match &self.kind {
             ScopeChainKind::SymAggr(_)
+            | ScopeChainKind::SymTrait(_)
             | ScopeChainKind::Primitives
             | ScopeChainKind::SymModule(_) => false,
 
@@ -709,6 +816,10 @@ impl<'db> SymModule<'db> {
             return Some(v.into());
         }
 
+        if let Some(&v) = self.trait_map(db).get(&id) {
+            return Some(v.into());
+        }
+
         if let Some(&v) = self.function_map(db).get(&id) {
             return Some(v.into());
         }
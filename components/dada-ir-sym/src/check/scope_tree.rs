@@ -5,7 +5,7 @@ use serde::Serialize;
 
 use crate::{
     check::scope::Scope, ir::classes::SymAggregate, ir::functions::SymFunction,
-    ir::module::SymModule, ir::variables::SymVariable,
+    ir::module::SymModule, ir::traits::SymTrait, ir::variables::SymVariable,
 };
 
 /// A `ScopeItem` defines a name resolution scope.
@@ -22,6 +22,9 @@ pub enum ScopeItem<'db> {
     /// A class or other aggregate
     Class(SymAggregate<'db>),
 
+    /// A trait
+    Trait(SymTrait<'db>),
+
     /// A function or method
     SymFunction(SymFunction<'db>),
 }
@@ -87,6 +90,7 @@ impl<'db> ScopeTreeNode<'db> for ScopeItem<'db> {
             ScopeItem::AstModule(sym) => sym.direct_super_scope(db),
             ScopeItem::SymModule(sym) => sym.direct_super_scope(db),
             ScopeItem::Class(sym) => sym.direct_super_scope(db),
+            ScopeItem::Trait(sym) => sym.direct_super_scope(db),
             ScopeItem::SymFunction(sym) => sym.direct_super_scope(db),
         }
     }
@@ -96,6 +100,7 @@ impl<'db> ScopeTreeNode<'db> for ScopeItem<'db> {
             ScopeItem::AstModule(sym) => sym.direct_generic_parameters(db),
             ScopeItem::SymModule(sym) => sym.direct_generic_parameters(db),
             ScopeItem::Class(sym) => sym.direct_generic_parameters(db),
+            ScopeItem::Trait(sym) => sym.direct_generic_parameters(db),
             ScopeItem::SymFunction(sym) => sym.direct_generic_parameters(db),
         }
     }
@@ -105,6 +110,7 @@ impl<'db> ScopeTreeNode<'db> for ScopeItem<'db> {
             ScopeItem::AstModule(sym) => sym.into_scope(db),
             ScopeItem::SymModule(sym) => sym.into_scope(db),
             ScopeItem::Class(sym) => sym.into_scope(db),
+            ScopeItem::Trait(sym) => sym.into_scope(db),
             ScopeItem::SymFunction(sym) => sym.into_scope(db),
         }
     }
@@ -120,6 +126,9 @@ impl<'db> ScopeTreeNode<'db> for ScopeItem<'db> {
             ScopeItem::Class(sym) => {
                 sym.push_direct_ast_where_clauses(db, out);
             }
+            ScopeItem::Trait(sym) => {
+                sym.push_direct_ast_where_clauses(db, out);
+            }
             ScopeItem::SymFunction(sym) => {
                 sym.push_direct_ast_where_clauses(db, out);
             }
@@ -121,6 +121,7 @@ pub async fn prepare_env<'db>(
     for ast_where_clause in ast_where_clauses {
         symbolify_ast_where_clause(&mut env, ast_where_clause, &mut where_clauses).await;
     }
+    env.declare_where_clauses(&where_clauses);
 
     PreparedEnv {
         env,
@@ -148,13 +149,22 @@ async fn set_variable_ty_from_input<'db>(env: &mut Env<'db>, input: &AstFunction
                 } else {
                     aggr_ty
                 }
+            } else if let Some(sym_trait) = env.scope.sym_trait() {
+                // Within a trait, `self` has the type `Self` of the implementing type.
+                let self_ty = SymTy::var(db, sym_trait.self_var(db));
+                if let Some(ast_perm) = arg.perm(db) {
+                    let sym_perm = ast_perm.check_in_env(env).await;
+                    SymTy::perm(db, sym_perm, self_ty)
+                } else {
+                    self_ty
+                }
             } else {
                 SymTy::err(
                     db,
                     Diagnostic::error(
                         db,
                         arg.span(db),
-                        "self parameter is only permitted within a class definition",
+                        "self parameter is only permitted within a class or trait definition",
                     )
                     .report(db),
                 )
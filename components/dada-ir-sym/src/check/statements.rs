@@ -12,18 +12,30 @@ use crate::{
 
 use super::{CheckTyInEnv, live_places::LivePlaces};
 
-#[boxed_async_fn]
 pub async fn check_block_statements<'db>(
     env: &mut Env<'db>,
     live_after: LivePlaces,
     block_span: Span<'db>,
     statements: &[AstStatement<'db>],
+) -> SymExpr<'db> {
+    let live_after_each = live_after.after_each(env, statements);
+    check_statements(env, block_span, statements, &live_after_each).await
+}
+
+/// Check `statements`, where `live_after_each[i]` are the places live after `statements[i]`.
+#[boxed_async_fn]
+async fn check_statements<'db>(
+    env: &mut Env<'db>,
+    block_span: Span<'db>,
+    statements: &[AstStatement<'db>],
+    live_after_each: &[LivePlaces],
 ) -> SymExpr<'db> {
     let db = env.db();
 
     let Some((first, rest)) = statements.split_first() else {
         return SymExpr::new(db, block_span, SymTy::unit(db), SymExprKind::Tuple(vec![]));
     };
+    let (&live_after_first, live_after_rest) = live_after_each.split_first().unwrap();
 
     match first {
         AstStatement::Let(s) => {
@@ -35,16 +47,19 @@ pub async fn check_block_statements<'db>(
                 None => env.fresh_ty_inference_var(s.name(db).span),
             };
 
+            // The initializer executes before the rest of the block.
+            let live_after_initializer = live_after_first;
+
             let (initializer, body) = env
                 .join(
                     async |env| match s.initializer(db) {
                         Some(initializer) => {
                             let initializer = initializer
-                                .check_in_env(env, LivePlaces::fixme())
+                                .check_in_env(env, live_after_initializer)
                                 .await
                                 .into_expr_with_enclosed_temporaries(env);
                             env.spawn_require_assignable_type(
-                                LivePlaces::fixme(),
+                                live_after_initializer,
                                 initializer.ty(db),
                                 ty,
                                 &InvalidInitializerType::new(lv, s.name(db).span, ty, initializer),
@@ -56,7 +71,7 @@ pub async fn check_block_statements<'db>(
                     },
                     async |env| {
                         env.push_program_variable_with_ty(lv, ty);
-                        check_block_statements(env, LivePlaces::fixme(), block_span, rest).await
+                        check_statements(env, block_span, rest, live_after_rest).await
                     },
                 )
                 .await;
@@ -77,8 +92,9 @@ pub async fn check_block_statements<'db>(
         }
 
         AstStatement::Expr(e) => {
+            let live_after_e = live_after_first;
             let check_e = async |env: &mut Env<'db>| {
-                e.check_in_env(env, LivePlaces::fixme())
+                e.check_in_env(env, live_after_e)
                     .await
                     .into_expr_with_enclosed_temporaries(env)
             };
@@ -89,7 +105,7 @@ pub async fn check_block_statements<'db>(
             } else {
                 let (ce, re) = env
                     .join(check_e, async |env| {
-                        check_block_statements(env, live_after, block_span, rest).await
+                        check_statements(env, block_span, rest, live_after_rest).await
                     })
                     .await;
                 SymExpr::new(
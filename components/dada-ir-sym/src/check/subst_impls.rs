@@ -2,8 +2,8 @@ use dada_ir_ast::ast::PermissionOp;
 
 use crate::ir::{
     exprs::{
-        SymBinaryOp, SymByteLiteral, SymExpr, SymExprKind, SymLiteral, SymMatchArm, SymPlaceExpr,
-        SymPlaceExprKind,
+        SymBinaryOp, SymByteLiteral, SymCapture, SymExpr, SymExprKind, SymLiteral, SymMatchArm,
+        SymPlaceExpr, SymPlaceExprKind,
     },
     functions::SymFunctionSignature,
     subst::{Subst, SubstWith, SubstitutionFns, identity_subst},
@@ -106,6 +106,42 @@ impl<'db> SubstWith<'db, SymGenericTerm<'db>> for SymExprKind<'db> {
                     .map(|&t| assert_bound_variable(db, t, bound_vars))
                     .collect(),
             },
+            SymExprKind::CallIndirect { callee, arg_temps } => SymExprKind::CallIndirect {
+                callee: callee.subst_with(db, bound_vars, subst_fns),
+                arg_temps: arg_temps
+                    .iter()
+                    .map(|&t| assert_bound_variable(db, t, bound_vars))
+                    .collect(),
+            },
+            SymExprKind::Closure {
+                captures,
+                inputs,
+                input_tys,
+                body,
+            } => {
+                let captures: Vec<_> = captures
+                    .iter()
+                    .map(|capture| SymCapture {
+                        lv: capture.lv,
+                        value: capture.value.subst_with(db, bound_vars, subst_fns),
+                    })
+                    .collect();
+                let input_tys = input_tys.subst_with(db, bound_vars, subst_fns);
+
+                // The captures and inputs are bound within the body.
+                let len = bound_vars.len();
+                bound_vars.extend(captures.iter().map(|capture| capture.lv));
+                bound_vars.extend_from_slice(inputs);
+                let body = body.subst_with(db, bound_vars, subst_fns);
+                bound_vars.truncate(len);
+
+                SymExprKind::Closure {
+                    captures,
+                    inputs: inputs.clone(),
+                    input_tys,
+                    body,
+                }
+            }
             SymExprKind::Return(sym_expr) => {
                 SymExprKind::Return(sym_expr.subst_with(db, bound_vars, subst_fns))
             }
@@ -118,13 +154,34 @@ impl<'db> SubstWith<'db, SymGenericTerm<'db>> for SymExprKind<'db> {
                 sym_expr.subst_with(db, bound_vars, subst_fns),
                 sym_expr1.subst_with(db, bound_vars, subst_fns),
             ),
-            SymExprKind::Aggregate { ty, fields } => SymExprKind::Aggregate {
+            SymExprKind::Aggregate { ty, fields, labels } => SymExprKind::Aggregate {
                 ty: ty.subst_with(db, bound_vars, subst_fns),
                 fields: fields.subst_with(db, bound_vars, subst_fns),
+                labels: labels.clone(),
+            },
+            SymExprKind::Variant {
+                ty,
+                variant,
+                fields,
+            } => SymExprKind::Variant {
+                ty: ty.subst_with(db, bound_vars, subst_fns),
+                variant: variant.subst_with(db, bound_vars, subst_fns),
+                fields: fields.subst_with(db, bound_vars, subst_fns),
+            },
+            SymExprKind::IsVariant { place, variant } => SymExprKind::IsVariant {
+                place: place.subst_with(db, bound_vars, subst_fns),
+                variant: variant.subst_with(db, bound_vars, subst_fns),
             },
             SymExprKind::Match { arms } => SymExprKind::Match {
                 arms: arms.subst_with(db, bound_vars, subst_fns),
             },
+            SymExprKind::Loop { body } => SymExprKind::Loop {
+                body: body.subst_with(db, bound_vars, subst_fns),
+            },
+            SymExprKind::Break(sym_expr) => {
+                SymExprKind::Break(sym_expr.subst_with(db, bound_vars, subst_fns))
+            }
+            SymExprKind::Continue => SymExprKind::Continue,
             SymExprKind::Error(reported) => {
                 SymExprKind::Error(reported.subst_with(db, bound_vars, subst_fns))
             }
@@ -179,6 +236,12 @@ impl<'db> SubstWith<'db, SymGenericTerm<'db>> for SymPlaceExprKind<'db> {
                 sym_place_expr.subst_with(db, bound_vars, subst_fns),
                 sym_field.subst_with(db, bound_vars, subst_fns),
             ),
+            SymPlaceExprKind::TupleElement(sym_place_expr, index) => {
+                SymPlaceExprKind::TupleElement(
+                    sym_place_expr.subst_with(db, bound_vars, subst_fns),
+                    index,
+                )
+            }
             SymPlaceExprKind::Error(reported) => {
                 SymPlaceExprKind::Error(reported.subst_with(db, bound_vars, subst_fns))
             }
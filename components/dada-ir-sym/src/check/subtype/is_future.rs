@@ -52,9 +52,10 @@ async fn require_future_red_type<'db>(
                 )
                 .await
             }
-            SymTyName::Primitive(_) | SymTyName::Aggregate(_) | SymTyName::Tuple { arity: _ } => {
-                Err(or_else.report(env, Because::JustSo))
-            }
+            SymTyName::Primitive(_)
+            | SymTyName::Aggregate(_)
+            | SymTyName::Tuple { arity: _ }
+            | SymTyName::Function { arity: _ } => Err(or_else.report(env, Because::JustSo)),
         },
 
         RedTy::Var(_) | RedTy::Never => Err(or_else.report(env, Because::JustSo)),
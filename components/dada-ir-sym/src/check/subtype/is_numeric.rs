@@ -62,9 +62,10 @@ async fn require_numeric_red_type<'db>(
                 | SymPrimitiveKind::Usize
                 | SymPrimitiveKind::Float { bits: _ } => Ok(()),
             },
-            SymTyName::Aggregate(_) | SymTyName::Future | SymTyName::Tuple { arity: _ } => {
-                Err(or_else.report(env, Because::JustSo))
-            }
+            SymTyName::Aggregate(_)
+            | SymTyName::Future
+            | SymTyName::Tuple { arity: _ }
+            | SymTyName::Function { arity: _ } => Err(or_else.report(env, Because::JustSo)),
         },
 
         RedTy::Var(_) | RedTy::Never => Err(or_else.report(env, Because::JustSo)),
@@ -29,6 +29,7 @@ use crate::{
 // * `(shared[place0] C0) <= (shared[place1] C1) if place1 <= place0 && C0 <= C1`
 // * `(shared[place0] C0) <= (our C1) if (mutable[place0] C0) <= C1`
 // * `X C0 <= X C1 if C0 <= C1`
+// * `given[place0] C0 <= C1 if P0 C0 <= C1` where `place0: P0 T` (and vice versa)
 // * `X <= our if X is copy+owned`
 // * `X <= my if X is move+owned`
 
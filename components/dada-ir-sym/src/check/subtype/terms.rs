@@ -159,7 +159,7 @@ pub async fn require_sub_red_terms<'db>(
                 .await?;
 
                 match name_lower.style(env.db()) {
-                    SymAggregateStyle::Struct => {}
+                    SymAggregateStyle::Struct | SymAggregateStyle::Enum => {}
                     SymAggregateStyle::Class => {
                         require_sub_perms(env, live_after, lower_perm, upper_perm, or_else).await?;
                     }
@@ -215,8 +215,9 @@ async fn require_infer_sub_infer<'db>(
         return Ok(());
     }
 
-    // FIXME: needs to take live-places into account
-    if env.insert_sub_infer_var_pair(lower_infer, upper_infer) {
+    // The bounds we propagate are related under `live_after`,
+    // so the same pair of variables may need to be related again under different live places.
+    if env.insert_sub_infer_var_pair(lower_infer, upper_infer, live_after) {
         env.require_both(
             async |env| {
                 env.for_each_bound(
@@ -440,12 +441,14 @@ async fn propagate_inverse_bound<'db>(
             RedTy::Error(_) => None,
 
             RedTy::Named(sym_ty_name, _) => match sym_ty_name {
-                SymTyName::Primitive(_) | SymTyName::Future | SymTyName::Tuple { .. } => {
-                    Some(generalize(env, red_ty, span)?)
-                }
+                SymTyName::Primitive(_)
+                | SymTyName::Future
+                | SymTyName::Tuple { .. }
+                | SymTyName::Function { .. } => Some(generalize(env, red_ty, span)?),
                 SymTyName::Aggregate(_sym_aggregate) => {
-                    // FIXME(#241): check if `sym_aggregate` is an enum
-                    // in which case we need to adjust based on `direction`
+                    // Enum variants are not types of their own (a value built from
+                    // `Option.Some` has type `Option[T]`), so enums generalize
+                    // just like structs and classes.
                     Some(generalize(env, red_ty, span)?)
                 }
             },
@@ -464,7 +467,7 @@ async fn propagate_inverse_bound<'db>(
                     // ... `?X <: B` so `B1 <: ?X`
                     require_ty_sub_infer(
                         env,
-                        LivePlaces::fixme(),
+                        LivePlaces::infer_bounds(),
                         // Combine `B1` with the permission variable from `?X`
                         perm_infer,
                         opposite_bound,
@@ -479,7 +482,7 @@ async fn propagate_inverse_bound<'db>(
                     // ... `B <: ?X` so `?X <: B1`
                     require_infer_sub_ty(
                         env,
-                        LivePlaces::fixme(),
+                        LivePlaces::infer_bounds(),
                         // Pass `?X` along with its permission variable as the lower term
                         perm_infer,
                         infer,
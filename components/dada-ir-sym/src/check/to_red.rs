@@ -14,7 +14,7 @@ use super::{
     Env,
     inference::Direction,
     live_places::LivePlaces,
-    places::PlaceTy,
+    places::{PlaceTy, given_perm},
     predicates::Predicate,
     red::{Live, RedChain, RedLink, RedPerm, RedTy},
     runtime::Runtime,
@@ -339,6 +339,11 @@ impl<'db> ToRedLinkVecs<'db> for SymPerm<'db> {
                     .collect::<Vec<_>>();
                 consumer.consume(env, links).await
             }
+            SymPermKind::Given(ref places) => {
+                let perm = given_perm(env, places).await;
+                perm.to_red_linkvecs(env, live_after, direction, consumer)
+                    .await
+            }
             SymPermKind::Apply(lhs, rhs) => {
                 lhs.to_red_linkvecs(
                     env,
@@ -376,7 +381,7 @@ impl<'db> ToRedLinkVecs<'db> for SymPerm<'db> {
                     {
                         vec![]
                     } else if env.var_is_declared_to_be(v, Predicate::Owned)
-                        && env.var_is_declared_to_be(v, Predicate::Unique)
+                        && env.var_is_declared_to_be(v, Predicate::Shared)
                     {
                         vec![RedLink::Our]
                     } else {
@@ -14,7 +14,7 @@ use crate::{
     ir::{
         types::{
             AnonymousPermSymbol, HasKind, SymGenericKind, SymGenericTerm, SymPerm, SymPermKind,
-            SymPlace, SymTy,
+            SymPlace, SymTy, SymTyName,
         },
         variables::FromVar,
     },
@@ -54,6 +54,32 @@ impl<'db> CheckTyInEnv<'db> for AstTy<'db> {
                 let symbol = decl.symbol(db);
                 SymTy::var(db, symbol)
             }
+
+            AstTyKind::Tuple(ref elements) => {
+                let mut generics: Vec<SymGenericTerm<'db>> = vec![];
+                for element in elements {
+                    generics.push(element.check_in_env(env).await.into());
+                }
+                SymTy::named(
+                    db,
+                    SymTyName::Tuple {
+                        arity: generics.len(),
+                    },
+                    generics,
+                )
+            }
+
+            AstTyKind::Function(ref inputs, output) => {
+                let mut input_tys = vec![];
+                for input in inputs {
+                    input_tys.push(input.check_in_env(env).await);
+                }
+                let output_ty = match output {
+                    Some(output) => output.check_in_env(env).await,
+                    None => SymTy::unit(db),
+                };
+                SymTy::function(db, input_tys, output_ty)
+            }
         })
         .await
     }
@@ -231,6 +257,37 @@ fn name_resolution_to_sym_ty<'db>(
                 )
                 .report(db),
         ),
+        NameResolutionSym::SymTrait(sym_trait) => SymTy::err(
+            db,
+            Diagnostic::error(db, source.span(db), "traits are not valid types")
+                .label(
+                    db,
+                    Level::Error,
+                    source.span(db),
+                    format!(
+                        "I expected a type here, but `{}` is a trait; try a generic type `T` with `where T is {}` instead",
+                        sym_trait.name(db),
+                        sym_trait.name(db),
+                    ),
+                )
+                .report(db),
+        ),
+        NameResolutionSym::SymVariant(sym_variant) => SymTy::err(
+            db,
+            Diagnostic::error(db, source.span(db), "enum variants are not valid types")
+                .label(
+                    db,
+                    Level::Error,
+                    source.span(db),
+                    format!(
+                        "I expected a type here, but `{}` is a variant of `{}`; try `{}` instead",
+                        sym_variant.name(db),
+                        sym_variant.owner(db).name(db),
+                        sym_variant.owner(db).name(db),
+                    ),
+                )
+                .report(db),
+        ),
     }
 }
 
@@ -279,7 +336,10 @@ impl<'db> CheckTyInEnv<'db> for AstPerm<'db> {
                 let places = paths_to_sym_places(env, paths).await;
                 SymPerm::new(db, SymPermKind::Mutable(places))
             }
-            AstPermKind::Given(Some(ref _span_vec)) => todo!(),
+            AstPermKind::Given(Some(ref paths)) => {
+                let places = paths_to_sym_places(env, paths).await;
+                SymPerm::new(db, SymPermKind::Given(places))
+            }
             AstPermKind::Referenced(None)
             | AstPermKind::Mutable(None)
             | AstPermKind::Given(None) => {
@@ -314,9 +374,11 @@ fn name_resolution_to_sym_perm<'db>(
 
         NameResolutionSym::SymModule(_)
         | NameResolutionSym::SymAggregate(_)
+        | NameResolutionSym::SymTrait(_)
         | NameResolutionSym::SymFunction(_)
         | NameResolutionSym::SymVariable(_)
-        | NameResolutionSym::SymPrimitive(_) => SymPerm::err(
+        | NameResolutionSym::SymPrimitive(_)
+        | NameResolutionSym::SymVariant(_) => SymPerm::err(
             db,
             Diagnostic::error(
                 db,
@@ -0,0 +1,251 @@
+//! Infer the variance of the generic parameters of classes, structs, and enums.
+
+use crate::{
+    ir::{
+        classes::SymAggregate,
+        types::{
+            SymGenericTerm, SymPerm, SymPermKind, SymPlace, SymPlaceKind, SymTy, SymTyKind,
+            SymTyName, Variance,
+        },
+        variables::SymVariable,
+    },
+    prelude::CheckedFieldTy,
+};
+
+/// Infer the variance of each generic parameter of `aggr` from the types of its fields
+/// (including the fields of enum variants).
+///
+/// * A generic that appears in the type of a `mut` field is invariant,
+///   since values of that type can be both read from and written into the field.
+/// * A generic that only appears beneath a permission other than `my` or `our`
+///   (e.g., `ref[x] T` or `P T`) is [relative](`Variance::relative`).
+/// * A generic that appears in no field is covariant.
+///
+/// The variances of other aggregates named in the field types come from the
+/// [`SymAggregate::variances`] query. When aggregates refer to one another,
+/// that query iterates from [bivariant](`initial_variances_in_cycle`) to a fixed point.
+pub(crate) fn infer_variances<'db>(
+    db: &'db dyn crate::Db,
+    aggr: SymAggregate<'db>,
+) -> Vec<Variance> {
+    VarianceCx { db }.aggregate_variances(aggr)
+}
+
+/// Where [`SymAggregate::variances`] starts when it is part of a cycle:
+/// every generic is bivariant until a use of it is found.
+pub(crate) fn initial_variances_in_cycle<'db>(
+    db: &'db dyn crate::Db,
+    aggr: SymAggregate<'db>,
+) -> Vec<Variance> {
+    vec![Variance::bivariant(); aggr.len_generics(db)]
+}
+
+/// Each iteration only adds uses, so iterating until the variances stop changing terminates.
+pub(crate) fn recover_from_variances_cycle<'db>(
+    _db: &'db dyn crate::Db,
+    _value: &[Variance],
+    _count: u32,
+    _aggr: SymAggregate<'db>,
+) -> salsa::CycleRecoveryAction<Vec<Variance>> {
+    salsa::CycleRecoveryAction::Iterate
+}
+
+struct VarianceCx<'db> {
+    db: &'db dyn crate::Db,
+}
+
+/// The way that a generic variable is used in some position of a field type.
+#[derive(Copy, Clone, Debug)]
+struct Polarity {
+    covariant: bool,
+    contravariant: bool,
+    relative: bool,
+}
+
+impl Polarity {
+    fn covariant() -> Self {
+        Self {
+            covariant: true,
+            contravariant: false,
+            relative: false,
+        }
+    }
+
+    fn invariant() -> Self {
+        Self {
+            covariant: true,
+            contravariant: true,
+            relative: false,
+        }
+    }
+
+    /// Polarity of a generic argument whose parameter has variance `variance`,
+    /// when the type it is given to appears in a position of polarity `self`.
+    fn compose(self, variance: Variance) -> Self {
+        Self {
+            covariant: (self.covariant && variance.at_least_covariant)
+                || (self.contravariant && variance.at_least_contravariant),
+            contravariant: (self.covariant && variance.at_least_contravariant)
+                || (self.contravariant && variance.at_least_covariant),
+            relative: self.relative || variance.relative,
+        }
+    }
+
+    fn relative(self) -> Self {
+        Self {
+            relative: true,
+            ..self
+        }
+    }
+}
+
+impl<'db> VarianceCx<'db> {
+    fn aggregate_variances(&mut self, aggr: SymAggregate<'db>) -> Vec<Variance> {
+        let db = self.db;
+        let variables = &aggr.symbols(db).generic_variables;
+        let mut uses = vec![None; variables.len()];
+
+        let variant_fields = aggr
+            .variants(db)
+            .flat_map(|variant| variant.fields(db).iter().copied());
+        for field in aggr.fields(db).chain(variant_fields) {
+            let polarity = if field.source(db).variable(db).mutable(db).is_some() {
+                Polarity::invariant()
+            } else {
+                Polarity::covariant()
+            };
+
+            // The outer binder is the aggregate's generics and the inner binder is `self`;
+            // `record` matches the generics' variables directly, so we can look at the bound type.
+            let field_ty = field.checked_field_ty(db).bound_value.bound_value;
+            self.visit_ty(variables, field_ty, polarity, &mut uses);
+        }
+
+        uses.into_iter()
+            .map(|variance| variance.unwrap_or_else(Variance::covariant))
+            .collect()
+    }
+
+    /// Variances of the generic arguments of a type named `name`.
+    fn name_variances(&mut self, name: SymTyName<'db>) -> Vec<Variance> {
+        match name {
+            SymTyName::Primitive(_) => vec![],
+            SymTyName::Future => vec![Variance::covariant()],
+            SymTyName::Tuple { arity } => vec![Variance::covariant(); arity],
+            SymTyName::Function { arity } => Variance::function(arity),
+            SymTyName::Aggregate(aggr) => aggr.variances(self.db).clone(),
+        }
+    }
+
+    fn visit_term(
+        &mut self,
+        variables: &[SymVariable<'db>],
+        term: SymGenericTerm<'db>,
+        polarity: Polarity,
+        uses: &mut [Option<Variance>],
+    ) {
+        match term {
+            SymGenericTerm::Type(ty) => self.visit_ty(variables, ty, polarity, uses),
+            SymGenericTerm::Perm(perm) => self.visit_perm(variables, perm, polarity, uses),
+            SymGenericTerm::Place(place) => self.visit_place(variables, place, polarity, uses),
+            SymGenericTerm::Error(_) => {}
+        }
+    }
+
+    fn visit_ty(
+        &mut self,
+        variables: &[SymVariable<'db>],
+        ty: SymTy<'db>,
+        polarity: Polarity,
+        uses: &mut [Option<Variance>],
+    ) {
+        let db = self.db;
+        match *ty.kind(db) {
+            SymTyKind::Perm(perm, ty) => {
+                self.visit_perm(variables, perm, polarity, uses);
+                let polarity = match perm.kind(db) {
+                    SymPermKind::My | SymPermKind::Our => polarity,
+                    _ => polarity.relative(),
+                };
+                self.visit_ty(variables, ty, polarity, uses);
+            }
+            SymTyKind::Named(name, ref generics) => {
+                let variances = self.name_variances(name);
+                assert_eq!(variances.len(), generics.len());
+                for (&variance, &generic) in variances.iter().zip(generics) {
+                    self.visit_term(variables, generic, polarity.compose(variance), uses);
+                }
+            }
+            SymTyKind::Var(var) => record(variables, var, polarity, uses),
+            SymTyKind::Infer(_) | SymTyKind::Never | SymTyKind::Error(_) => {}
+        }
+    }
+
+    fn visit_perm(
+        &mut self,
+        variables: &[SymVariable<'db>],
+        perm: SymPerm<'db>,
+        polarity: Polarity,
+        uses: &mut [Option<Variance>],
+    ) {
+        match *perm.kind(self.db) {
+            SymPermKind::Referenced(ref places)
+            | SymPermKind::Mutable(ref places)
+            | SymPermKind::Given(ref places) => {
+                for &place in places {
+                    self.visit_place(variables, place, polarity, uses);
+                }
+            }
+            SymPermKind::Apply(perm1, perm2) | SymPermKind::Or(perm1, perm2) => {
+                self.visit_perm(variables, perm1, polarity, uses);
+                self.visit_perm(variables, perm2, polarity, uses);
+            }
+            SymPermKind::Var(var) => record(variables, var, polarity, uses),
+            SymPermKind::My | SymPermKind::Our | SymPermKind::Infer(_) | SymPermKind::Error(_) => {}
+        }
+    }
+
+    fn visit_place(
+        &mut self,
+        variables: &[SymVariable<'db>],
+        place: SymPlace<'db>,
+        polarity: Polarity,
+        uses: &mut [Option<Variance>],
+    ) {
+        match *place.kind(self.db) {
+            SymPlaceKind::Var(var) => record(variables, var, polarity, uses),
+            SymPlaceKind::Field(owner, _)
+            | SymPlaceKind::TupleElement(owner, _)
+            | SymPlaceKind::Index(owner) => self.visit_place(variables, owner, polarity, uses),
+            SymPlaceKind::Erased | SymPlaceKind::Error(_) => {}
+        }
+    }
+}
+
+/// Record a use of `var` with the given polarity.
+/// Variables that are not generics of the aggregate (e.g., `self`) are ignored.
+/// A generic is relative only if every use of it is relative.
+fn record<'db>(
+    variables: &[SymVariable<'db>],
+    var: SymVariable<'db>,
+    polarity: Polarity,
+    uses: &mut [Option<Variance>],
+) {
+    let Some(index) = variables.iter().position(|&v| v == var) else {
+        return;
+    };
+
+    // A generic passed to a parameter that is never used does not count as a use.
+    if !polarity.covariant && !polarity.contravariant {
+        return;
+    }
+
+    let variance = uses[index].get_or_insert(Variance {
+        at_least_covariant: false,
+        at_least_contravariant: false,
+        relative: true,
+    });
+    variance.at_least_covariant |= polarity.covariant;
+    variance.at_least_contravariant |= polarity.contravariant;
+    variance.relative &= polarity.relative;
+}
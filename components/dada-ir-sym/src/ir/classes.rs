@@ -1,7 +1,11 @@
 use std::borrow::Cow;
 
 use dada_ir_ast::{
-    ast::{AstAggregate, AstAggregateKind, AstFieldDecl, AstMember, Identifier, SpannedIdentifier},
+    ast::{
+        AstAggregate, AstAggregateKind, AstFieldDecl, AstMember, AstVariant, Identifier,
+        SpannedIdentifier,
+    },
+    diagnostic::{Diagnostic, Level},
     span::{SourceSpanned, Span, Spanned},
 };
 use dada_parser::prelude::*;
@@ -9,10 +13,11 @@ use dada_util::{FromImpls, SalsaSerialize};
 use salsa::Update;
 
 use crate::{
-    check::scope::Scope,
+    check::scope::{NameResolutionSym, ResolveToSym, Scope},
     check::scope_tree::{ScopeItem, ScopeTreeNode},
     ir::functions::{SignatureSymbols, SymFunction, SymFunctionSource},
     ir::populate::PopulateSignatureSymbols,
+    ir::traits::{SymTrait, report_not_a_trait},
     ir::types::{SymGenericKind, SymTy, SymTyKind},
     ir::variables::SymVariable,
     prelude::Symbol,
@@ -42,6 +47,7 @@ impl<'db> SymAggregate<'db> {
         match self.source(db).kind(db) {
             AstAggregateKind::Class => SymAggregateStyle::Class,
             AstAggregateKind::Struct => SymAggregateStyle::Struct,
+            AstAggregateKind::Enum => SymAggregateStyle::Enum,
         }
     }
 
@@ -55,6 +61,11 @@ impl<'db> SymAggregate<'db> {
         self.style(db) == SymAggregateStyle::Class
     }
 
+    /// True if this is an enum
+    pub fn is_enum(self, db: &'db dyn crate::Db) -> bool {
+        self.style(db) == SymAggregateStyle::Enum
+    }
+
     /// Number of generic parameters
     pub fn len_generics(&self, db: &'db dyn crate::Db) -> usize {
         if let Some(generics) = self.source(db).generics(db) {
@@ -64,11 +75,15 @@ impl<'db> SymAggregate<'db> {
         }
     }
 
-    /// Variance of generic parameters
-    pub fn variances(&self, db: &'db dyn crate::Db) -> Vec<Variance> {
-        let len_generics = self.len_generics(db);
-        // FIXME
-        vec![Variance::covariant(); len_generics]
+    /// Variance of generic parameters, inferred from the types of the fields.
+    /// See [`crate::check::variances::infer_variances`][].
+    #[salsa::tracked(
+        return_ref,
+        cycle_fn = crate::check::variances::recover_from_variances_cycle,
+        cycle_initial = crate::check::variances::initial_variances_in_cycle
+    )]
+    pub fn variances(self, db: &'db dyn crate::Db) -> Vec<Variance> {
+        crate::check::variances::infer_variances(db, self)
     }
 
     /// Kinds of generic parameters
@@ -157,8 +172,27 @@ impl<'db> SymAggregate<'db> {
     #[salsa::tracked(return_ref)]
     pub fn members(self, db: &'db dyn crate::Db) -> Vec<SymClassMember<'db>> {
         // If the class is declared like `class Foo(x: u32, y: u32)` then we make a constructor `new`
-        // and a field for each of those members
-        let ctor_members = self.source(db).inputs(db).iter().flat_map(|inputs| {
+        // and a field for each of those members.
+        // Enums are constructed from their variants, so they cannot have an automatic constructor.
+        let ctor_inputs = match self.source(db).inputs(db) {
+            Some(inputs) if self.is_enum(db) => {
+                Diagnostic::error(
+                    db,
+                    inputs.span,
+                    "enums cannot have an automatic constructor",
+                )
+                .label(
+                    db,
+                    Level::Error,
+                    inputs.span,
+                    "declare the fields as part of a variant instead",
+                )
+                .report(db);
+                None
+            }
+            inputs => inputs.as_ref(),
+        };
+        let ctor_members = ctor_inputs.into_iter().flat_map(|inputs| {
             let ctor = SymFunction::new(
                 db,
                 self.into(),
@@ -181,6 +215,7 @@ impl<'db> SymAggregate<'db> {
         });
 
         // Also include anything the user explicitly wrote
+        let mut variant_index = 0..;
         let explicit_members = self.source(db).members(db).iter().map(|m| match *m {
             AstMember::Field(ast_field_decl) => {
                 let SpannedIdentifier { span, id } = ast_field_decl.variable(db).name(db);
@@ -189,6 +224,15 @@ impl<'db> SymAggregate<'db> {
             AstMember::Function(ast_function) => {
                 SymFunction::new(db, self.into(), ast_function.into()).into()
             }
+            AstMember::Variant(ast_variant) => SymVariant::new(
+                db,
+                self,
+                ast_variant.name(db),
+                ast_variant.name_span(db),
+                variant_index.next().unwrap(),
+                ast_variant,
+            )
+            .into(),
         });
 
         ctor_members.chain(explicit_members).collect()
@@ -224,6 +268,14 @@ impl<'db> SymAggregate<'db> {
         })
     }
 
+    /// Returns iterator over all variants in this enum (empty if this is not an enum).
+    pub fn variants(self, db: &'db dyn crate::Db) -> impl Iterator<Item = SymVariant<'db>> {
+        self.members(db).iter().filter_map(|&m| match m {
+            SymClassMember::SymVariant(v) => Some(v),
+            _ => None,
+        })
+    }
+
     /// Returns iterator over all methods in this class.
     pub fn methods(self, db: &'db dyn crate::Db) -> impl Iterator<Item = SymFunction<'db>> {
         self.members(db).iter().filter_map(|&m| match m {
@@ -231,6 +283,32 @@ impl<'db> SymAggregate<'db> {
             _ => None,
         })
     }
+    /// Traits this class declares that it implements (`class Foo is Show`).
+    /// Paths that do not name a trait are reported and dropped.
+    #[salsa::tracked(return_ref)]
+    pub fn traits(self, db: &'db dyn crate::Db) -> Vec<SymTrait<'db>> {
+        let Some(paths) = self.source(db).traits(db) else {
+            return vec![];
+        };
+
+        let scope = self.class_scope(db);
+        paths
+            .iter()
+            .filter_map(|&path| match path.resolve_to_sym(db, &scope) {
+                Ok(NameResolutionSym::SymTrait(sym_trait)) => Some(sym_trait),
+                Ok(sym) => {
+                    report_not_a_trait(db, path.span(db), sym);
+                    None
+                }
+                Err(_) => None,
+            })
+            .collect()
+    }
+
+    /// True if this class declares that it implements `sym_trait`.
+    pub fn implements(self, db: &'db dyn crate::Db, sym_trait: SymTrait<'db>) -> bool {
+        self.traits(db).contains(&sym_trait)
+    }
 }
 
 impl std::fmt::Display for SymAggregate<'_> {
@@ -280,6 +358,7 @@ impl<'db> SourceSpanned<'db> for SymAggregate<'db> {
 pub enum SymAggregateStyle {
     Struct,
     Class,
+    Enum,
 }
 
 /// Symbol for a class member
@@ -290,6 +369,9 @@ pub enum SymClassMember<'db> {
 
     /// Class methods
     SymFunction(SymFunction<'db>),
+
+    /// Enum variants
+    SymVariant(SymVariant<'db>),
 }
 
 impl<'db> SymClassMember<'db> {
@@ -298,6 +380,7 @@ impl<'db> SymClassMember<'db> {
         match self {
             SymClassMember::SymField(f) => f.name(db) == id,
             SymClassMember::SymFunction(f) => f.name(db) == id,
+            SymClassMember::SymVariant(v) => v.name(db) == id,
         }
     }
 }
@@ -307,6 +390,7 @@ impl<'db> Spanned<'db> for SymClassMember<'db> {
         match self {
             SymClassMember::SymField(f) => f.name_span(db),
             SymClassMember::SymFunction(f) => f.name_span(db),
+            SymClassMember::SymVariant(v) => v.name_span(db),
         }
     }
 }
@@ -316,6 +400,7 @@ impl<'db> SourceSpanned<'db> for SymClassMember<'db> {
         match self {
             SymClassMember::SymField(f) => f.source_span(db),
             SymClassMember::SymFunction(f) => f.source_span(db),
+            SymClassMember::SymVariant(v) => v.source_span(db),
         }
     }
 }
@@ -376,3 +461,66 @@ impl std::fmt::Display for SymField<'_> {
             .unwrap_or_else(|| std::fmt::Debug::fmt(self, f))
     }
 }
+
+/// Symbol for a variant of an enum
+#[derive(SalsaSerialize)]
+#[salsa::tracked(debug)]
+pub struct SymVariant<'db> {
+    /// The enum in which this variant is declared.
+    pub owner: SymAggregate<'db>,
+
+    /// Variant name
+    pub name: Identifier<'db>,
+
+    /// Span of variant name. Also returned by [`Spanned`][] impl.
+    pub name_span: Span<'db>,
+
+    /// Position of this variant within the enum; used as its tag at runtime.
+    pub index: usize,
+
+    /// AST for variant declaration
+    pub source: AstVariant<'db>,
+}
+
+#[salsa::tracked]
+impl<'db> SymVariant<'db> {
+    /// The fields of the variant's payload. These are declared in the scope of the enum,
+    /// so their types may reference the enum's generic parameters.
+    #[salsa::tracked(return_ref)]
+    pub fn fields(self, db: &'db dyn crate::Db) -> Vec<SymField<'db>> {
+        self.source(db)
+            .fields(db)
+            .iter()
+            .flatten()
+            .map(|field_decl| {
+                let SpannedIdentifier { span, id } = field_decl.variable(db).name(db);
+                SymField::new(db, self.owner(db).into(), id, span, *field_decl)
+            })
+            .collect()
+    }
+
+    /// True if the variant was declared with a `()` section (e.g., `Some(value: T)`),
+    /// in which case it must be constructed like a function call.
+    pub fn has_payload(self, db: &'db dyn crate::Db) -> bool {
+        self.source(db).fields(db).is_some()
+    }
+}
+
+impl<'db> Spanned<'db> for SymVariant<'db> {
+    fn span(&self, db: &'db dyn dada_ir_ast::Db) -> Span<'db> {
+        self.name_span(db)
+    }
+}
+
+impl<'db> SourceSpanned<'db> for SymVariant<'db> {
+    fn source_span(&self, db: &'db dyn dada_ir_ast::Db) -> Span<'db> {
+        self.source(db).span(db)
+    }
+}
+
+impl std::fmt::Display for SymVariant<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        salsa::with_attached_database(|db| write!(f, "{}", self.name(db)))
+            .unwrap_or_else(|| std::fmt::Debug::fmt(self, f))
+    }
+}
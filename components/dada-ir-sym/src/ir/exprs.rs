@@ -14,7 +14,7 @@
 //! The object IR gives us enough information to make those determinations.
 
 use crate::{
-    ir::classes::SymField,
+    ir::classes::{SymField, SymVariant},
     ir::functions::SymFunction,
     ir::types::{SymGenericTerm, SymPlace, SymTy},
     ir::variables::{FromVar, SymVariable},
@@ -94,6 +94,133 @@ impl<'db> SymExpr<'db> {
             },
         )
     }
+
+    /// Create an expression like `()`
+    pub(crate) fn unit(db: &'db dyn crate::Db, span: Span<'db>) -> SymExpr<'db> {
+        SymExpr::new(db, span, SymTy::unit(db), SymExprKind::Tuple(vec![]))
+    }
+
+    /// Create an expression like `while $condition { $body }`, which is
+    /// `loop { if $condition { $body; () } else { break () } }`
+    pub(crate) fn while_loop(
+        db: &'db dyn crate::Db,
+        span: Span<'db>,
+        condition: SymExpr<'db>,
+        body: SymExpr<'db>,
+    ) -> SymExpr<'db> {
+        let unit_ty = SymTy::unit(db);
+        let body = SymExpr::new(
+            db,
+            body.span(db),
+            unit_ty,
+            SymExprKind::Semi(body, SymExpr::unit(db, span)),
+        );
+        let exit = SymExpr::new(
+            db,
+            span,
+            SymTy::never(db),
+            SymExprKind::Break(SymExpr::unit(db, span)),
+        );
+        let iteration = SymExpr::new(
+            db,
+            span,
+            unit_ty,
+            SymExprKind::Match {
+                arms: vec![
+                    SymMatchArm {
+                        condition: Some(condition),
+                        body,
+                    },
+                    SymMatchArm {
+                        condition: None,
+                        body: exit,
+                    },
+                ],
+            },
+        );
+        SymExpr::new(db, span, unit_ty, SymExprKind::Loop { body: iteration })
+    }
+
+    /// Invoke `op` on this expression and then on each of its subexpressions, in order.
+    /// This includes the bodies of closures and the values they capture.
+    pub fn visit(self, db: &'db dyn crate::Db, op: &mut dyn FnMut(SymExpr<'db>)) {
+        op(self);
+
+        match self.kind(db) {
+            SymExprKind::Semi(e1, e2) | SymExprKind::BinaryOp(_, e1, e2) => {
+                e1.visit(db, op);
+                e2.visit(db, op);
+            }
+            SymExprKind::Tuple(exprs)
+            | SymExprKind::Aggregate {
+                ty: _,
+                fields: exprs,
+                labels: _,
+            }
+            | SymExprKind::Variant {
+                ty: _,
+                variant: _,
+                fields: exprs,
+            } => {
+                for expr in exprs {
+                    expr.visit(db, op);
+                }
+            }
+            SymExprKind::LetIn {
+                lv: _,
+                ty: _,
+                initializer,
+                body,
+            } => {
+                if let Some(initializer) = initializer {
+                    initializer.visit(db, op);
+                }
+                body.visit(db, op);
+            }
+            SymExprKind::Await {
+                future: expr,
+                await_keyword: _,
+            }
+            | SymExprKind::Assign {
+                place: _,
+                value: expr,
+            }
+            | SymExprKind::Return(expr)
+            | SymExprKind::Break(expr)
+            | SymExprKind::Loop { body: expr }
+            | SymExprKind::Not {
+                operand: expr,
+                op_span: _,
+            } => expr.visit(db, op),
+            SymExprKind::Closure {
+                captures,
+                inputs: _,
+                input_tys: _,
+                body,
+            } => {
+                for capture in captures {
+                    capture.value.visit(db, op);
+                }
+                body.visit(db, op);
+            }
+            SymExprKind::Match { arms } => {
+                for arm in arms {
+                    if let Some(condition) = arm.condition {
+                        condition.visit(db, op);
+                    }
+                    arm.body.visit(db, op);
+                }
+            }
+            SymExprKind::Primitive(_)
+            | SymExprKind::ByteLiteral(_)
+            | SymExprKind::PermissionOp(..)
+            | SymExprKind::Call { .. }
+            | SymExprKind::CallIndirect { .. }
+            | SymExprKind::IsVariant { .. }
+            | SymExprKind::Continue
+            | SymExprKind::Error(_) => {}
+        }
+    }
 }
 
 impl<'db> SourceSpanned<'db> for SymExpr<'db> {
@@ -155,6 +282,23 @@ pub enum SymExprKind<'db> {
         arg_temps: Vec<SymVariable<'db>>,
     },
 
+    /// `$0($1..)` where `$0` is a value of [function type](`crate::ir::types::SymTyName::Function`)
+    CallIndirect {
+        callee: SymPlaceExpr<'db>,
+        arg_temps: Vec<SymVariable<'db>>,
+    },
+
+    /// `fn($inputs..) { $body }`
+    ///
+    /// When the closure is created, the `value` of each capture is stored in the closure.
+    /// When it is called, the captures and `inputs` are bound to variables within the `body`.
+    Closure {
+        captures: Vec<SymCapture<'db>>,
+        inputs: Vec<SymVariable<'db>>,
+        input_tys: Vec<SymTy<'db>>,
+        body: SymExpr<'db>,
+    },
+
     /// Return a value from this function
     Return(SymExpr<'db>),
 
@@ -171,11 +315,37 @@ pub enum SymExprKind<'db> {
     Aggregate {
         ty: SymTy<'db>,
         fields: Vec<SymExpr<'db>>,
+
+        /// For each field, the span of its name where it was given (the `x` in `x: ...`), if any.
+        labels: Vec<Option<Span<'db>>>,
+    },
+
+    /// Something like `Option.Some(x)`; `ty` is the type of the enum
+    Variant {
+        ty: SymTy<'db>,
+        variant: SymVariant<'db>,
+        fields: Vec<SymExpr<'db>>,
+    },
+
+    /// True if the enum value stored in `place` is an instance of `variant`
+    IsVariant {
+        place: SymPlaceExpr<'db>,
+        variant: SymVariant<'db>,
     },
 
     /// Match, if/else-if chain, etc
     Match { arms: Vec<SymMatchArm<'db>> },
 
+    /// Execute `body` repeatedly until a `Break` exits the loop.
+    /// `while` and `for` loops are lowered to this.
+    Loop { body: SymExpr<'db> },
+
+    /// Exit the innermost enclosing loop, which then produces `value`
+    Break(SymExpr<'db>),
+
+    /// Start the next iteration of the innermost enclosing loop
+    Continue,
+
     /// Error occurred somewhere.
     Error(Reported),
 }
@@ -237,9 +407,12 @@ impl TryFrom<AstBinaryOp> for SymBinaryOp {
 }
 
 /// A match arm is one part of a match statement.
+///
+/// Patterns are lowered before we get here: testing the pattern becomes
+/// an [`IsVariant`](`SymExprKind::IsVariant`) condition and its bindings become
+/// `let` expressions wrapping the body.
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Update, Serialize)]
 pub struct SymMatchArm<'db> {
-    // FIXME: patterns
     /// Condition to evaluate; if `None` then it always applies
     pub condition: Option<SymExpr<'db>>,
 
@@ -247,6 +420,17 @@ pub struct SymMatchArm<'db> {
     pub body: SymExpr<'db>,
 }
 
+/// A variable of a [closure](`SymExprKind::Closure`) body holding a value
+/// from the enclosing function (for now, always a reference to a local variable).
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Update, Serialize)]
+pub struct SymCapture<'db> {
+    /// Variable within the closure body.
+    pub lv: SymVariable<'db>,
+
+    /// Value stored when the closure is created, evaluated in the enclosing function.
+    pub value: SymExpr<'db>,
+}
+
 #[derive(SalsaSerialize)]
 #[salsa::tracked(debug)]
 pub struct SymPlaceExpr<'db> {
@@ -278,10 +462,25 @@ impl<'db> SymPlaceExpr<'db> {
         )
     }
 
+    /// The local variable this place is found in (e.g., `a` for `a.b.c`),
+    /// or `None` if the place is erroneous.
+    pub fn root_variable(self, db: &'db dyn crate::Db) -> Option<SymVariable<'db>> {
+        match *self.kind(db) {
+            SymPlaceExprKind::Var(lv) => Some(lv),
+            SymPlaceExprKind::Field(place, _) | SymPlaceExprKind::TupleElement(place, _) => {
+                place.root_variable(db)
+            }
+            SymPlaceExprKind::Error(_) => None,
+        }
+    }
+
     pub fn into_sym_place(self, db: &'db dyn crate::Db) -> SymPlace<'db> {
         match *self.kind(db) {
             SymPlaceExprKind::Var(lv) => SymPlace::var(db, lv),
             SymPlaceExprKind::Field(place, field) => place.into_sym_place(db).field(db, field),
+            SymPlaceExprKind::TupleElement(place, index) => {
+                place.into_sym_place(db).tuple_element(db, index)
+            }
             SymPlaceExprKind::Error(r) => SymPlace::err(db, r),
         }
     }
@@ -291,5 +490,6 @@ impl<'db> SymPlaceExpr<'db> {
 pub enum SymPlaceExprKind<'db> {
     Var(SymVariable<'db>),
     Field(SymPlaceExpr<'db>, SymField<'db>),
+    TupleElement(SymPlaceExpr<'db>, usize),
     Error(Reported),
 }
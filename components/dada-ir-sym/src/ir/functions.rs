@@ -3,7 +3,7 @@ use std::borrow::Cow;
 use dada_ir_ast::{
     ast::{
         AstAggregate, AstFunction, AstFunctionEffects, AstFunctionInput, Identifier,
-        SpannedIdentifier,
+        SpannedIdentifier, VisibilityKind,
     },
     span::{SourceSpanned, Span, Spanned},
 };
@@ -19,8 +19,11 @@ use crate::{
     ir::{
         binder::{Binder, LeafBoundTerm},
         classes::SymAggregate,
-        populate::{PopulateDefaultSymbols, PopulateSignatureSymbols},
-        types::SymTy,
+        populate::{
+            PopulateDefaultSymbols, PopulateSignatureSymbols, self_arg_requires_default_perm,
+        },
+        traits::SymTrait,
+        types::{AnonymousPermSymbol, SymTy},
         variables::SymVariable,
     },
 };
@@ -104,6 +107,61 @@ impl<'db> SymFunction<'db> {
         self.source(db).name(db).span
     }
 
+    /// True if the function was declared with `export`.
+    pub fn is_export(self, db: &'db dyn crate::Db) -> bool {
+        match self.source(db) {
+            SymFunctionSource::Function(ast_function) => ast_function
+                .visibility(db)
+                .is_some_and(|v| v.kind == VisibilityKind::Export),
+            SymFunctionSource::Constructor(..) => false,
+        }
+    }
+
+    /// False if the function was declared without a body, e.g., `fn print(s: String)`.
+    /// Body-less functions in `libdada` are provided by the host.
+    pub fn has_body(self, db: &'db dyn crate::Db) -> bool {
+        match self.source(db) {
+            SymFunctionSource::Function(ast_function) => ast_function.body(db).is_some(),
+            SymFunctionSource::Constructor(..) => true,
+        }
+    }
+
+    /// The aggregate declaring this function, if it is a method or constructor.
+    pub fn owner_aggregate(self, db: &'db dyn crate::Db) -> Option<SymAggregate<'db>> {
+        match self.super_scope_item(db) {
+            ScopeItem::Class(aggr) => Some(aggr),
+            ScopeItem::AstModule(_)
+            | ScopeItem::SymModule(_)
+            | ScopeItem::Trait(_)
+            | ScopeItem::SymFunction(_) => None,
+        }
+    }
+
+    /// The trait declaring this function, if it is a trait method.
+    pub fn owner_trait(self, db: &'db dyn crate::Db) -> Option<SymTrait<'db>> {
+        match self.super_scope_item(db) {
+            ScopeItem::Trait(sym_trait) => Some(sym_trait),
+            ScopeItem::AstModule(_)
+            | ScopeItem::SymModule(_)
+            | ScopeItem::Class(_)
+            | ScopeItem::SymFunction(_) => None,
+        }
+    }
+
+    /// If this is a class method declared like `fn m(self)`,
+    /// the generic permission variable that `self` implicitly has.
+    /// Trait methods have no such variable; their `self` has the permissions of `Self`.
+    pub fn implicit_self_perm_var(self, db: &'db dyn crate::Db) -> Option<SymVariable<'db>> {
+        let AstFunctionInput::SelfArg(arg) = *self.source(db).inputs(db).first()? else {
+            return None;
+        };
+        if self_arg_requires_default_perm(db, arg, &self.scope(db)) {
+            Some(arg.anonymous_perm_symbol(db))
+        } else {
+            None
+        }
+    }
+
     fn scope_from_symbols<'sym>(
         self,
         db: &'db dyn crate::Db,
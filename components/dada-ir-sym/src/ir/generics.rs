@@ -2,19 +2,22 @@ use dada_util::SalsaSerialize;
 use salsa::Update;
 use serde::Serialize;
 
-use super::types::SymGenericTerm;
+use super::{traits::SymTrait, types::SymGenericTerm};
 
 #[derive(SalsaSerialize)]
 #[salsa::interned(debug)]
 pub struct SymWhereClause<'db> {
     pub subject: SymGenericTerm<'db>,
-    pub kind: SymWhereClauseKind,
+    pub kind: SymWhereClauseKind<'db>,
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Update, Debug, Serialize)]
-pub enum SymWhereClauseKind {
+pub enum SymWhereClauseKind<'db> {
     Unique,
     Shared,
     Owned,
     Lent,
+
+    /// The subject is a type that implements the given trait.
+    Trait(SymTrait<'db>),
 }
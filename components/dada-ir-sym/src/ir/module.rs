@@ -13,7 +13,7 @@ use crate::{
         scope_tree::{ScopeItem, ScopeTreeNode},
     },
     ir::{
-        classes::SymAggregate, functions::SymFunction, primitive::SymPrimitive,
+        classes::SymAggregate, functions::SymFunction, primitive::SymPrimitive, traits::SymTrait,
         variables::SymVariable,
     },
     prelude::Symbol,
@@ -31,6 +31,9 @@ pub struct SymModule<'db> {
     pub(crate) class_map: Map<Identifier<'db>, SymAggregate<'db>>,
     #[tracked]
     #[return_ref]
+    pub(crate) trait_map: Map<Identifier<'db>, SymTrait<'db>>,
+    #[tracked]
+    #[return_ref]
     pub(crate) function_map: Map<Identifier<'db>, SymFunction<'db>>,
     #[tracked]
     #[return_ref]
@@ -66,6 +69,7 @@ impl<'db> SymModule<'db> {
             .values()
             .copied()
             .map(SymItem::from)
+            .chain(self.trait_map(db).values().copied().map(SymItem::from))
             .chain(self.function_map(db).values().copied().map(SymItem::from))
     }
 
@@ -118,6 +122,7 @@ impl<'db> Symbol<'db> for AstModule<'db> {
     #[salsa::tracked]
     fn symbol(self, db: &'db dyn crate::Db) -> SymModule<'db> {
         let mut class_map = Map::default();
+        let mut trait_map = Map::default();
         let mut function_map = Map::default();
         let mut ast_use_map = Map::default();
         for item in self.items(db) {
@@ -139,6 +144,14 @@ impl<'db> Symbol<'db> for AstModule<'db> {
                         SymAggregate::new(db, self.into(), ast_class_item),
                     );
                 }
+                AstItem::Trait(ast_trait) => {
+                    insert(
+                        db,
+                        &mut trait_map,
+                        ast_trait.name(db),
+                        SymTrait::new(db, self.into(), ast_trait),
+                    );
+                }
                 AstItem::Function(ast_function) => {
                     insert(
                         db,
@@ -154,10 +167,11 @@ impl<'db> Symbol<'db> for AstModule<'db> {
         // when resolving names, we prefer the maps that come earlier in this list.
         let canonical_map = &mut Map::default();
         insert_into_canonical_map(db, canonical_map, &class_map);
+        insert_into_canonical_map(db, canonical_map, &trait_map);
         insert_into_canonical_map(db, canonical_map, &function_map);
         insert_into_canonical_map(db, canonical_map, &ast_use_map);
 
-        SymModule::new(db, self, class_map, function_map, ast_use_map)
+        SymModule::new(db, self, class_map, trait_map, function_map, ast_use_map)
     }
 }
 
@@ -236,6 +250,7 @@ fn report_duplicate<'db>(
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, FromImpls)]
 pub enum SymItem<'db> {
     SymClass(SymAggregate<'db>),
+    SymTrait(SymTrait<'db>),
     SymFunction(SymFunction<'db>),
     SymPrimitive(SymPrimitive<'db>),
 }
@@ -244,6 +259,7 @@ impl<'db> SymItem<'db> {
     pub fn name(self, db: &'db dyn crate::Db) -> Identifier<'db> {
         match self {
             SymItem::SymClass(sym_class) => sym_class.name(db),
+            SymItem::SymTrait(sym_trait) => sym_trait.name(db),
             SymItem::SymFunction(sym_function) => sym_function.name(db),
             SymItem::SymPrimitive(sym_primitive) => sym_primitive.name(db),
         }
@@ -254,6 +270,7 @@ impl<'db> Spanned<'db> for SymItem<'db> {
     fn span(&self, db: &'db dyn dada_ir_ast::Db) -> Span<'db> {
         match self {
             SymItem::SymClass(sym_class) => sym_class.span(db),
+            SymItem::SymTrait(sym_trait) => sym_trait.span(db),
             SymItem::SymFunction(sym_function) => sym_function.span(db),
             SymItem::SymPrimitive(_) => well_known::prelude_span(db),
         }
@@ -264,6 +281,7 @@ impl<'db> SourceSpanned<'db> for SymItem<'db> {
     fn source_span(&self, db: &'db dyn dada_ir_ast::Db) -> Span<'db> {
         match self {
             SymItem::SymClass(a) => a.source_span(db),
+            SymItem::SymTrait(t) => t.source_span(db),
             SymItem::SymFunction(f) => f.source_span(db),
             SymItem::SymPrimitive(_) => well_known::prelude_span(db),
         }
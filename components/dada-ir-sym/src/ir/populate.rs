@@ -49,6 +49,13 @@ impl<'db> PopulateSignatureSymbols<'db> for AstTy<'db> {
             AstTyKind::GenericDecl(ast_generic_decl) => {
                 ast_generic_decl.populate_signature_symbols(db, symbols)
             }
+            AstTyKind::Tuple(elements) => elements
+                .iter()
+                .for_each(|e| e.populate_signature_symbols(db, symbols)),
+            AstTyKind::Function(inputs, output) => inputs
+                .iter()
+                .chain(output)
+                .for_each(|e| e.populate_signature_symbols(db, symbols)),
         }
     }
 }
@@ -266,8 +273,8 @@ pub(crate) fn self_arg_requires_default_perm<'db>(
     }
 
     match scope.aggregate().map(|a| a.style(db)) {
-        None | Some(SymAggregateStyle::Struct) => {
-            // Methods on structs don't need a default permission.
+        None | Some(SymAggregateStyle::Struct | SymAggregateStyle::Enum) => {
+            // Methods on structs and enums don't need a default permission.
             false
         }
 
@@ -311,7 +318,7 @@ pub(crate) fn variable_decl_requires_default_perm<'db>(
         AstTyKind::Named(path, _) => {
             if let Ok(sym) = path.resolve_to_sym(db, scope) {
                 match sym.style(db) {
-                    Some(SymAggregateStyle::Struct) | None => false,
+                    Some(SymAggregateStyle::Struct | SymAggregateStyle::Enum) | None => false,
                     Some(SymAggregateStyle::Class) => true,
                 }
             } else {
@@ -322,6 +329,14 @@ pub(crate) fn variable_decl_requires_default_perm<'db>(
             // No default symbol in this case.
             false
         }
+        AstTyKind::Tuple(..) => {
+            // Tuples are laid out like structs.
+            false
+        }
+        AstTyKind::Function(..) => {
+            // Function values may hold references to captured state, like classes.
+            true
+        }
     }
 }
 
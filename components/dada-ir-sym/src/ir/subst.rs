@@ -14,7 +14,7 @@ use crate::{
 };
 
 use super::{
-    classes::SymField,
+    classes::{SymField, SymVariant},
     functions::SymFunction,
     generics::{SymWhereClause, SymWhereClauseKind},
     indices::InferVarIndex,
@@ -307,6 +307,14 @@ impl<'db> SubstWith<'db, SymGenericTerm<'db>> for SymPerm<'db> {
                         .collect(),
                 ),
             ),
+            SymPermKind::Given(vec) => SymPerm::new(
+                db,
+                SymPermKind::Given(
+                    vec.iter()
+                        .map(|g| g.subst_with(db, bound_vars, subst_fns))
+                        .collect(),
+                ),
+            ),
             SymPermKind::Error(reported) => SymPerm::new(
                 db,
                 SymPermKind::Error(reported.subst_with(db, bound_vars, subst_fns)),
@@ -354,6 +362,10 @@ impl<'db> SubstWith<'db, SymGenericTerm<'db>> for SymPlace<'db> {
                 db,
                 SymPlaceKind::Field(sym_place.subst_with(db, bound_vars, subst_fns), *identifier),
             ),
+            SymPlaceKind::TupleElement(sym_place, index) => SymPlace::new(
+                db,
+                SymPlaceKind::TupleElement(sym_place.subst_with(db, bound_vars, subst_fns), *index),
+            ),
             SymPlaceKind::Index(sym_place) => SymPlace::new(
                 db,
                 SymPlaceKind::Index(sym_place.subst_with(db, bound_vars, subst_fns)),
@@ -565,7 +577,8 @@ identity_subst! {
         Span<'db>,
         SymFunction<'db>,
         SymField<'db>,
-        SymWhereClauseKind,
+        SymVariant<'db>,
+        SymWhereClauseKind<'db>,
     }
 }
 
@@ -0,0 +1,154 @@
+use std::borrow::Cow;
+
+use dada_ir_ast::{
+    ast::{AstTrait, Identifier},
+    diagnostic::{Diagnostic, Level, Reported},
+    span::{SourceSpanned, Span, Spanned},
+};
+use dada_parser::prelude::*;
+use dada_util::SalsaSerialize;
+
+use crate::{
+    check::scope::{NameResolutionSym, Scope},
+    check::scope_tree::{ScopeItem, ScopeTreeNode},
+    ir::functions::SymFunction,
+    ir::types::SymGenericKind,
+    ir::variables::SymVariable,
+};
+
+/// A trait like `trait Show { fn show(self) -> String }`.
+///
+/// The methods of a trait are declared without a body.
+/// Within the trait, `Self` is a generic type parameter standing for the implementing type;
+/// it is the first generic parameter of each trait method.
+#[derive(SalsaSerialize)]
+#[salsa::tracked(debug)]
+pub struct SymTrait<'db> {
+    /// The scope in which this trait is declared.
+    super_scope: ScopeItem<'db>,
+
+    /// The AST for this trait.
+    source: AstTrait<'db>,
+}
+
+#[salsa::tracked]
+impl<'db> SymTrait<'db> {
+    /// Name of the trait.
+    pub fn name(&self, db: &'db dyn salsa::Database) -> Identifier<'db> {
+        self.source(db).name(db)
+    }
+
+    /// Span of the trait name, typically used in diagnostics.
+    /// Also returned by the [`Spanned`][] impl.
+    pub fn name_span(&self, db: &'db dyn dada_ir_ast::Db) -> Span<'db> {
+        self.source(db).name_span(db)
+    }
+
+    /// The `Self` type parameter, standing for the type implementing the trait.
+    #[salsa::tracked(return_ref)]
+    pub fn symbols(self, db: &'db dyn crate::Db) -> Vec<SymVariable<'db>> {
+        vec![SymVariable::new(
+            db,
+            SymGenericKind::Type,
+            Some(Identifier::self_ty_ident(db)),
+            self.name_span(db),
+        )]
+    }
+
+    /// The `Self` type parameter, standing for the type implementing the trait.
+    pub fn self_var(self, db: &'db dyn crate::Db) -> SymVariable<'db> {
+        self.symbols(db)[0]
+    }
+
+    /// Tracked list of trait methods.
+    #[salsa::tracked(return_ref)]
+    pub fn methods(self, db: &'db dyn crate::Db) -> Vec<SymFunction<'db>> {
+        self.source(db)
+            .methods(db)
+            .iter()
+            .map(|&ast_function| SymFunction::new(db, self.into(), ast_function.into()))
+            .collect()
+    }
+
+    /// Returns the method with the given name, if it exists.
+    pub fn method_named(
+        self,
+        db: &'db dyn crate::Db,
+        id: Identifier<'db>,
+    ) -> Option<SymFunction<'db>> {
+        self.methods(db)
+            .iter()
+            .copied()
+            .find(|method| method.name(db) == id)
+    }
+
+    /// Returns the base scope used to resolve the trait methods.
+    pub(crate) fn trait_scope(self, db: &'db dyn crate::Db) -> Scope<'db, 'db> {
+        self.super_scope(db)
+            .into_scope(db)
+            .with_link(self)
+            .with_link(Cow::Borrowed(&self.symbols(db)[..]))
+    }
+}
+
+impl std::fmt::Display for SymTrait<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        salsa::with_attached_database(|db| write!(f, "{}", self.name(db)))
+            .unwrap_or_else(|| std::fmt::Debug::fmt(self, f))
+    }
+}
+
+impl<'db> ScopeTreeNode<'db> for SymTrait<'db> {
+    fn direct_super_scope(self, db: &'db dyn crate::Db) -> Option<ScopeItem<'db>> {
+        Some(self.super_scope(db))
+    }
+
+    fn direct_generic_parameters(self, db: &'db dyn crate::Db) -> &'db Vec<SymVariable<'db>> {
+        self.symbols(db)
+    }
+
+    fn into_scope(self, db: &'db dyn crate::Db) -> Scope<'db, 'db> {
+        self.trait_scope(db)
+    }
+
+    fn push_direct_ast_where_clauses(
+        self,
+        db: &'db dyn crate::Db,
+        out: &mut Vec<dada_ir_ast::ast::AstWhereClause<'db>>,
+    ) {
+        if let Some(wc) = self.source(db).where_clauses(db) {
+            out.extend(wc.clauses(db));
+        }
+    }
+}
+
+impl<'db> Spanned<'db> for SymTrait<'db> {
+    fn span(&self, db: &'db dyn dada_ir_ast::Db) -> Span<'db> {
+        self.name_span(db)
+    }
+}
+
+impl<'db> SourceSpanned<'db> for SymTrait<'db> {
+    fn source_span(&self, db: &'db dyn dada_ir_ast::Db) -> Span<'db> {
+        self.source(db).span(db)
+    }
+}
+
+/// Reports an error because `span`, which resolved to `sym`, was expected to name a trait.
+pub(crate) fn report_not_a_trait<'db>(
+    db: &'db dyn crate::Db,
+    span: Span<'db>,
+    sym: NameResolutionSym<'db>,
+) -> Reported {
+    Diagnostic::error(db, span, "expected a trait")
+        .label(
+            db,
+            Level::Error,
+            span,
+            format!(
+                "I expected the name of a trait here, but I found {}",
+                sym.describe(db)
+            ),
+        )
+        .report(db)
+}
@@ -2,7 +2,7 @@ use crate::{
     Db,
     ir::{
         binder::LeafBoundTerm,
-        classes::{SymAggregate, SymField},
+        classes::{SymAggregate, SymClassMember, SymField},
         indices::{FromInfer, FromInferVar, InferVarIndex},
         primitive::{SymPrimitive, SymPrimitiveKind},
         variables::{FromVar, SymVariable},
@@ -21,7 +21,7 @@ use serde::Serialize;
 
 use super::classes::SymAggregateStyle;
 
-#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Serialize)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Update, Debug, Serialize)]
 pub struct Variance {
     /// If true, then `T[P] <: T[Q]` requires `P <: Q` (necessary, not sufficient)
     pub at_least_covariant: bool,
@@ -53,6 +53,35 @@ impl Variance {
             relative: false,
         }
     }
+
+    /// The variance of a generic that is not used at all, so `T[P] <: T[Q]` for any `P` and `Q`.
+    /// This is where inference starts for aggregates whose fields refer to one another.
+    pub fn bivariant() -> Self {
+        Self {
+            at_least_covariant: false,
+            at_least_contravariant: false,
+            relative: true,
+        }
+    }
+
+    /// Variances of the generic arguments of a function type with `arity` inputs
+    /// (see [`SymTyName::Function`]). The inputs are contravariant and the output covariant.
+    /// A function value owns neither, so all of them are relative.
+    pub fn function(arity: usize) -> Vec<Self> {
+        let input = Self {
+            at_least_covariant: false,
+            at_least_contravariant: true,
+            relative: true,
+        };
+        let output = Self {
+            at_least_covariant: true,
+            at_least_contravariant: false,
+            relative: true,
+        };
+        std::iter::repeat_n(input, arity)
+            .chain(std::iter::once(output))
+            .collect()
+    }
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Update, Debug, Serialize)]
@@ -242,6 +271,7 @@ impl<'db> SymGenericTerm<'db> {
                 | SymPermKind::Our
                 | SymPermKind::Referenced(_)
                 | SymPermKind::Mutable(_)
+                | SymPermKind::Given(_)
                 | SymPermKind::Var(_)
                 | SymPermKind::Error(_)
                 | SymPermKind::Or(..)
@@ -251,6 +281,42 @@ impl<'db> SymGenericTerm<'db> {
                 SymPlaceKind::Var(_)
                 | SymPlaceKind::Erased
                 | SymPlaceKind::Field(..)
+                | SymPlaceKind::TupleElement(..)
+                | SymPlaceKind::Index(..)
+                | SymPlaceKind::Error(..) => None,
+            },
+            SymGenericTerm::Error(_) => None,
+        }
+    }
+
+    /// Returns the generic variable if `self` is a generic variable (e.g., `T`).
+    pub fn as_var(self, db: &'db dyn crate::Db) -> Option<SymVariable<'db>> {
+        match self {
+            SymGenericTerm::Type(ty) => match ty.kind(db) {
+                SymTyKind::Var(var) => Some(*var),
+                SymTyKind::Infer(..)
+                | SymTyKind::Named(..)
+                | SymTyKind::Never
+                | SymTyKind::Error(_)
+                | SymTyKind::Perm(..) => None,
+            },
+            SymGenericTerm::Perm(perm) => match perm.kind(db) {
+                SymPermKind::Var(var) => Some(*var),
+                SymPermKind::My
+                | SymPermKind::Our
+                | SymPermKind::Referenced(_)
+                | SymPermKind::Mutable(_)
+                | SymPermKind::Given(_)
+                | SymPermKind::Infer(_)
+                | SymPermKind::Error(_)
+                | SymPermKind::Or(..)
+                | SymPermKind::Apply(..) => None,
+            },
+            SymGenericTerm::Place(place) => match place.kind(db) {
+                SymPlaceKind::Var(var) => Some(*var),
+                SymPlaceKind::Erased
+                | SymPlaceKind::Field(..)
+                | SymPlaceKind::TupleElement(..)
                 | SymPlaceKind::Index(..)
                 | SymPlaceKind::Error(..) => None,
             },
@@ -325,6 +391,17 @@ impl<'db> SymTy<'db> {
         unit_ty(db)
     }
 
+    /// Returns a [`SymTyKind::Named`][] type for `fn(inputs...) -> output`.
+    pub fn function(db: &'db dyn Db, inputs: Vec<SymTy<'db>>, output: SymTy<'db>) -> Self {
+        let arity = inputs.len();
+        let generics = inputs
+            .into_iter()
+            .chain(std::iter::once(output))
+            .map(SymGenericTerm::from)
+            .collect();
+        SymTy::named(db, SymTyName::Function { arity }, generics)
+    }
+
     /// Returns a [`SymTyKind::Named`][] type for `bool`.
     pub fn boolean(db: &'db dyn Db) -> Self {
         SymTy::named(db, SymPrimitiveKind::Bool.intern(db).into(), vec![])
@@ -362,6 +439,12 @@ impl<'db> SymTy<'db> {
     pub fn shared(self, db: &'db dyn Db) -> Self {
         SymTy::new(db, SymTyKind::Perm(SymPerm::our(db), self))
     }
+
+    /// Returns the fields and methods that can be accessed on a value of this type (e.g., `x.foo`),
+    /// ignoring the trait methods of generic types.
+    pub fn members(self, db: &'db dyn Db) -> Vec<SymClassMember<'db>> {
+        crate::check::member_lookup::value_members(db, self)
+    }
 }
 
 impl<'db> FromInfer<'db> for SymTy<'db> {
@@ -458,6 +541,13 @@ pub enum SymTyName<'db> {
     Tuple {
         arity: usize,
     },
+
+    /// A function value taking `arity` inputs.
+    /// The generic arguments are the input types followed by the output type.
+    #[no_from_impl]
+    Function {
+        arity: usize,
+    },
 }
 
 impl<'db> SymTyName<'db> {
@@ -468,6 +558,7 @@ impl<'db> SymTyName<'db> {
             SymTyName::Aggregate(sym_aggregate) => sym_aggregate.style(db),
             SymTyName::Future => SymAggregateStyle::Class,
             SymTyName::Tuple { arity: _ } => SymAggregateStyle::Struct,
+            SymTyName::Function { arity: _ } => SymAggregateStyle::Class,
         }
     }
 }
@@ -480,6 +571,7 @@ impl std::fmt::Display for SymTyName<'_> {
                 SymTyName::Primitive(primitive) => write!(f, "{primitive}"),
                 SymTyName::Aggregate(class) => write!(f, "{}", class.name(db)),
                 SymTyName::Tuple { arity } => write!(f, "{arity}-tuple"),
+                SymTyName::Function { arity } => write!(f, "fn/{arity}"),
                 SymTyName::Future => write!(f, "Future"),
             }
         })
@@ -515,6 +607,11 @@ impl<'db> SymPerm<'db> {
         SymPerm::new(db, SymPermKind::Mutable(places))
     }
 
+    /// Returns a permission `given` with the given places.
+    pub fn given(db: &'db dyn crate::Db, places: Vec<SymPlace<'db>>) -> Self {
+        SymPerm::new(db, SymPermKind::Given(places))
+    }
+
     /// Returns a generic permission with the given generic variable `var`.
     pub fn var(db: &'db dyn crate::Db, var: SymVariable<'db>) -> Self {
         SymPerm::new(db, SymPermKind::Var(var))
@@ -605,6 +702,16 @@ impl std::fmt::Display for SymPerm<'_> {
                     }
                     write!(f, "]")
                 }
+                SymPermKind::Given(places) => {
+                    write!(f, "given[")?;
+                    for (i, place) in places.iter().enumerate() {
+                        if i > 0 {
+                            write!(f, ", ")?;
+                        }
+                        write!(f, "{place}")?;
+                    }
+                    write!(f, "]")
+                }
                 SymPermKind::Apply(perm1, perm2) => write!(f, "{perm1} {perm2}"),
                 SymPermKind::Infer(infer_var_index) => write!(f, "?{}", infer_var_index.as_usize()),
                 SymPermKind::Var(sym_variable) => write!(f, "{sym_variable}"),
@@ -651,6 +758,10 @@ pub enum SymPermKind<'db> {
     /// `mutable[x]`
     Mutable(Vec<SymPlace<'db>>),
 
+    /// `given[x]`, the permission of a value given from `x`
+    /// (i.e., the permission from the type of `x`).
+    Given(Vec<SymPlace<'db>>),
+
     /// `perm1 perm2` (e.g., `shared[x] mutable[y]`)
     Apply(SymPerm<'db>, SymPerm<'db>),
 
@@ -679,6 +790,10 @@ impl<'db> SymPlace<'db> {
         SymPlace::new(db, SymPlaceKind::Field(self, field))
     }
 
+    pub fn tuple_element(self, db: &'db dyn crate::Db, index: usize) -> Self {
+        SymPlace::new(db, SymPlaceKind::TupleElement(self, index))
+    }
+
     pub fn erased(db: &'db dyn crate::Db) -> Self {
         SymPlace::new(db, SymPlaceKind::Erased)
     }
@@ -688,6 +803,7 @@ impl<'db> SymPlace<'db> {
         match self.kind(db) {
             SymPlaceKind::Var(..) => true,
             SymPlaceKind::Field(sym_place, _) => sym_place.no_inference_vars(db),
+            SymPlaceKind::TupleElement(sym_place, _) => sym_place.no_inference_vars(db),
             SymPlaceKind::Index(sym_place) => sym_place.no_inference_vars(db),
             SymPlaceKind::Error(..) => true,
             SymPlaceKind::Erased => true,
@@ -705,6 +821,7 @@ impl<'db> SymPlace<'db> {
         self == other
             || match (self.kind(db), other.kind(db)) {
                 (_, SymPlaceKind::Field(p2, _)) => self.is_prefix_of(db, *p2),
+                (_, SymPlaceKind::TupleElement(p2, _)) => self.is_prefix_of(db, *p2),
                 _ => false,
             }
     }
@@ -717,6 +834,7 @@ impl std::fmt::Display for SymPlace<'_> {
             match self.kind(db) {
                 SymPlaceKind::Var(var) => write!(f, "{var}"),
                 SymPlaceKind::Field(place, field) => write!(f, "{place}.{field}"),
+                SymPlaceKind::TupleElement(place, index) => write!(f, "{place}.{index}"),
                 SymPlaceKind::Index(place) => write!(f, "{place}[_]"),
                 SymPlaceKind::Error(_) => write!(f, "<error>"),
                 SymPlaceKind::Erased => write!(f, "_"),
@@ -749,6 +867,9 @@ pub enum SymPlaceKind<'db> {
     /// `x.f`
     Field(SymPlace<'db>, SymField<'db>),
 
+    /// `x.0`
+    TupleElement(SymPlace<'db>, usize),
+
     /// `x[_]`
     Index(SymPlace<'db>),
 
@@ -114,6 +114,29 @@ pub fn pointer_struct<'db>(db: &'db dyn crate::Db) -> Errors<SymAggregate<'db>>
     }
 }
 
+/// Returns the functions and methods in the `libdada` prelude that are declared without a body.
+/// These are provided by the host and imported by generated code, in declaration order.
+#[salsa::tracked(return_ref)]
+pub fn host_functions<'db>(db: &'db dyn crate::Db) -> Vec<SymFunction<'db>> {
+    let mut functions = prelude_module(db)
+        .items(db)
+        .flat_map(|item| match item {
+            SymItem::SymFunction(function) => vec![function],
+            SymItem::SymClass(class) => class.methods(db).collect(),
+            // Trait methods have no body but are implemented by aggregates, not the host.
+            SymItem::SymTrait(_) | SymItem::SymPrimitive(_) => vec![],
+        })
+        .filter(|function| !function.has_body(db))
+        .collect::<Vec<_>>();
+    functions.sort_by_key(|function| function.name_span(db).start);
+    functions
+}
+
+/// True if `function` is provided by the host (see [`host_functions`]).
+pub fn is_host_function<'db>(db: &'db dyn crate::Db, function: SymFunction<'db>) -> bool {
+    host_functions(db).contains(&function)
+}
+
 fn report_not_found<'db>(db: &'db dyn crate::Db, module: SymModule<'db>, name: &str) -> Reported {
     let module_span = module.span(db);
     Diagnostic::error(db, module_span, format!("could not find {name}")).report(db)
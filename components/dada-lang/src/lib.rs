@@ -1,9 +1,11 @@
 #![feature(panic_payload_as_str)]
 #![doc = include_str!("../docs/overview.md")]
 
+use std::{path::PathBuf, str::FromStr};
+
 use dada_debug::DebugOptions;
 use dada_ir_ast::diagnostic::RenderOptions;
-use dada_util::Fallible;
+use dada_util::{Fallible, bail};
 use structopt::StructOpt;
 
 mod main_lib;
@@ -39,9 +41,13 @@ impl GlobalOptions {
 
 #[derive(Debug, StructOpt)]
 pub enum Command {
+    #[structopt(alias = "build")]
     Compile {
         #[structopt(flatten)]
         compile_options: CompileOptions,
+
+        #[structopt(flatten)]
+        emit_options: EmitOptions,
     },
 
     Run {
@@ -69,6 +75,49 @@ pub struct CompileOptions {
     input: String,
 }
 
+#[derive(Debug, StructOpt)]
+pub struct EmitOptions {
+    /// Artifacts to write once compilation succeeds (comma-separated: `wasm`, `wat`).
+    #[structopt(long, use_delimiter = true)]
+    emit: Vec<EmitKind>,
+
+    /// Directory where emitted artifacts are written.
+    #[structopt(long, short, parse(from_os_str), default_value = ".")]
+    output_dir: PathBuf,
+}
+
+/// Kinds of artifacts that can be requested with `--emit`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum EmitKind {
+    /// The binary WebAssembly module.
+    Wasm,
+
+    /// The WebAssembly module disassembled into the text format.
+    Wat,
+}
+
+impl EmitKind {
+    /// File extension used for artifacts of this kind.
+    pub fn extension(self) -> &'static str {
+        match self {
+            EmitKind::Wasm => "wasm",
+            EmitKind::Wat => "wat",
+        }
+    }
+}
+
+impl FromStr for EmitKind {
+    type Err = dada_util::Error;
+
+    fn from_str(s: &str) -> Fallible<Self> {
+        match s {
+            "wasm" => Ok(EmitKind::Wasm),
+            "wat" => Ok(EmitKind::Wat),
+            _ => bail!("unknown artifact kind `{s}` (expected `wasm` or `wat`)"),
+        }
+    }
+}
+
 #[derive(Debug, StructOpt)]
 pub struct RunOptions {
     #[structopt(flatten)]
@@ -18,7 +18,10 @@ impl Main {
 
     pub fn run(mut self, command: Command) -> Fallible<()> {
         match command {
-            Command::Compile { compile_options } => self.compile(&compile_options, None)?,
+            Command::Compile {
+                compile_options,
+                emit_options,
+            } => self.compile(&compile_options, Some(&emit_options), None)?,
             Command::Test { test_options } => self.test(test_options)?,
             Command::Run { run_options } => self.run_command(&run_options)?,
             Command::Debug {
@@ -31,7 +34,7 @@ impl Main {
                     "serving debug results on http://localhost:{port}/",
                     port = debug_options.port
                 );
-                self.compile(&compile_options, Some(debug_tx))?;
+                self.compile(&compile_options, None, Some(debug_tx))?;
                 eprintln!(
                     "compilation complete. Debug at http://localhost:{port}/",
                     port = debug_options.port
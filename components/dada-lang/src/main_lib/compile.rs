@@ -1,10 +1,10 @@
 use std::{path::Path, sync::mpsc::Sender};
 
 use dada_compiler::{Compiler, RealFs};
-use dada_ir_ast::{DebugEvent, diagnostic::Level};
-use dada_util::{Fallible, bail};
+use dada_ir_ast::{DebugEvent, diagnostic::Level, inputs::SourceFile};
+use dada_util::{Context, Fallible, bail};
 
-use crate::CompileOptions;
+use crate::{CompileOptions, EmitKind, EmitOptions};
 
 use super::Main;
 
@@ -12,6 +12,7 @@ impl Main {
     pub(super) fn compile(
         &mut self,
         compile_options: &CompileOptions,
+        emit_options: Option<&EmitOptions>,
         debug_tx: Option<Sender<DebugEvent>>,
     ) -> Fallible<()> {
         let debug_mode = debug_tx.is_some();
@@ -32,6 +33,53 @@ impl Main {
             bail!("compilation failed due to errors");
         }
 
+        if let Some(emit_options) = emit_options {
+            self.emit(&compiler, source_file, source_url, emit_options)?;
+        }
+
+        Ok(())
+    }
+
+    /// Write the artifacts requested by `emit_options` into the output directory.
+    /// Each artifact is named after the input file, e.g., `hello.dada` yields `hello.wasm`.
+    fn emit(
+        &self,
+        compiler: &Compiler,
+        source_file: SourceFile,
+        source_url: &Path,
+        emit_options: &EmitOptions,
+    ) -> Fallible<()> {
+        if emit_options.emit.is_empty() {
+            return Ok(());
+        }
+
+        let Some(bytes) = compiler.codegen_main_fn(source_file) else {
+            bail!("no `main` function found in `{}`", source_url.display());
+        };
+
+        let Some(stem) = source_url.file_stem() else {
+            bail!(
+                "cannot determine artifact name for `{}`",
+                source_url.display()
+            );
+        };
+
+        let output_dir = &emit_options.output_dir;
+        std::fs::create_dir_all(output_dir)
+            .with_context(|| format!("creating output directory `{}`", output_dir.display()))?;
+
+        for &kind in &emit_options.emit {
+            let path = output_dir.join(stem).with_extension(kind.extension());
+            let contents = match kind {
+                EmitKind::Wasm => bytes.clone(),
+                EmitKind::Wat => wasmprinter::print_bytes(bytes)
+                    .context("disassembling generated wasm")?
+                    .into_bytes(),
+            };
+            std::fs::write(&path, contents)
+                .with_context(|| format!("writing `{}`", path.display()))?;
+        }
+
         Ok(())
     }
 }
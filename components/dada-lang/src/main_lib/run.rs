@@ -1,18 +1,20 @@
 use std::path::Path;
 
 use dada_compiler::{Compiler, RealFs};
-use dada_util::Fallible;
+use dada_ir_ast::diagnostic::Level;
+use dada_util::{Fallible, bail};
 
 use crate::RunOptions;
 
 use super::Main;
 
+pub(super) mod host;
+
 impl Main {
     pub(super) fn run_command(&mut self, run_options: &RunOptions) -> Fallible<()> {
         let mut compiler = Compiler::new(RealFs::default(), None);
         let source_url = Path::new(&run_options.compile_options.input);
         let source_file = compiler.load_source_file(source_url)?;
-        let bytes = compiler.codegen_main_fn(source_file);
         let diagnostics = compiler.check_all(source_file);
 
         for diagnostic in &diagnostics {
@@ -22,8 +24,18 @@ impl Main {
             );
         }
 
-        let _ = bytes;
+        if diagnostics.iter().any(|d| d.level >= Level::Error) {
+            bail!("compilation failed due to errors");
+        }
+
+        let Some(bytes) = compiler.codegen_main_fn(source_file) else {
+            bail!(
+                "no `main` function found in `{}`",
+                run_options.compile_options.input
+            );
+        };
 
-        Ok(())
+        // A trap is returned as an error, so `dada run` exits with a nonzero status.
+        host::execute(bytes, &mut host::Output::Stdout)
     }
 }
@@ -0,0 +1,163 @@
+//! The embedded WebAssembly runtime used by `dada run`.
+//!
+//! Generated modules call into the host by importing functions from the [`HOST_MODULE`][].
+//! Each host function implements an async fn of the prelude and is polled like any other future:
+//! the arguments are the callee's stack pointer, the number of times the same future
+//! was polled before, and then the flattened Dada values. The first result says whether
+//! the function is ready; the others (zero here) are the awaited value.
+
+use dada_codegen::HOST_MODULE;
+use dada_util::{Context, Fallible, bail};
+use wasmtime::{Caller, Engine, ExternType, Instance, Linker, Module, Store, Val};
+
+/// Signature of a function provided by the host, which is given the number of earlier polls
+/// and the Dada values and returns whether it is ready.
+type HostFn = fn(&mut Caller<'_, Output>, u32, &[Val]) -> wasmtime::Result<bool>;
+
+/// Where the text printed by the program goes.
+pub(crate) enum Output {
+    /// Write to the process's stdout, as `dada run` does.
+    Stdout,
+
+    /// Accumulate in a string, as `dada test` does.
+    Captured(String),
+}
+
+impl Output {
+    fn println(&mut self, text: &str) {
+        match self {
+            Output::Stdout => println!("{text}"),
+            Output::Captured(captured) => {
+                captured.push_str(text);
+                captured.push('\n');
+            }
+        }
+    }
+}
+
+/// Validate and instantiate the wasm module in `bytes` and invoke its `main` export,
+/// sending anything the program prints to `output`.
+///
+/// Returns `Err` if the module is invalid, cannot be instantiated,
+/// or traps during execution. Text printed before a trap is still in `output`.
+pub(crate) fn execute(bytes: &[u8], output: &mut Output) -> Fallible<()> {
+    let engine = Engine::default();
+    Module::validate(&engine, bytes).context("generated module is not valid WebAssembly")?;
+    let module = Module::new(&engine, bytes)?;
+
+    let mut linker = Linker::new(&engine);
+    for import in module.imports() {
+        let ExternType::Func(func_ty) = import.ty() else {
+            bail!(
+                "unsupported import `{}::{}`: only functions can be imported",
+                import.module(),
+                import.name()
+            );
+        };
+
+        let host_fn: HostFn = match (import.module(), import.name()) {
+            (HOST_MODULE, "print") => print,
+            (HOST_MODULE, "yield_now") => yield_now,
+            (module, name) => bail!("no host function named `{module}::{name}`"),
+        };
+
+        let result_tys = func_ty.results().collect::<Vec<_>>();
+        linker.func_new(
+            import.module(),
+            import.name(),
+            func_ty,
+            move |mut caller, params, results| {
+                let [_stack_pointer, Val::I32(polls), args @ ..] = params else {
+                    bail!("host functions expect a stack pointer and a poll count: {params:?}");
+                };
+                let ready = host_fn(&mut caller, *polls as u32, args)?;
+                for (result, ty) in results.iter_mut().zip(&result_tys) {
+                    *result = Val::default_for_ty(ty).expect("host results have defaults");
+                }
+                results[0] = Val::I32(i32::from(ready));
+                Ok(())
+            },
+        )?;
+    }
+
+    // The store owns the output while the program runs; we hand it back afterwards
+    // whether or not the program completed.
+    let mut store = Store::new(&engine, std::mem::replace(output, Output::Stdout));
+    let result = linker
+        .instantiate(&mut store, &module)
+        .and_then(|instance| call_main(&mut store, instance));
+    *output = store.into_data();
+    result
+}
+
+/// Invoke the `main` export of `instance`.
+fn call_main(store: &mut Store<Output>, instance: Instance) -> Fallible<()> {
+    let Some(main) = instance.get_func(&mut *store, "main") else {
+        bail!("generated module does not export a `main` function");
+    };
+
+    // The only parameter to `main` is the stack pointer,
+    // which starts at the address the module exports as `__stack_pointer`.
+    let stack_pointer = match instance.get_global(&mut *store, "__stack_pointer") {
+        Some(global) => global.get(&mut *store),
+        None => Val::I32(0),
+    };
+    let main_ty = main.ty(&*store);
+    let params = main_ty
+        .params()
+        .enumerate()
+        .map(|(index, ty)| match index {
+            0 => stack_pointer.clone(),
+            _ => Val::default_for_ty(&ty).expect("`main` takes only numeric parameters"),
+        })
+        .collect::<Vec<_>>();
+    let mut results = main_ty
+        .results()
+        .map(|ty| Val::default_for_ty(&ty).expect("`main` returns only numeric results"))
+        .collect::<Vec<_>>();
+
+    main.call(store, &params, &mut results)
+        .context("program terminated abnormally")
+}
+
+/// Host implementation of the prelude's `print(s: String)`.
+///
+/// The `String` arrives as a pointer to its object, which holds a flag word
+/// followed by the `data` pointer, `length`, and `capacity` fields.
+/// It is always ready.
+fn print(caller: &mut Caller<'_, Output>, _polls: u32, args: &[Val]) -> wasmtime::Result<bool> {
+    let [string] = args else {
+        bail!("`print` expects a `String` argument, found {args:?}");
+    };
+    let Some(string) = string.i32() else {
+        bail!("`print` expects a `String` argument, found {args:?}");
+    };
+
+    let Some(memory) = caller
+        .get_export("memory")
+        .and_then(|export| export.into_memory())
+    else {
+        bail!("generated module does not export its `memory`");
+    };
+
+    let mut fields = [0; 8];
+    memory.read(&*caller, string as u32 as usize + 4, &mut fields)?;
+    let data = u32::from_le_bytes([fields[0], fields[1], fields[2], fields[3]]);
+    let length = u32::from_le_bytes([fields[4], fields[5], fields[6], fields[7]]);
+
+    let mut buffer = vec![0; length as usize];
+    memory.read(&*caller, data as usize, &mut buffer)?;
+    caller.data_mut().println(&String::from_utf8_lossy(&buffer));
+
+    Ok(true)
+}
+
+/// Host implementation of the prelude's `yield_now()`,
+/// which is pending the first time it is polled and ready the next.
+fn yield_now(
+    _caller: &mut Caller<'_, Output>,
+    polls: u32,
+    _args: &[Val],
+) -> wasmtime::Result<bool> {
+    Ok(polls > 0)
+}
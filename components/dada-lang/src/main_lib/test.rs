@@ -10,6 +10,7 @@ use expected::{ExpectedDiagnostic, Probe};
 use indicatif::ProgressBar;
 use panic_hook::CapturedPanic;
 use rayon::prelude::*;
+use regex::Regex;
 use walkdir::WalkDir;
 
 use crate::{GlobalOptions, TestOptions};
@@ -60,6 +61,14 @@ enum Failure {
         txt_path: PathBuf,
         diff: String,
     },
+
+    /// The disassembled module did not contain the `expected` pattern.
+    ///
+    /// The module was written to `txt_path`, or no module was generated if it is `None`.
+    Wat {
+        expected: Regex,
+        txt_path: Option<PathBuf>,
+    },
 }
 
 impl Failure {}
@@ -369,6 +378,20 @@ impl FailedTest {
                     writeln!(result, "Diff:")?;
                     writeln!(result, "```diff\n{diff}\n```")?;
                 }
+                Failure::Wat { expected, txt_path } => {
+                    writeln!(result)?;
+                    writeln!(result, "# Generated module did not match")?;
+                    writeln!(result)?;
+                    writeln!(result, "Expected: `{expected}`")?;
+                    match txt_path {
+                        Some(txt_path) => writeln!(
+                            result,
+                            "[Actual]({})",
+                            self.relativize(&self.path, txt_path).display()
+                        )?,
+                        None => writeln!(result, "No module was generated.")?,
+                    }
+                }
                 Failure::InternalCompilerError(captured_panic) => {
                     writeln!(result)?;
                     writeln!(result, "# Internal compiler error")?;
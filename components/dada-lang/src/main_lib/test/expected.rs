@@ -2,7 +2,7 @@ use std::path::{Path, PathBuf};
 
 use dada_compiler::Compiler;
 use dada_ir_ast::{
-    diagnostic::Diagnostic,
+    diagnostic::{Diagnostic, Level},
     inputs::SourceFile,
     span::{AbsoluteOffset, AbsoluteSpan},
 };
@@ -12,6 +12,7 @@ use regex::Regex;
 
 use crate::GlobalOptions;
 
+use super::super::run::host;
 use super::{FailedTest, Failure};
 
 #[derive(Clone, Debug)]
@@ -41,6 +42,10 @@ pub struct TestExpectations {
     codegen: bool,
     fixme: bool,
     probes: Vec<Probe>,
+
+    /// Patterns that must appear in the disassembled module, from `#:wat` configuration lines.
+    /// `#:wat text` expects `text` verbatim and `#:wat /regex` expects a match for `regex`.
+    wat: Vec<Regex>,
 }
 
 /// A "probe" is a test where we inspect some piece of compiler state
@@ -77,6 +82,24 @@ pub enum ProbeKind {
 
     /// Tests the type of the smallest containing expression
     ExprType,
+
+    /// Tests the location (`file:line:column`) where the name here was defined
+    Definition,
+
+    /// Tests the locations of all references to the name here, separated by commas
+    References,
+
+    /// Tests the locations that renaming the name here to `renamed` would replace,
+    /// separated by commas, or the error explaining why it cannot be renamed
+    Rename,
+
+    /// Tests the declaration whose name is here as it would appear in an outline of the file,
+    /// like `struct Point { field x, method size }`
+    Declaration,
+
+    /// Tests the names that could be typed here (the cursor being at the start of the probe),
+    /// sorted and separated by commas
+    Completions,
 }
 
 enum Bless {
@@ -122,6 +145,7 @@ impl TestExpectations {
             codegen: true,
             fixme: false,
             probes: vec![],
+            wat: vec![],
         };
         expectations.initialize(db)?;
         Ok(expectations)
@@ -253,6 +277,11 @@ impl TestExpectations {
                 let valid_probe_kinds = &[
                     ("VariableType", ProbeKind::VariableType),
                     ("ExprType", ProbeKind::ExprType),
+                    ("Definition", ProbeKind::Definition),
+                    ("References", ProbeKind::References),
+                    ("Rename", ProbeKind::Rename),
+                    ("Declaration", ProbeKind::Declaration),
+                    ("Completions", ProbeKind::Completions),
                 ];
                 let user_probe_kind = c.name("kind").unwrap().as_str();
                 let Some(&(_, kind)) = valid_probe_kinds
@@ -315,6 +344,15 @@ impl TestExpectations {
             return Ok(());
         }
 
+        if let Some(expected) = line.strip_prefix("wat ") {
+            let expected = expected.trim();
+            self.wat.push(match expected.strip_prefix('/') {
+                Some(re) => Regex::new(re)?,
+                None => Regex::new(&regex::escape(expected))?,
+            });
+            return Ok(());
+        }
+
         if line == "FIXME" {
             self.fixme = true;
             return Ok(());
@@ -344,13 +382,33 @@ impl TestExpectations {
             Self::generate_fn_asts,
         )?);
 
-        let actual_diagnostics = compiler.check_all(self.source_file);
+        // Probes may need to modify the compiler's inputs,
+        // so they are performed before we borrow the diagnostics.
+        let probe_failures = self.perform_probes(compiler);
+        test.failures.extend(probe_failures);
 
-        if self.codegen {
-            let _wasm_bytes = compiler.codegen_main_fn(self.source_file);
+        // Programs that compile without errors are run, and whatever they print
+        // is compared against the `stdout` auxiliary file.
+        let has_errors = compiler
+            .check_all(self.source_file)
+            .iter()
+            .any(|d| d.level >= Level::Error);
+        let wasm_bytes = if self.codegen && !has_errors {
+            compiler.codegen_main_fn(self.source_file).clone()
+        } else {
+            None
+        };
+        test.failures
+            .extend(self.compare_wat(compiler, wasm_bytes.as_deref())?);
+        if let Some(wasm_bytes) = wasm_bytes {
+            let stdout = Self::execute_main(&wasm_bytes);
+            let enabled = !stdout.is_empty() || self.ref_path(compiler, "stdout").exists();
+            test.failures.extend(
+                self.compare_auxiliary(compiler, "stdout", enabled, |_, _| stdout.clone())?,
+            );
         }
 
-        test.failures.extend(self.perform_probes(compiler));
+        let actual_diagnostics = compiler.check_all(self.source_file);
 
         for diagnostic in &actual_diagnostics {
             writeln!(
@@ -370,7 +428,55 @@ impl TestExpectations {
         }
     }
 
-    fn perform_probes(&self, compiler: &Compiler) -> Vec<Failure> {
+    /// Check the `#:wat` expectations against the disassembled module,
+    /// which is written to the `wat` text file for inspection.
+    fn compare_wat(
+        &self,
+        compiler: &mut Compiler,
+        wasm_bytes: Option<&[u8]>,
+    ) -> Fallible<Vec<Failure>> {
+        let txt_path = self.txt_path(compiler, "wat");
+        self.remove_stale_file(&txt_path)?;
+        if self.wat.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let wat = match wasm_bytes {
+            Some(wasm_bytes) => {
+                let wat =
+                    wasmprinter::print_bytes(wasm_bytes).context("disassembling generated wasm")?;
+                self.write_file(&txt_path, &wat)?;
+                Some(wat)
+            }
+            None => None,
+        };
+
+        Ok(self
+            .wat
+            .iter()
+            .filter(|expected| !wat.as_ref().is_some_and(|wat| expected.is_match(wat)))
+            .map(|expected| Failure::Wat {
+                expected: expected.clone(),
+                txt_path: wat.as_ref().map(|_| txt_path.clone()),
+            })
+            .collect())
+    }
+
+    /// Run the generated module and return what it printed.
+    /// If it fails, the error is reported as the final line.
+    fn execute_main(wasm_bytes: &[u8]) -> String {
+        let mut output = host::Output::Captured(String::new());
+        let result = host::execute(wasm_bytes, &mut output);
+        let host::Output::Captured(mut stdout) = output else {
+            unreachable!("output is captured")
+        };
+        if let Err(error) = result {
+            stdout.push_str(&format!("error: {error}: {}\n", error.root_cause()));
+        }
+        stdout
+    }
+
+    fn perform_probes(&self, compiler: &mut Compiler) -> Vec<Failure> {
         self.probes
             .iter()
             .filter_map(|probe| {
@@ -381,6 +487,20 @@ impl TestExpectations {
                     ProbeKind::ExprType => compiler
                         .probe_expression_type(probe.span)
                         .unwrap_or_else(|| "<no expression found>".to_string()),
+                    ProbeKind::Definition => compiler
+                        .probe_definition(probe.span)
+                        .unwrap_or_else(|| "<no definition found>".to_string()),
+                    ProbeKind::References => compiler.probe_references(probe.span),
+                    ProbeKind::Rename => compiler.probe_rename(probe.span, "renamed"),
+                    ProbeKind::Declaration => compiler
+                        .probe_declaration(probe.span)
+                        .unwrap_or_else(|| "<no declaration found>".to_string()),
+                    ProbeKind::Completions => compiler
+                        .probe_completions(probe.span)
+                        .into_iter()
+                        .map(|completion| completion.label)
+                        .collect::<Vec<_>>()
+                        .join(", "),
                 };
 
                 if probe.message.is_match(&actual) {
@@ -51,6 +51,48 @@ pub trait Lsp: Sized {
         editor: &mut dyn Editor<Self>,
         params: lsp_types::HoverParams,
     ) -> Fallible<Option<lsp_types::Hover>>;
+
+    /// Handle go-to-definition requests.
+    fn goto_definition(
+        &mut self,
+        editor: &mut dyn Editor<Self>,
+        params: lsp_types::GotoDefinitionParams,
+    ) -> Fallible<Option<lsp_types::GotoDefinitionResponse>>;
+
+    /// Handle find-all-references requests.
+    fn references(
+        &mut self,
+        editor: &mut dyn Editor<Self>,
+        params: lsp_types::ReferenceParams,
+    ) -> Fallible<Option<Vec<lsp_types::Location>>>;
+
+    /// Handle rename requests.
+    fn rename(
+        &mut self,
+        editor: &mut dyn Editor<Self>,
+        params: lsp_types::RenameParams,
+    ) -> Fallible<Option<lsp_types::WorkspaceEdit>>;
+
+    /// Handle completion requests.
+    fn completion(
+        &mut self,
+        editor: &mut dyn Editor<Self>,
+        params: lsp_types::CompletionParams,
+    ) -> Fallible<Option<lsp_types::CompletionResponse>>;
+
+    /// Handle requests for the symbols declared in a document (e.g., for an outline).
+    fn document_symbol(
+        &mut self,
+        editor: &mut dyn Editor<Self>,
+        params: lsp_types::DocumentSymbolParams,
+    ) -> Fallible<Option<lsp_types::DocumentSymbolResponse>>;
+
+    /// Handle requests to search for symbols by name across the workspace.
+    fn workspace_symbol(
+        &mut self,
+        editor: &mut dyn Editor<Self>,
+        params: lsp_types::WorkspaceSymbolParams,
+    ) -> Fallible<Option<lsp_types::WorkspaceSymbolResponse>>;
 }
 
 pub trait LspFork: Sized + Send {
@@ -89,6 +131,12 @@ pub fn run_server<L: Lsp>() -> Fallible<()> {
         .on_notification::<notification::DidOpenTextDocument>(Lsp::did_open)
         .on_notification::<notification::DidChangeTextDocument>(Lsp::did_change)
         .on_request::<request::HoverRequest>(Lsp::hover)
+        .on_request::<request::GotoDefinition>(Lsp::goto_definition)
+        .on_request::<request::References>(Lsp::references)
+        .on_request::<request::Rename>(Lsp::rename)
+        .on_request::<request::Completion>(Lsp::completion)
+        .on_request::<request::DocumentSymbolRequest>(Lsp::document_symbol)
+        .on_request::<request::WorkspaceSymbolRequest>(Lsp::workspace_symbol)
         .execute()?;
 
     io_threads.join()?;
@@ -1,17 +1,18 @@
+use std::collections::HashMap;
 use std::str::FromStr;
 use std::sync::{Arc, Mutex};
 
 use dada_compiler::{Compiler, Fork, RealFs};
 use dada_ir_ast::diagnostic::{Diagnostic, DiagnosticLabel, Level};
-use dada_ir_ast::inputs::SourceFile;
+use dada_ir_ast::inputs::{SourceFile, line_starts};
 use dada_ir_ast::span::{AbsoluteOffset, AbsoluteSpan};
 use dada_util::{Fallible, Map, Set, bail};
 use lsp::{Editor, Lsp, LspFork};
 use lsp_types::{
-    DidChangeTextDocumentParams, DidOpenTextDocumentParams, HoverProviderCapability, MessageType,
-    OneOf, PublishDiagnosticsParams, TextDocumentContentChangeEvent, TextDocumentItem,
-    TextDocumentSyncCapability, TextDocumentSyncKind, TextDocumentSyncOptions, Uri,
-    VersionedTextDocumentIdentifier,
+    CompletionItemKind, CompletionOptions, DidChangeTextDocumentParams, DidOpenTextDocumentParams,
+    HoverProviderCapability, MessageType, OneOf, PublishDiagnosticsParams,
+    TextDocumentContentChangeEvent, TextDocumentItem, TextDocumentSyncCapability,
+    TextDocumentSyncKind, TextDocumentSyncOptions, Uri, VersionedTextDocumentIdentifier,
 };
 use lsp_types::{InitializeParams, ServerCapabilities};
 
@@ -52,13 +53,21 @@ impl lsp::Lsp for Server {
             text_document_sync: Some(TextDocumentSyncCapability::Options(
                 TextDocumentSyncOptions {
                     open_close: Some(true),
-                    change: Some(TextDocumentSyncKind::FULL),
+                    change: Some(TextDocumentSyncKind::INCREMENTAL),
                     will_save: None,
                     will_save_wait_until: None,
                     save: None,
                 },
             )),
             definition_provider: Some(OneOf::Left(true)),
+            references_provider: Some(OneOf::Left(true)),
+            rename_provider: Some(OneOf::Left(true)),
+            completion_provider: Some(CompletionOptions {
+                trigger_characters: Some(vec![".".to_string()]),
+                ..CompletionOptions::default()
+            }),
+            document_symbol_provider: Some(OneOf::Left(true)),
+            workspace_symbol_provider: Some(OneOf::Left(true)),
             ..ServerCapabilities::default()
         })
     }
@@ -109,23 +118,16 @@ impl lsp::Lsp for Server {
 
         let source_file = self.db.get_previously_opened_source_file(uri_str)?;
 
-        for TextDocumentContentChangeEvent {
-            range,
-            range_length: _,
-            text,
-        } in content_changes
-        {
-            match range {
-                Some(_) => {
-                    // FIXME: We should implement incremental change events; but LSP sends line/column
-                    // positions and that's just *annoying*.
-                    bail!("we requested full content change events");
-                }
-                None => {
-                    let _old_contents = source_file.set_contents(&mut self.db).to(Ok(text));
-                }
-            }
-        }
+        let mut contents = match source_file.contents(&self.db) {
+            Ok(contents) => contents.clone(),
+            // We do not know what the editor has, so we can only start over from a full replacement.
+            Err(error) => match content_changes.first() {
+                Some(change) if change.range.is_none() => String::new(),
+                _ => bail!("cannot apply changes to `{uri_str}`, which failed to load: {error}"),
+            },
+        };
+        apply_content_changes(&mut contents, content_changes)?;
+        let _old_contents = source_file.set_contents(&mut self.db).to(Ok(contents));
 
         editor.show_message(MessageType::INFO, format!("did change {uri_str}"))?;
 
@@ -148,32 +150,8 @@ impl lsp::Lsp for Server {
             work_done_progress_params: _,
         } = params;
 
-        // Get the source file
-        let source_file = self.db.get_previously_opened_source_file(uri.as_str())?;
-
-        // Convert LSP position to absolute offset
-        let line = position.line as usize;
-        let character = position.character as usize;
-
-        // Get line starts
-        let line_starts = source_file.line_starts(&self.db);
-
-        // Make sure the line is valid
-        if line >= line_starts.len() - 1 {
+        let Some(span) = self.position_span(&uri, position)? else {
             return Ok(None);
-        }
-
-        // Get the start offset of the line
-        let line_start = line_starts[line];
-
-        // Calculate the absolute offset
-        let offset = AbsoluteOffset::from(line_start.as_usize() + character);
-
-        // Create a span at the position
-        let span = AbsoluteSpan {
-            source_file,
-            start: offset,
-            end: offset,
         };
 
         // Use probe_expression_type to get the type
@@ -192,6 +170,221 @@ impl lsp::Lsp for Server {
             Ok(None)
         })
     }
+
+    fn goto_definition(
+        &mut self,
+        _editor: &mut dyn Editor<Self>,
+        params: lsp_types::GotoDefinitionParams,
+    ) -> Fallible<Option<lsp_types::GotoDefinitionResponse>> {
+        let lsp_types::GotoDefinitionParams {
+            text_document_position_params:
+                lsp_types::TextDocumentPositionParams {
+                    text_document: lsp_types::TextDocumentIdentifier { uri },
+                    position,
+                },
+            work_done_progress_params: _,
+            partial_result_params: _,
+        } = params;
+
+        let Some(span) = self.position_span(&uri, position)? else {
+            return Ok(None);
+        };
+
+        // The definition may be in another file, including libdada (with a `libdada:///` URL)
+        self.db.attach(|db| {
+            Ok(dada_probe::probe_definition(db, span).map(|definition| {
+                lsp_types::GotoDefinitionResponse::Scalar(EditorDiagnostics::lsp_location(
+                    db, definition,
+                ))
+            }))
+        })
+    }
+
+    fn references(
+        &mut self,
+        _editor: &mut dyn Editor<Self>,
+        params: lsp_types::ReferenceParams,
+    ) -> Fallible<Option<Vec<lsp_types::Location>>> {
+        let lsp_types::ReferenceParams {
+            text_document_position:
+                lsp_types::TextDocumentPositionParams {
+                    text_document: lsp_types::TextDocumentIdentifier { uri },
+                    position,
+                },
+            work_done_progress_params: _,
+            partial_result_params: _,
+            context:
+                lsp_types::ReferenceContext {
+                    include_declaration,
+                },
+        } = params;
+
+        let Some(span) = self.position_span(&uri, position)? else {
+            return Ok(None);
+        };
+
+        let source_files = self.db.crate_source_files(span.source_file);
+        self.db.attach(|db| {
+            let definition = dada_probe::probe_definition(db, span);
+            let locations = dada_probe::probe_references(db, span, &source_files)
+                .into_iter()
+                .filter(|&reference| include_declaration || Some(reference) != definition)
+                .map(|reference| EditorDiagnostics::lsp_location(db, reference))
+                .collect();
+            Ok(Some(locations))
+        })
+    }
+
+    fn rename(
+        &mut self,
+        _editor: &mut dyn Editor<Self>,
+        params: lsp_types::RenameParams,
+    ) -> Fallible<Option<lsp_types::WorkspaceEdit>> {
+        let lsp_types::RenameParams {
+            text_document_position:
+                lsp_types::TextDocumentPositionParams {
+                    text_document: lsp_types::TextDocumentIdentifier { uri },
+                    position,
+                },
+            new_name,
+            work_done_progress_params: _,
+        } = params;
+
+        let Some(span) = self.position_span(&uri, position)? else {
+            return Ok(None);
+        };
+
+        let references = self.db.rename(span, &new_name)?;
+        if references.is_empty() {
+            return Ok(None);
+        }
+
+        self.db.attach(|db| {
+            let mut changes: HashMap<Uri, Vec<lsp_types::TextEdit>> = HashMap::new();
+            for reference in references {
+                let lsp_types::Location { uri, range } =
+                    EditorDiagnostics::lsp_location(db, reference);
+                changes.entry(uri).or_default().push(lsp_types::TextEdit {
+                    range,
+                    new_text: new_name.clone(),
+                });
+            }
+
+            Ok(Some(lsp_types::WorkspaceEdit {
+                changes: Some(changes),
+                ..Default::default()
+            }))
+        })
+    }
+
+    fn completion(
+        &mut self,
+        _editor: &mut dyn Editor<Self>,
+        params: lsp_types::CompletionParams,
+    ) -> Fallible<Option<lsp_types::CompletionResponse>> {
+        let lsp_types::CompletionParams {
+            text_document_position:
+                lsp_types::TextDocumentPositionParams {
+                    text_document: lsp_types::TextDocumentIdentifier { uri },
+                    position,
+                },
+            work_done_progress_params: _,
+            partial_result_params: _,
+            context: _,
+        } = params;
+
+        let Some(span) = self.position_span(&uri, position)? else {
+            return Ok(None);
+        };
+
+        let items = self
+            .db
+            .probe_completions(span)
+            .into_iter()
+            .map(|completion| lsp_types::CompletionItem {
+                label: completion.label,
+                kind: Some(completion_item_kind(completion.kind)),
+                ..Default::default()
+            })
+            .collect();
+        Ok(Some(lsp_types::CompletionResponse::Array(items)))
+    }
+
+    fn document_symbol(
+        &mut self,
+        _editor: &mut dyn Editor<Self>,
+        params: lsp_types::DocumentSymbolParams,
+    ) -> Fallible<Option<lsp_types::DocumentSymbolResponse>> {
+        let lsp_types::DocumentSymbolParams {
+            text_document: lsp_types::TextDocumentIdentifier { uri },
+            work_done_progress_params: _,
+            partial_result_params: _,
+        } = params;
+
+        let source_file = self.db.get_previously_opened_source_file(uri.as_str())?;
+        self.db.attach(|db| {
+            let symbols = dada_probe::probe_declarations(db, source_file)
+                .into_iter()
+                .map(|declaration| lsp_document_symbol(db, declaration))
+                .collect();
+            Ok(Some(lsp_types::DocumentSymbolResponse::Nested(symbols)))
+        })
+    }
+
+    fn workspace_symbol(
+        &mut self,
+        _editor: &mut dyn Editor<Self>,
+        params: lsp_types::WorkspaceSymbolParams,
+    ) -> Fallible<Option<lsp_types::WorkspaceSymbolResponse>> {
+        let lsp_types::WorkspaceSymbolParams {
+            query,
+            work_done_progress_params: _,
+            partial_result_params: _,
+        } = params;
+
+        // Match names containing the query, ignoring case.
+        let query = query.to_lowercase();
+        let source_files = self.db.workspace_source_files();
+        self.db.attach(|db| {
+            let mut symbols = vec![];
+            for &source_file in &source_files {
+                for declaration in dada_probe::probe_declarations(db, source_file) {
+                    push_workspace_symbols(db, &query, None, declaration, &mut symbols);
+                }
+            }
+            Ok(Some(lsp_types::WorkspaceSymbolResponse::Nested(symbols)))
+        })
+    }
+}
+
+impl Server {
+    /// Converts an LSP position in the document `uri` into an (empty) span.
+    /// Returns `None` if the position is not within the document.
+    fn position_span(
+        &self,
+        uri: &Uri,
+        position: lsp_types::Position,
+    ) -> Fallible<Option<AbsoluteSpan>> {
+        // Get the source file
+        let source_file = self.db.get_previously_opened_source_file(uri.as_str())?;
+
+        // Convert LSP position to absolute offset
+        let Some(offset) = offset_of_position(
+            source_file.contents_if_ok(&self.db),
+            source_file.line_starts(&self.db),
+            position,
+        ) else {
+            return Ok(None);
+        };
+        let offset = AbsoluteOffset::from(offset);
+
+        // Create a span at the position
+        Ok(Some(AbsoluteSpan {
+            source_file,
+            start: offset,
+            end: offset,
+        }))
+    }
 }
 
 struct ServerFork {
@@ -334,9 +527,20 @@ impl EditorDiagnostics {
         offset: AbsoluteOffset,
     ) -> lsp_types::Position {
         let (line, column) = source_file.line_col(db, offset);
+
+        // LSP counts columns in UTF-16 code units, not bytes
+        let line_start = source_file.line_starts(db)[line.as_usize()];
+        let character = match source_file
+            .contents_if_ok(db)
+            .get(line_start.as_usize()..offset.as_usize())
+        {
+            Some(prefix) => prefix.encode_utf16().count() as u32,
+            None => column.as_u32(),
+        };
+
         lsp_types::Position {
             line: line.as_u32(),
-            character: column.as_u32(),
+            character,
         }
     }
 
@@ -344,3 +548,237 @@ impl EditorDiagnostics {
         Uri::from_str(url.as_str()).unwrap()
     }
 }
+
+/// Applies the changes from a `didChange` notification to `contents`.
+/// Each change applies to the contents that result from the changes before it.
+fn apply_content_changes(
+    contents: &mut String,
+    content_changes: Vec<TextDocumentContentChangeEvent>,
+) -> Fallible<()> {
+    for TextDocumentContentChangeEvent {
+        range,
+        range_length: _,
+        text,
+    } in content_changes
+    {
+        match range {
+            Some(range) => {
+                let line_starts = line_starts(contents);
+                let (Some(start), Some(end)) = (
+                    offset_of_position(contents, &line_starts, range.start),
+                    offset_of_position(contents, &line_starts, range.end),
+                ) else {
+                    bail!("change event has a range outside of the document: {range:?}");
+                };
+                if start > end {
+                    bail!("change event has a backwards range: {range:?}");
+                }
+                contents.replace_range(start..end, &text);
+            }
+            None => *contents = text,
+        }
+    }
+    Ok(())
+}
+
+/// Converts an LSP position into a byte offset in `text`, whose line starts are `line_starts`
+/// (see [`SourceFile::line_starts`]); this is the inverse of [`Server::lsp_position`].
+/// LSP counts the `character` of a position in UTF-16 code units;
+/// a `character` past the end of its line refers to the end of that line
+/// (before the `\r\n` or `\n` that ends it).
+/// Returns `None` if `text` has no such line.
+fn offset_of_position(
+    text: &str,
+    line_starts: &[AbsoluteOffset],
+    position: lsp_types::Position,
+) -> Option<usize> {
+    // The last entry of `line_starts` is the end of the text, not the start of a line.
+    let line = position.line as usize;
+    if line + 1 >= line_starts.len() {
+        return None;
+    }
+    let line_start = line_starts[line].as_usize();
+    let line_text = &text[line_start..line_starts[line + 1].as_usize()];
+    let line_text = line_text.strip_suffix('\n').unwrap_or(line_text);
+    let line_end = line_start + line_text.strip_suffix('\r').unwrap_or(line_text).len();
+
+    let mut character = 0;
+    for (index, ch) in text[line_start..line_end].char_indices() {
+        if character >= position.character as usize {
+            return Some(line_start + index);
+        }
+        character += ch.len_utf16();
+    }
+    Some(line_end)
+}
+
+fn completion_item_kind(kind: dada_probe::CompletionKind) -> CompletionItemKind {
+    use dada_probe::CompletionKind;
+    match kind {
+        CompletionKind::Field => CompletionItemKind::FIELD,
+        CompletionKind::Method => CompletionItemKind::METHOD,
+        CompletionKind::Variable => CompletionItemKind::VARIABLE,
+        CompletionKind::TypeParameter => CompletionItemKind::TYPE_PARAMETER,
+        CompletionKind::Function => CompletionItemKind::FUNCTION,
+        CompletionKind::Class => CompletionItemKind::CLASS,
+        CompletionKind::Trait => CompletionItemKind::INTERFACE,
+        CompletionKind::Module => CompletionItemKind::MODULE,
+        CompletionKind::Primitive => CompletionItemKind::STRUCT,
+        CompletionKind::Variant => CompletionItemKind::ENUM_MEMBER,
+        CompletionKind::Keyword => CompletionItemKind::KEYWORD,
+    }
+}
+
+fn lsp_symbol_kind(kind: dada_probe::DeclarationKind) -> lsp_types::SymbolKind {
+    use dada_probe::DeclarationKind;
+    match kind {
+        DeclarationKind::Class => lsp_types::SymbolKind::CLASS,
+        DeclarationKind::Struct => lsp_types::SymbolKind::STRUCT,
+        DeclarationKind::Enum => lsp_types::SymbolKind::ENUM,
+        DeclarationKind::Trait => lsp_types::SymbolKind::INTERFACE,
+        DeclarationKind::Function => lsp_types::SymbolKind::FUNCTION,
+        DeclarationKind::Field => lsp_types::SymbolKind::FIELD,
+        DeclarationKind::Method => lsp_types::SymbolKind::METHOD,
+        DeclarationKind::Variant => lsp_types::SymbolKind::ENUM_MEMBER,
+    }
+}
+
+// The `deprecated` field of `DocumentSymbol` is deprecated in favor of `tags`,
+// but it must still be initialized.
+#[expect(deprecated)]
+fn lsp_document_symbol(
+    db: &Compiler,
+    declaration: dada_probe::Declaration,
+) -> lsp_types::DocumentSymbol {
+    let children = declaration
+        .members
+        .into_iter()
+        .map(|member| lsp_document_symbol(db, member))
+        .collect::<Vec<_>>();
+
+    lsp_types::DocumentSymbol {
+        name: declaration.name,
+        detail: None,
+        kind: lsp_symbol_kind(declaration.kind),
+        tags: None,
+        deprecated: None,
+        range: EditorDiagnostics::lsp_range(db, declaration.span),
+        selection_range: EditorDiagnostics::lsp_range(db, declaration.name_span),
+        children: (!children.is_empty()).then_some(children),
+    }
+}
+
+/// Pushes `declaration` and its members onto `symbols` if their names contain `query` (which is lowercase).
+fn push_workspace_symbols(
+    db: &Compiler,
+    query: &str,
+    container_name: Option<String>,
+    declaration: dada_probe::Declaration,
+    symbols: &mut Vec<lsp_types::WorkspaceSymbol>,
+) {
+    if declaration.name.to_lowercase().contains(query) {
+        symbols.push(lsp_types::WorkspaceSymbol {
+            name: declaration.name.clone(),
+            kind: lsp_symbol_kind(declaration.kind),
+            tags: None,
+            container_name,
+            location: OneOf::Left(EditorDiagnostics::lsp_location(db, declaration.name_span)),
+            data: None,
+        });
+    }
+
+    for member in declaration.members {
+        push_workspace_symbols(db, query, Some(declaration.name.clone()), member, symbols);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::{Position, Range, TextDocumentContentChangeEvent};
+
+    use dada_ir_ast::inputs::line_starts;
+
+    use super::apply_content_changes;
+
+    fn offset_of_position(text: &str, position: Position) -> Option<usize> {
+        super::offset_of_position(text, &line_starts(text), position)
+    }
+
+    fn position(line: u32, character: u32) -> Position {
+        Position { line, character }
+    }
+
+    fn change(start: Position, end: Position, text: &str) -> TextDocumentContentChangeEvent {
+        TextDocumentContentChangeEvent {
+            range: Some(Range { start, end }),
+            range_length: None,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn offset_counts_utf16_code_units() {
+        // `𝕩` is one `char` of 4 bytes but 2 UTF-16 code units.
+        let text = "let 𝕩 = 1\n";
+        assert_eq!(offset_of_position(text, position(0, 4)), Some(4));
+        assert_eq!(offset_of_position(text, position(0, 6)), Some(8));
+        assert_eq!(offset_of_position(text, position(0, 7)), Some(9));
+    }
+
+    #[test]
+    fn offset_past_end_of_line() {
+        let text = "ab\ncd\n";
+        assert_eq!(offset_of_position(text, position(0, 2)), Some(2));
+        assert_eq!(offset_of_position(text, position(0, 99)), Some(2));
+        assert_eq!(offset_of_position(text, position(1, 99)), Some(5));
+    }
+
+    #[test]
+    fn offset_past_end_of_crlf_line() {
+        let text = "ab\r\ncd\r\n";
+        assert_eq!(offset_of_position(text, position(0, 99)), Some(2));
+        assert_eq!(offset_of_position(text, position(1, 0)), Some(4));
+        assert_eq!(offset_of_position(text, position(1, 99)), Some(6));
+    }
+
+    #[test]
+    fn offset_on_last_line() {
+        let text = "ab\ncd";
+        assert_eq!(offset_of_position(text, position(1, 1)), Some(4));
+        assert_eq!(offset_of_position(text, position(1, 99)), Some(5));
+        assert_eq!(offset_of_position("ab\n", position(1, 0)), Some(3));
+        assert_eq!(offset_of_position(text, position(2, 0)), None);
+    }
+
+    #[test]
+    fn apply_sequence_of_changes() {
+        let mut contents = "fn main() {\r\n    let x = 1\r\n}\r\n".to_string();
+        apply_content_changes(
+            &mut contents,
+            vec![
+                // Rename `x` to `answer`.
+                change(position(1, 8), position(1, 9), "answer"),
+                // Then replace the initializer, using positions in the renamed text.
+                change(position(1, 17), position(1, 18), "42"),
+                // Then append a line at the end of the second line.
+                change(position(1, 99), position(1, 99), "\r\n    print(answer)"),
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            contents,
+            "fn main() {\r\n    let answer = 42\r\n    print(answer)\r\n}\r\n"
+        );
+    }
+
+    #[test]
+    fn apply_change_outside_document() {
+        let mut contents = "ab\n".to_string();
+        let result = apply_content_changes(
+            &mut contents,
+            vec![change(position(5, 0), position(5, 0), "x")],
+        );
+        assert!(result.is_err());
+        assert_eq!(contents, "ab\n");
+    }
+}
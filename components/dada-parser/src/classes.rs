@@ -1,7 +1,8 @@
 use dada_ir_ast::{
     ast::{
         AstAggregate, AstAggregateKind, AstFieldDecl, AstFunction, AstGenericDecl, AstMember,
-        AstTy, AstTyKind, AstVisibility, AstWhereClauses, SpanVec, VariableDecl, VisibilityKind,
+        AstPath, AstTy, AstTyKind, AstVariant, AstVisibility, AstWhereClauses, SpanVec,
+        VariableDecl, VisibilityKind,
     },
     span::{Span, Spanned},
 };
@@ -51,6 +52,15 @@ impl<'db> Parse<'db> for AstAggregate<'db> {
             AstFieldDecl::eat_comma,
         )?;
 
+        let traits = if parser.eat_keyword(Keyword::Is).is_ok() {
+            match AstPath::opt_parse_separated(db, parser, operator::PLUS)? {
+                Some(traits) => Some(traits),
+                None => return Err(parser.illformed(Expected::Nonterminal("trait name"))),
+            }
+        } else {
+            None
+        };
+
         let where_clauses = AstWhereClauses::opt_parse(db, parser)?;
 
         let body = parser.defer_delimited(Delimiter::CurlyBraces).ok();
@@ -64,6 +74,7 @@ impl<'db> Parse<'db> for AstAggregate<'db> {
             id.span,
             generics,
             inputs,
+            traits,
             where_clauses,
             body,
         )))
@@ -108,6 +119,12 @@ impl<'db> Parse<'db> for AstAggregatePrefix<'db> {
                 aggregate_kind: AstAggregateKind::Struct,
                 aggregate_keyword: span,
             }))
+        } else if let Ok(span) = parser.eat_keyword(Keyword::Enum) {
+            Ok(Some(AstAggregatePrefix {
+                visibility,
+                aggregate_kind: AstAggregateKind::Enum,
+                aggregate_keyword: span,
+            }))
         } else {
             Ok(None)
         }
@@ -123,8 +140,13 @@ impl<'db> crate::prelude::ClassItemMembers<'db> for AstAggregate<'db> {
     #[salsa::tracked(return_ref)]
     fn members(self, db: &'db dyn crate::Db) -> SpanVec<'db, AstMember<'db>> {
         if let Some(contents) = self.contents(db) {
-            Parser::deferred(db, self, contents, |parser| {
-                parser.parse_many_and_report_diagnostics::<AstMember<'db>>(db)
+            Parser::deferred(db, self, contents, |parser| match self.kind(db) {
+                AstAggregateKind::Enum => {
+                    parser.parse_many_and_report_diagnostics::<AstEnumMember>(db)
+                }
+                AstAggregateKind::Class | AstAggregateKind::Struct => {
+                    parser.parse_many_and_report_diagnostics::<AstMember<'db>>(db)
+                }
             })
         } else {
             SpanVec {
@@ -156,6 +178,58 @@ impl<'db> Parse<'db> for AstMember<'db> {
     }
 }
 
+/// The members of an enum are its variants and methods.
+struct AstEnumMember;
+
+impl<'db> Parse<'db> for AstEnumMember {
+    type Output = AstMember<'db>;
+
+    fn opt_parse(
+        db: &'db dyn crate::Db,
+        parser: &mut Parser<'_, 'db>,
+    ) -> Result<Option<AstMember<'db>>, super::ParseFail<'db>> {
+        // As for `AstMember`, parse `fn` first as it begins with a keyword.
+        AstFunction::opt_parse(db, parser)
+            .or_opt_parse::<AstMember<'db>, AstVariant<'db>>(db, parser)
+    }
+
+    fn expected() -> Expected {
+        Expected::Nonterminal("enum member")
+    }
+}
+
+/// `Name` or `Name(field: T, ...)`, optionally followed by a comma
+impl<'db> Parse<'db> for AstVariant<'db> {
+    type Output = Self;
+
+    fn opt_parse(
+        db: &'db dyn crate::Db,
+        parser: &mut Parser<'_, 'db>,
+    ) -> Result<Option<Self>, super::ParseFail<'db>> {
+        let Ok(name) = parser.eat_id() else {
+            return Ok(None);
+        };
+
+        let fields = AstFieldDecl::opt_parse_delimited(
+            db,
+            parser,
+            Delimiter::Parentheses,
+            AstFieldDecl::eat_comma,
+        )?;
+
+        let span = name.span.to(db, parser.last_span());
+
+        // Variants may be separated by commas or by newlines.
+        let _ = parser.eat_op(operator::COMMA);
+
+        Ok(Some(AstVariant::new(db, span, name.id, name.span, fields)))
+    }
+
+    fn expected() -> Expected {
+        Expected::Nonterminal("enum variant")
+    }
+}
+
 impl<'db> Parse<'db> for AstFieldDecl<'db> {
     type Output = Self;
 
@@ -249,6 +323,8 @@ impl<'db> Parse<'db> for VariableDecl<'db> {
             AstTyKind::Perm(ast_perm, ast_ty) => (Some(ast_perm), ast_ty),
             AstTyKind::Named(..) => (None, ty),
             AstTyKind::GenericDecl(..) => (None, ty),
+            AstTyKind::Tuple(..) => (None, ty),
+            AstTyKind::Function(..) => (None, ty),
         };
 
         Ok(Some(VariableDecl::new(db, mutable, name, perm, base_ty)))
@@ -1,7 +1,8 @@
 use dada_ir_ast::ast::{
-    AstBinaryOp, AstBlock, AstConstructorField, AstExpr, AstExprKind, AstPath, AstPathKind,
-    DeferredParse, Identifier, IfArm, Literal, LiteralKind, PermissionOp, SpannedBinaryOp,
-    SpannedIdentifier, SpannedUnaryOp, SquareBracketArgs, UnaryOp,
+    AstBinaryOp, AstBlock, AstConstructorField, AstExpr, AstExprKind, AstMatchArm, AstPath,
+    AstPathKind, AstPattern, AstPatternKind, AstTy, DeferredParse, Identifier, IfArm, Literal,
+    LiteralKind, PermissionOp, SpannedBinaryOp, SpannedIdentifier, SpannedUnaryOp,
+    SquareBracketArgs, UnaryOp, VariableDecl,
 };
 
 use crate::{
@@ -156,6 +157,13 @@ fn postfix_expr_precedence<'db, const SELECT: u32>(
                 continue;
             }
 
+            // Tuple elements are accessed by index, e.g., `pair.0`
+            if let Ok(id) = parser.eat_tuple_index() {
+                let owner = AstExpr::new(start_span.to(db, mid_span), kind);
+                kind = AstExprKind::DotId(owner, id);
+                continue;
+            }
+
             if let Ok(await_keyword) = parser.eat_keyword(Keyword::Await) {
                 let future = AstExpr::new(start_span.to(db, mid_span), kind);
                 kind = AstExprKind::Await {
@@ -216,6 +224,21 @@ fn base_expr_precedence<'db, const SELECT: u32>(
         return Ok(Some(if_chain(db, parser, if_span)?));
     }
 
+    if parser.eat_keyword(Keyword::Match).is_ok() {
+        let scrutinee = eat_expr_with_precedence(
+            db,
+            parser,
+            binary_expr_precedence::<{ SELECT_ALL - SELECT_STRUCT }>,
+        )?;
+        let arms = AstMatchArm::eat_delimited(
+            db,
+            parser,
+            crate::tokenizer::Delimiter::CurlyBraces,
+            AstMatchArm::eat_many,
+        )?;
+        return Ok(Some(AstExprKind::Match(scrutinee, arms)));
+    }
+
     if let Ok(id) = parser.eat_id() {
         // Could be `X { field1: value1, .. }`
         if (SELECT & SELECT_STRUCT != 0) && parser.next_token_on_same_line() {
@@ -233,6 +256,20 @@ fn base_expr_precedence<'db, const SELECT: u32>(
         return Ok(Some(AstExprKind::Id(id)));
     }
 
+    // Could be `(a, b)`, `(a,)`, or `(a)`; only the last is not a tuple.
+    if let Some((mut elements, trailing_comma)) = AstExpr::opt_parse_delimited(
+        db,
+        parser,
+        crate::tokenizer::Delimiter::Parentheses,
+        AstExpr::eat_comma_trailing,
+    )? {
+        if elements.values.len() == 1 && !trailing_comma {
+            let element = elements.values.pop().unwrap();
+            return Ok(Some(*element.kind));
+        }
+        return Ok(Some(AstExprKind::Tuple(elements)));
+    }
+
     if let Ok(span) = parser.eat_keyword(Keyword::Self_) {
         let id = SpannedIdentifier {
             span,
@@ -241,6 +278,74 @@ fn base_expr_precedence<'db, const SELECT: u32>(
         return Ok(Some(AstExprKind::Id(id)));
     }
 
+    if parser.eat_keyword(Keyword::Loop).is_ok() {
+        let body = AstBlock::eat(db, parser)?;
+        return Ok(Some(AstExprKind::Loop(body)));
+    }
+
+    if parser.eat_keyword(Keyword::While).is_ok() {
+        let condition = eat_expr_with_precedence(
+            db,
+            parser,
+            binary_expr_precedence::<{ SELECT_ALL - SELECT_STRUCT }>,
+        )?;
+        let body = AstBlock::eat(db, parser)?;
+        return Ok(Some(AstExprKind::While(condition, body)));
+    }
+
+    if parser.eat_keyword(Keyword::For).is_ok() {
+        let binding = parser.eat_id()?;
+        parser.eat_keyword(Keyword::In)?;
+        let start = eat_expr_with_precedence(
+            db,
+            parser,
+            binary_expr_precedence::<{ SELECT_ALL - SELECT_STRUCT }>,
+        )?;
+        parser.eat_op(operator::DOTDOT)?;
+        let end = eat_expr_with_precedence(
+            db,
+            parser,
+            binary_expr_precedence::<{ SELECT_ALL - SELECT_STRUCT }>,
+        )?;
+        let body = AstBlock::eat(db, parser)?;
+        return Ok(Some(AstExprKind::For {
+            binding,
+            start,
+            end,
+            body,
+        }));
+    }
+
+    if parser.eat_keyword(Keyword::Fn).is_ok() {
+        let inputs = VariableDecl::eat_delimited(
+            db,
+            parser,
+            crate::tokenizer::Delimiter::Parentheses,
+            VariableDecl::eat_comma,
+        )?;
+        let output = AstTy::opt_parse_guarded(operator::ARROW, db, parser)?;
+        let body = AstBlock::eat(db, parser)?;
+        return Ok(Some(AstExprKind::Closure {
+            inputs,
+            output,
+            body,
+        }));
+    }
+
+    if parser.eat_keyword(Keyword::Break).is_ok() {
+        // Could be `break foo`
+        if parser.next_token_on_same_line() {
+            if let Some(expr) = AstExpr::opt_parse(db, parser)? {
+                return Ok(Some(AstExprKind::Break(Some(expr))));
+            }
+        }
+        return Ok(Some(AstExprKind::Break(None)));
+    }
+
+    if parser.eat_keyword(Keyword::Continue).is_ok() {
+        return Ok(Some(AstExprKind::Continue));
+    }
+
     if parser.eat_keyword(Keyword::Return).is_ok() {
         // Could be `return foo`
         if parser.next_token_on_same_line() {
@@ -323,6 +428,77 @@ fn if_chain<'db>(
     Ok(AstExprKind::If(arms))
 }
 
+/// `P => E`, optionally followed by a comma
+impl<'db> Parse<'db> for AstMatchArm<'db> {
+    type Output = Self;
+
+    fn opt_parse(
+        db: &'db dyn crate::Db,
+        parser: &mut Parser<'_, 'db>,
+    ) -> Result<Option<Self::Output>, crate::ParseFail<'db>> {
+        let Some(pattern) = AstPattern::opt_parse(db, parser)? else {
+            return Ok(None);
+        };
+
+        parser.eat_op(operator::FAT_ARROW)?;
+
+        let body = AstExpr::eat(db, parser)?;
+
+        // Arms may be separated by commas or by newlines.
+        let _ = parser.eat_op(operator::COMMA);
+
+        Ok(Some(AstMatchArm { pattern, body }))
+    }
+
+    fn expected() -> crate::Expected {
+        crate::Expected::Nonterminal("match arm")
+    }
+}
+
+/// `_`, `x`, `Path.Variant`, or `Path.Variant(P0, ..., Pn)`
+impl<'db> Parse<'db> for AstPattern<'db> {
+    type Output = Self;
+
+    fn opt_parse(
+        db: &'db dyn crate::Db,
+        parser: &mut Parser<'_, 'db>,
+    ) -> Result<Option<Self::Output>, crate::ParseFail<'db>> {
+        let start_span = parser.peek_span();
+
+        let Some(path) = AstPath::opt_parse(db, parser)? else {
+            return Ok(None);
+        };
+
+        let fields = if parser.next_token_on_same_line() {
+            AstPattern::opt_parse_delimited(
+                db,
+                parser,
+                crate::tokenizer::Delimiter::Parentheses,
+                AstPattern::eat_comma,
+            )?
+        } else {
+            None
+        };
+
+        let kind = match (path.kind(db), fields) {
+            (AstPathKind::Identifier(id), None) if id.id.text(db) == "_" => {
+                AstPatternKind::Wildcard
+            }
+            (AstPathKind::Identifier(id), None) => AstPatternKind::Binding(*id),
+            (_, fields) => AstPatternKind::Variant(path, fields),
+        };
+
+        Ok(Some(AstPattern::new(
+            start_span.to(db, parser.last_span()),
+            kind,
+        )))
+    }
+
+    fn expected() -> crate::Expected {
+        crate::Expected::Nonterminal("pattern")
+    }
+}
+
 impl<'db> Parse<'db> for PermissionOp {
     type Output = Self;
 
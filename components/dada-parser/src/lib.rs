@@ -6,7 +6,7 @@ use tokenizer::{
 };
 
 use dada_ir_ast::{
-    ast::{AstModule, DeferredParse, SpanVec, SpannedIdentifier},
+    ast::{AstModule, DeferredParse, Identifier, LiteralKind, SpanVec, SpannedIdentifier},
     diagnostic::{Diagnostic, Level, Reported},
     inputs::SourceFile,
     span::{Anchor, Offset, Span, Spanned},
@@ -23,6 +23,7 @@ mod module_body;
 pub mod prelude;
 mod square_bracket_args;
 mod tokenizer;
+mod traits;
 mod types;
 
 #[salsa::tracked]
@@ -344,6 +345,22 @@ impl<'token, 'db> Parser<'token, 'db> {
         Err(self.illformed(Expected::Identifier))
     }
 
+    /// Eats an integer literal naming a tuple element (e.g., the `0` in `pair.0`)
+    /// and returns it as an identifier.
+    pub fn eat_tuple_index(&mut self) -> Result<SpannedIdentifier<'db>, ParseFail<'db>> {
+        if let Some(&Token {
+            kind: TokenKind::Literal(LiteralKind::Integer, text),
+            span,
+            skipped: _,
+        }) = self.peek()
+        {
+            self.eat_next_token().unwrap();
+            let id = Identifier::new(self.db, text.to_string());
+            return Ok(SpannedIdentifier { span, id });
+        }
+        Err(self.illformed(Expected::Nonterminal("tuple index")))
+    }
+
     pub fn eat_op(&mut self, op: Op) -> Result<Span<'db>, ParseFail<'db>> {
         const MAX_LEN: usize = 5;
         assert!(op.len() < MAX_LEN, "unexpectedly long operator");
@@ -597,19 +614,52 @@ trait Parse<'db>: Sized {
         Self::opt_parse_separated(db, parser, operator::COMMA)
     }
 
+    /// Like [`Self::eat_comma`], but also returns true if the list ended with a comma.
+    /// This is what distinguishes the 1-tuple `(a,)` from `(a)`.
+    fn eat_comma_trailing(
+        db: &'db dyn crate::Db,
+        parser: &mut Parser<'_, 'db>,
+    ) -> Result<(SpanVec<'db, Self::Output>, bool), ParseFail<'db>> {
+        match Self::opt_parse_separated_trailing(db, parser, operator::COMMA)? {
+            Some(v) => Ok(v),
+            None => Ok((
+                SpanVec {
+                    span: parser.last_span().at_end(),
+                    values: vec![],
+                },
+                false,
+            )),
+        }
+    }
+
     /// Parse a `separator` separated list of Self
     fn opt_parse_separated(
         db: &'db dyn crate::Db,
         parser: &mut Parser<'_, 'db>,
         separator: operator::Op,
     ) -> Result<Option<SpanVec<'db, Self::Output>>, ParseFail<'db>> {
+        Ok(Self::opt_parse_separated_trailing(db, parser, separator)?.map(|(values, _)| values))
+    }
+
+    /// Parse a `separator` separated list of Self,
+    /// also returning true if the list ended with a `separator`.
+    fn opt_parse_separated_trailing(
+        db: &'db dyn crate::Db,
+        parser: &mut Parser<'_, 'db>,
+        separator: operator::Op,
+    ) -> Result<Option<(SpanVec<'db, Self::Output>, bool)>, ParseFail<'db>> {
         match Self::opt_parse(db, parser) {
             Ok(Some(item)) => {
                 let mut values = vec![item];
+                let mut trailing = false;
 
                 while parser.eat_op(separator).is_ok() {
+                    trailing = true;
                     match Self::opt_parse(db, parser) {
-                        Ok(Some(item)) => values.push(item),
+                        Ok(Some(item)) => {
+                            values.push(item);
+                            trailing = false;
+                        }
                         Ok(None) => break,
                         Err(err) => {
                             parser.push_diagnostic(err.into_diagnostic(db));
@@ -618,10 +668,13 @@ trait Parse<'db>: Sized {
                     }
                 }
 
-                Ok(Some(SpanVec {
-                    span: parser.last_span(),
-                    values,
-                }))
+                Ok(Some((
+                    SpanVec {
+                        span: parser.last_span(),
+                        values,
+                    },
+                    trailing,
+                )))
             }
 
             Ok(None) => Ok(None),
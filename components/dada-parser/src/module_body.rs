@@ -1,5 +1,5 @@
 use dada_ir_ast::{
-    ast::{AstAggregate, AstFunction, AstItem, AstModule, AstPath, AstUse, SpanVec},
+    ast::{AstAggregate, AstFunction, AstItem, AstModule, AstPath, AstTrait, AstUse, SpanVec},
     diagnostic::Diagnostic,
 };
 
@@ -60,12 +60,13 @@ impl<'db> Parse<'db> for AstItem<'db> {
         parser: &mut Parser<'_, 'db>,
     ) -> Result<Option<Self>, ParseFail<'db>> {
         AstAggregate::opt_parse(db, parser)
+            .or_opt_parse::<Self, AstTrait<'db>>(db, parser)
             .or_opt_parse::<Self, AstUse<'db>>(db, parser)
             .or_opt_parse::<Self, AstFunction<'db>>(db, parser)
     }
 
     fn expected() -> Expected {
-        panic!("module-level item (class, function, use)")
+        panic!("module-level item (class, trait, function, use)")
     }
 }
 
@@ -1,4 +1,4 @@
-use dada_ir_ast::ast::{AstBlock, AstGenericTerm, AstMember};
+use dada_ir_ast::ast::{AstBlock, AstFunction, AstGenericTerm, AstMember};
 
 use super::*;
 
@@ -12,6 +12,11 @@ pub trait ClassItemMembers<'db> {
     fn members(self, db: &'db dyn crate::Db) -> &'db SpanVec<'db, AstMember<'db>>;
 }
 
+/// Given a [`dada_ir_ast::ast::AstTrait`], parse its methods
+pub trait TraitItemMethods<'db> {
+    fn methods(self, db: &'db dyn crate::Db) -> &'db SpanVec<'db, AstFunction<'db>>;
+}
+
 /// Given a [`dada_ir_ast::ast::AstFunction`], parse its associated body into a block
 pub trait FunctionBlock<'db> {
     fn body_block(self, db: &'db dyn crate::Db) -> Option<AstBlock<'db>>;
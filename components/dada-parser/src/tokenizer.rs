@@ -93,7 +93,9 @@ keywords! {
         Await = "await",
         Box = "box",
         Boxed = "boxed",
+        Break = "break",
         Class = "class",
+        Continue = "continue",
         Crate = "crate",
         Dyn = "dyn",
         Else = "else",
@@ -101,10 +103,13 @@ keywords! {
         Export = "export",
         False = "false",
         Fn = "fn",
+        For = "for",
         If = "if",
+        In = "in",
         Is = "is",
         Lent = "lent",
         Let = "let",
+        Loop = "loop",
         Give = "give",
         Given = "given",
         Match = "match",
@@ -124,12 +129,14 @@ keywords! {
         Shared = "shared",
         Struct = "struct",
         Tracked = "tracked",
+        Trait = "trait",
         True = "true",
         Type = "type",
         Unique = "unique",
         Unsafe = "unsafe",
         Use = "use",
         Where = "where",
+        While = "while",
     }
 }
 
@@ -185,7 +192,9 @@ pub mod operator {
     pub const EQ: Op = Op(&['=']);
     pub const EQEQ: Op = Op(&['=', '=']);
     pub const ARROW: Op = Op(&['-', '>']);
+    pub const FAT_ARROW: Op = Op(&['=', '>']);
     pub const DOT: Op = Op(&['.']);
+    pub const DOTDOT: Op = Op(&['.', '.']);
     pub const COLON: Op = Op(&[':']);
     pub const BANG: Op = Op(&['!']);
     pub const COMMA: Op = Op(&[',']);
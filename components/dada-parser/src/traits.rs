@@ -0,0 +1,96 @@
+use dada_ir_ast::{
+    ast::{AstFunction, AstTrait, AstVisibility, AstWhereClauses, SpanVec},
+    span::Spanned,
+};
+use salsa::Update;
+
+use crate::ParseFail;
+
+use super::{
+    Expected, Parse, Parser,
+    tokenizer::{Delimiter, Keyword},
+};
+
+/// trait Name { ... }
+impl<'db> Parse<'db> for AstTrait<'db> {
+    type Output = Self;
+
+    fn opt_parse(
+        db: &'db dyn crate::Db,
+        parser: &mut Parser<'_, 'db>,
+    ) -> Result<Option<Self>, ParseFail<'db>> {
+        if !AstTraitPrefix::can_eat(db, parser) {
+            return Ok(None);
+        }
+
+        let start = parser.peek_span();
+
+        let AstTraitPrefix { visibility } = AstTraitPrefix::eat(db, parser)?;
+
+        let id = parser.eat_id()?;
+
+        let where_clauses = AstWhereClauses::opt_parse(db, parser)?;
+
+        let body = parser.defer_delimited(Delimiter::CurlyBraces).ok();
+
+        Ok(Some(AstTrait::new(
+            db,
+            start.to(db, parser.last_span()),
+            visibility,
+            id.id,
+            id.span,
+            where_clauses,
+            body,
+        )))
+    }
+
+    fn expected() -> Expected {
+        Expected::Keyword(Keyword::Trait)
+    }
+}
+
+/// The *prefix* parses a trait declaration up until
+/// the `trait` keyword, like `AstAggregatePrefix` does for classes.
+#[derive(Update)]
+struct AstTraitPrefix<'db> {
+    /// Visibility of the trait
+    visibility: Option<AstVisibility<'db>>,
+}
+
+impl<'db> Parse<'db> for AstTraitPrefix<'db> {
+    type Output = Self;
+
+    fn opt_parse(
+        db: &'db dyn crate::Db,
+        parser: &mut Parser<'_, 'db>,
+    ) -> Result<Option<Self>, ParseFail<'db>> {
+        let visibility = AstVisibility::opt_parse(db, parser)?;
+
+        if parser.eat_keyword(Keyword::Trait).is_ok() {
+            Ok(Some(AstTraitPrefix { visibility }))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn expected() -> Expected {
+        Expected::Nonterminal("trait")
+    }
+}
+
+#[salsa::tracked]
+impl<'db> crate::prelude::TraitItemMethods<'db> for AstTrait<'db> {
+    #[salsa::tracked(return_ref)]
+    fn methods(self, db: &'db dyn crate::Db) -> SpanVec<'db, AstFunction<'db>> {
+        if let Some(contents) = self.contents(db) {
+            Parser::deferred(db, self, contents, |parser| {
+                parser.parse_many_and_report_diagnostics::<AstFunction<'db>>(db)
+            })
+        } else {
+            SpanVec {
+                span: self.span(db).at_end(),
+                values: vec![],
+            }
+        }
+    }
+}
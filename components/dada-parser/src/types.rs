@@ -10,7 +10,7 @@ use dada_ir_ast::{
 
 use super::{
     Expected, Parse, ParseFail, Parser,
-    tokenizer::{Delimiter, Keyword},
+    tokenizer::{Delimiter, Keyword, operator},
 };
 
 // Parsing types and permissions is annoying.
@@ -28,6 +28,15 @@ enum TyOrPerm<'db> {
 
     /// P1 P2
     Apply(AstPerm<'db>, AstTy<'db>),
+
+    /// `(T1, T2)` or `(T1,)`; the span covers the parentheses
+    Tuple(Span<'db>, SpanVec<'db, AstTy<'db>>),
+
+    /// `(T)`, which is just `T`; the span covers the parentheses
+    Parenthesized(Span<'db>, AstTy<'db>),
+
+    /// `fn(T1, T2) -> T3`; the span covers everything from `fn` onwards
+    Function(Span<'db>, SpanVec<'db, AstTy<'db>>, Option<AstTy<'db>>),
 }
 
 impl<'db> Parse<'db> for TyOrPerm<'db> {
@@ -56,6 +65,30 @@ impl<'db> Parse<'db> for TyOrPerm<'db> {
             return TyOrPerm::PermKeyword(p).maybe_apply(db, parser);
         }
 
+        if let Some((mut elements, trailing_comma)) = AstTy::opt_parse_delimited(
+            db,
+            parser,
+            Delimiter::Parentheses,
+            AstTy::eat_comma_trailing,
+        )? {
+            if elements.values.len() == 1 && !trailing_comma {
+                let element = elements.values.pop().unwrap();
+                return Ok(Some(TyOrPerm::Parenthesized(parser.last_span(), element)));
+            }
+            return Ok(Some(TyOrPerm::Tuple(parser.last_span(), elements)));
+        }
+
+        if let Ok(fn_span) = parser.eat_keyword(Keyword::Fn) {
+            let inputs =
+                AstTy::eat_delimited(db, parser, Delimiter::Parentheses, AstTy::eat_comma)?;
+            let output = AstTy::opt_parse_guarded(operator::ARROW, db, parser)?;
+            return Ok(Some(TyOrPerm::Function(
+                fn_span.to(db, parser.last_span()),
+                inputs,
+                output,
+            )));
+        }
+
         Ok(None)
     }
 
@@ -74,6 +107,8 @@ impl<'db> Spanned<'db> for TyOrPerm<'db> {
             TyOrPerm::Generic(decl) => decl.span(db),
             TyOrPerm::PermKeyword(p) => p.span(db),
             TyOrPerm::Apply(p, ty) => p.span(db).to(db, ty.span(db)),
+            TyOrPerm::Tuple(span, _) | TyOrPerm::Parenthesized(span, _) => *span,
+            TyOrPerm::Function(span, _, _) => *span,
         }
     }
 }
@@ -103,6 +138,7 @@ impl<'db> TyOrPerm<'db> {
             TyOrPerm::Generic(decl) => matches!(decl.kind(db), AstGenericKind::Perm(_)),
             TyOrPerm::PermKeyword(_) => true,
             TyOrPerm::Apply(_, _) => false,
+            TyOrPerm::Tuple(..) | TyOrPerm::Parenthesized(..) | TyOrPerm::Function(..) => false,
         }
     }
 
@@ -123,6 +159,7 @@ impl<'db> TyOrPerm<'db> {
             },
             TyOrPerm::PermKeyword(p) => Some(p),
             TyOrPerm::Apply(_, _) => None,
+            TyOrPerm::Tuple(..) | TyOrPerm::Parenthesized(..) | TyOrPerm::Function(..) => None,
         }
     }
 
@@ -133,6 +170,7 @@ impl<'db> TyOrPerm<'db> {
             TyOrPerm::Generic(decl) => matches!(decl.kind(db), AstGenericKind::Type(_)),
             TyOrPerm::PermKeyword(_) => false,
             TyOrPerm::Apply(_, _) => true,
+            TyOrPerm::Tuple(..) | TyOrPerm::Parenthesized(..) | TyOrPerm::Function(..) => true,
         }
     }
 
@@ -146,6 +184,11 @@ impl<'db> TyOrPerm<'db> {
             },
             TyOrPerm::PermKeyword(_) => None,
             TyOrPerm::Apply(p, t) => Some(AstTy::new(db, span, AstTyKind::Perm(p, t))),
+            TyOrPerm::Tuple(_, elements) => Some(AstTy::new(db, span, AstTyKind::Tuple(elements))),
+            TyOrPerm::Parenthesized(_, ty) => Some(ty),
+            TyOrPerm::Function(_, inputs, output) => {
+                Some(AstTy::new(db, span, AstTyKind::Function(inputs, output)))
+            }
         }
     }
 }
@@ -272,7 +315,10 @@ impl<'db> Parse<'db> for AstGenericTerm<'db> {
             TyOrPerm::Generic(_)
             | TyOrPerm::PermKeyword(_)
             | TyOrPerm::Path(..)
-            | TyOrPerm::Apply(_, _) => {
+            | TyOrPerm::Apply(_, _)
+            | TyOrPerm::Tuple(..)
+            | TyOrPerm::Parenthesized(..)
+            | TyOrPerm::Function(..) => {
                 let can_be_perm = ty_or_perm.can_be_perm(db);
                 let can_be_ty = ty_or_perm.can_be_ty(db);
 
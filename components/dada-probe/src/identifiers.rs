@@ -0,0 +1,403 @@
+//! Finding the identifiers with a given name in the syntax tree of a module.
+
+use dada_ir_ast::{
+    ast::{
+        AstAggregate, AstBlock, AstConstructorField, AstExpr, AstExprKind, AstFieldDecl,
+        AstFunction, AstFunctionInput, AstGenericDecl, AstGenericTerm, AstItem, AstLetStatement,
+        AstMatchArm, AstMember, AstModule, AstPath, AstPathKind, AstPattern, AstPatternKind,
+        AstPerm, AstPermKind, AstStatement, AstTrait, AstTy, AstTyKind, AstUse, AstVariant,
+        AstWhereClause, AstWhereClauseKind, AstWhereClauses, Identifier, IfArm, SpanVec,
+        SpannedIdentifier, VariableDecl,
+    },
+    span::Span,
+};
+use dada_parser::prelude::*;
+
+/// Returns the spans of the identifiers `id` in `module`, including those in
+/// the bodies of its functions. Comments and string literals are not part of
+/// the syntax tree, so mentions of `id` within them are never found.
+pub(crate) fn identifiers_in_module<'db>(
+    db: &'db dyn crate::Db,
+    module: AstModule<'db>,
+    id: Identifier<'db>,
+) -> Vec<Span<'db>> {
+    let mut search = IdentifierSearch {
+        db,
+        id,
+        spans: vec![],
+    };
+    module.items(db).search(&mut search);
+    search.spans
+}
+
+struct IdentifierSearch<'db> {
+    db: &'db dyn crate::Db,
+
+    /// The identifier we are looking for.
+    id: Identifier<'db>,
+
+    /// Spans where `id` was found.
+    spans: Vec<Span<'db>>,
+}
+
+impl<'db> IdentifierSearch<'db> {
+    fn found(&mut self, id: Identifier<'db>, span: Span<'db>) {
+        if id == self.id {
+            self.spans.push(span);
+        }
+    }
+}
+
+/// Syntax that may contain identifiers.
+trait ContainsIdentifiers<'db> {
+    /// Record the span of each identifier in `self` that `search` is looking for.
+    fn search(&self, search: &mut IdentifierSearch<'db>);
+}
+
+impl<'db, T: ContainsIdentifiers<'db>> ContainsIdentifiers<'db> for [T] {
+    fn search(&self, search: &mut IdentifierSearch<'db>) {
+        for element in self {
+            element.search(search);
+        }
+    }
+}
+
+impl<'db, T: ContainsIdentifiers<'db> + salsa::Update> ContainsIdentifiers<'db>
+    for SpanVec<'db, T>
+{
+    fn search(&self, search: &mut IdentifierSearch<'db>) {
+        self.values.search(search)
+    }
+}
+
+impl<'db, T: ContainsIdentifiers<'db>> ContainsIdentifiers<'db> for Option<T> {
+    fn search(&self, search: &mut IdentifierSearch<'db>) {
+        if let Some(value) = self {
+            value.search(search)
+        }
+    }
+}
+
+impl<'db> ContainsIdentifiers<'db> for SpannedIdentifier<'db> {
+    fn search(&self, search: &mut IdentifierSearch<'db>) {
+        search.found(self.id, self.span)
+    }
+}
+
+impl<'db> ContainsIdentifiers<'db> for AstItem<'db> {
+    fn search(&self, search: &mut IdentifierSearch<'db>) {
+        match self {
+            AstItem::SourceFile(_) => (),
+            AstItem::Use(u) => u.search(search),
+            AstItem::Aggregate(a) => a.search(search),
+            AstItem::Trait(t) => t.search(search),
+            AstItem::Function(f) => f.search(search),
+        }
+    }
+}
+
+impl<'db> ContainsIdentifiers<'db> for AstUse<'db> {
+    fn search(&self, search: &mut IdentifierSearch<'db>) {
+        let db = search.db;
+        self.crate_name(db).search(search);
+        self.path(db).search(search);
+        self.as_id(db).search(search);
+    }
+}
+
+impl<'db> ContainsIdentifiers<'db> for AstAggregate<'db> {
+    fn search(&self, search: &mut IdentifierSearch<'db>) {
+        let db = search.db;
+        search.found(self.name(db), self.name_span(db));
+        self.generics(db).search(search);
+        self.inputs(db).search(search);
+        self.traits(db).search(search);
+        self.where_clauses(db).search(search);
+        self.members(db).search(search);
+    }
+}
+
+impl<'db> ContainsIdentifiers<'db> for AstTrait<'db> {
+    fn search(&self, search: &mut IdentifierSearch<'db>) {
+        let db = search.db;
+        search.found(self.name(db), self.name_span(db));
+        self.where_clauses(db).search(search);
+        self.methods(db).search(search);
+    }
+}
+
+impl<'db> ContainsIdentifiers<'db> for AstMember<'db> {
+    fn search(&self, search: &mut IdentifierSearch<'db>) {
+        match self {
+            AstMember::Field(f) => f.search(search),
+            AstMember::Function(f) => f.search(search),
+            AstMember::Variant(v) => v.search(search),
+        }
+    }
+}
+
+impl<'db> ContainsIdentifiers<'db> for AstFieldDecl<'db> {
+    fn search(&self, search: &mut IdentifierSearch<'db>) {
+        self.variable(search.db).search(search)
+    }
+}
+
+impl<'db> ContainsIdentifiers<'db> for AstVariant<'db> {
+    fn search(&self, search: &mut IdentifierSearch<'db>) {
+        let db = search.db;
+        search.found(self.name(db), self.name_span(db));
+        self.fields(db).search(search);
+    }
+}
+
+impl<'db> ContainsIdentifiers<'db> for AstFunction<'db> {
+    fn search(&self, search: &mut IdentifierSearch<'db>) {
+        let db = search.db;
+        self.name(db).search(search);
+        self.generics(db).search(search);
+        self.inputs(db).search(search);
+        self.output_ty(db).search(search);
+        self.where_clauses(db).search(search);
+        self.body_block(db).search(search);
+    }
+}
+
+impl<'db> ContainsIdentifiers<'db> for AstFunctionInput<'db> {
+    fn search(&self, search: &mut IdentifierSearch<'db>) {
+        match self {
+            AstFunctionInput::SelfArg(arg) => arg.perm(search.db).search(search),
+            AstFunctionInput::Variable(v) => v.search(search),
+        }
+    }
+}
+
+impl<'db> ContainsIdentifiers<'db> for VariableDecl<'db> {
+    fn search(&self, search: &mut IdentifierSearch<'db>) {
+        let db = search.db;
+        self.name(db).search(search);
+        self.perm(db).search(search);
+        self.base_ty(db).search(search);
+    }
+}
+
+impl<'db> ContainsIdentifiers<'db> for AstGenericDecl<'db> {
+    fn search(&self, search: &mut IdentifierSearch<'db>) {
+        self.name(search.db).search(search)
+    }
+}
+
+impl<'db> ContainsIdentifiers<'db> for AstWhereClauses<'db> {
+    fn search(&self, search: &mut IdentifierSearch<'db>) {
+        self.clauses(search.db).search(search)
+    }
+}
+
+impl<'db> ContainsIdentifiers<'db> for AstWhereClause<'db> {
+    fn search(&self, search: &mut IdentifierSearch<'db>) {
+        let db = search.db;
+        self.subject(db).search(search);
+        self.kinds(db).search(search);
+    }
+}
+
+impl<'db> ContainsIdentifiers<'db> for AstWhereClauseKind<'db> {
+    fn search(&self, search: &mut IdentifierSearch<'db>) {
+        match self {
+            AstWhereClauseKind::Trait(path) => path.search(search),
+            AstWhereClauseKind::Reference(_)
+            | AstWhereClauseKind::Mutable(_)
+            | AstWhereClauseKind::Shared(_)
+            | AstWhereClauseKind::Unique(_)
+            | AstWhereClauseKind::Owned(_)
+            | AstWhereClauseKind::Lent(_) => (),
+        }
+    }
+}
+
+impl<'db> ContainsIdentifiers<'db> for AstGenericTerm<'db> {
+    fn search(&self, search: &mut IdentifierSearch<'db>) {
+        match self {
+            AstGenericTerm::Ty(ty) => ty.search(search),
+            AstGenericTerm::Perm(perm) => perm.search(search),
+            AstGenericTerm::Id(id) => id.search(search),
+        }
+    }
+}
+
+impl<'db> ContainsIdentifiers<'db> for AstTy<'db> {
+    fn search(&self, search: &mut IdentifierSearch<'db>) {
+        match self.kind(search.db) {
+            AstTyKind::Perm(perm, ty) => {
+                perm.search(search);
+                ty.search(search);
+            }
+            AstTyKind::Named(path, args) => {
+                path.search(search);
+                args.search(search);
+            }
+            AstTyKind::GenericDecl(decl) => decl.search(search),
+            AstTyKind::Tuple(elements) => elements.search(search),
+            AstTyKind::Function(inputs, output) => {
+                inputs.search(search);
+                output.search(search);
+            }
+        }
+    }
+}
+
+impl<'db> ContainsIdentifiers<'db> for AstPerm<'db> {
+    fn search(&self, search: &mut IdentifierSearch<'db>) {
+        match self.kind(search.db) {
+            AstPermKind::Referenced(places)
+            | AstPermKind::Mutable(places)
+            | AstPermKind::Given(places) => places.search(search),
+            AstPermKind::Variable(id) => id.search(search),
+            AstPermKind::GenericDecl(decl) => decl.search(search),
+            AstPermKind::My | AstPermKind::Our => (),
+        }
+    }
+}
+
+impl<'db> ContainsIdentifiers<'db> for AstPath<'db> {
+    fn search(&self, search: &mut IdentifierSearch<'db>) {
+        match self.kind(search.db) {
+            AstPathKind::Identifier(id) => id.search(search),
+            AstPathKind::GenericArgs { path, args } => {
+                path.search(search);
+                args.search(search);
+            }
+            AstPathKind::Member { path, id } => {
+                path.search(search);
+                id.search(search);
+            }
+        }
+    }
+}
+
+impl<'db> ContainsIdentifiers<'db> for AstBlock<'db> {
+    fn search(&self, search: &mut IdentifierSearch<'db>) {
+        self.statements(search.db).search(search)
+    }
+}
+
+impl<'db> ContainsIdentifiers<'db> for AstStatement<'db> {
+    fn search(&self, search: &mut IdentifierSearch<'db>) {
+        match self {
+            AstStatement::Let(s) => s.search(search),
+            AstStatement::Expr(e) => e.search(search),
+        }
+    }
+}
+
+impl<'db> ContainsIdentifiers<'db> for AstLetStatement<'db> {
+    fn search(&self, search: &mut IdentifierSearch<'db>) {
+        let db = search.db;
+        self.name(db).search(search);
+        self.ty(db).search(search);
+        self.initializer(db).search(search);
+    }
+}
+
+impl<'db> ContainsIdentifiers<'db> for AstExpr<'db> {
+    fn search(&self, search: &mut IdentifierSearch<'db>) {
+        match &*self.kind {
+            AstExprKind::Block(block) => block.search(search),
+            AstExprKind::Literal(_) => (),
+            AstExprKind::Id(id) => id.search(search),
+            AstExprKind::DotId(owner, id) => {
+                owner.search(search);
+                id.search(search);
+            }
+            AstExprKind::SquareBracketOp(owner, args) => {
+                owner.search(search);
+                args.parse_as_generics(search.db).search(search);
+            }
+            AstExprKind::ParenthesisOp(owner, args) => {
+                owner.search(search);
+                args.search(search);
+            }
+            AstExprKind::Tuple(elements) => elements.search(search),
+            AstExprKind::Constructor(path, fields) => {
+                path.search(search);
+                fields.search(search);
+            }
+            AstExprKind::Return(value) => value.search(search),
+            AstExprKind::Await {
+                future,
+                await_keyword: _,
+            } => future.search(search),
+            AstExprKind::PermissionOp { value, op: _ } => value.search(search),
+            AstExprKind::BinaryOp(_, lhs, rhs) => {
+                lhs.search(search);
+                rhs.search(search);
+            }
+            AstExprKind::UnaryOp(_, operand) => operand.search(search),
+            AstExprKind::If(arms) => arms.search(search),
+            AstExprKind::Match(scrutinee, arms) => {
+                scrutinee.search(search);
+                arms.search(search);
+            }
+            AstExprKind::Loop(body) => body.search(search),
+            AstExprKind::While(condition, body) => {
+                condition.search(search);
+                body.search(search);
+            }
+            AstExprKind::For {
+                binding,
+                start,
+                end,
+                body,
+            } => {
+                binding.search(search);
+                start.search(search);
+                end.search(search);
+                body.search(search);
+            }
+            AstExprKind::Break(value) => value.search(search),
+            AstExprKind::Continue => (),
+            AstExprKind::Closure {
+                inputs,
+                output,
+                body,
+            } => {
+                inputs.search(search);
+                output.search(search);
+                body.search(search);
+            }
+        }
+    }
+}
+
+impl<'db> ContainsIdentifiers<'db> for AstConstructorField<'db> {
+    fn search(&self, search: &mut IdentifierSearch<'db>) {
+        self.name.search(search);
+        self.value.search(search);
+    }
+}
+
+impl<'db> ContainsIdentifiers<'db> for IfArm<'db> {
+    fn search(&self, search: &mut IdentifierSearch<'db>) {
+        self.condition.search(search);
+        self.result.search(search);
+    }
+}
+
+impl<'db> ContainsIdentifiers<'db> for AstMatchArm<'db> {
+    fn search(&self, search: &mut IdentifierSearch<'db>) {
+        self.pattern.search(search);
+        self.body.search(search);
+    }
+}
+
+impl<'db> ContainsIdentifiers<'db> for AstPattern<'db> {
+    fn search(&self, search: &mut IdentifierSearch<'db>) {
+        match &*self.kind {
+            AstPatternKind::Wildcard => (),
+            AstPatternKind::Binding(id) => id.search(search),
+            AstPatternKind::Variant(path, fields) => {
+                path.search(search);
+                fields.search(search);
+            }
+        }
+    }
+}
@@ -1,15 +1,28 @@
 use std::ops::ControlFlow;
 
-use dada_ir_ast::span::{AbsoluteSpan, SourceSpanned};
+use dada_ir_ast::{
+    ast::Identifier,
+    inputs::SourceFile,
+    span::{AbsoluteOffset, AbsoluteSpan, SourceSpanned, Span},
+};
 pub use dada_ir_sym::Db;
 use dada_ir_sym::{
+    check::{NameResolutionSym, Scope},
     ir::{
-        exprs::{SymExpr, SymExprKind},
-        functions::SymFunction,
+        classes::{SymAggregate, SymAggregateStyle, SymClassMember},
+        exprs::{SymExpr, SymExprKind, SymPlaceExpr, SymPlaceExprKind},
+        functions::{SymFunction, SymFunctionSource},
         module::SymItem,
+        types::{SymGenericKind, SymTy, SymTyKind, SymTyName},
+        variables::SymVariable,
     },
     prelude::{CheckedBody, Symbol},
 };
+use dada_parser::prelude::SourceFileParse;
+use dada_util::{Fallible, Map, bail};
+use identifiers::identifiers_in_module;
+
+mod identifiers;
 
 /// Probe for the type of an expression found in a given file at a given span.
 /// Returns the type of the smallest expression that contains the given span.
@@ -63,6 +76,746 @@ pub fn probe_variable_type<'db>(db: &'db dyn crate::Db, span: AbsoluteSpan) -> O
     })
 }
 
+/// Probe for the definition of the identifier found in a given file at a given span.
+/// Returns the span where the variable, field, function, or aggregate that it names was declared
+/// (which may be in libdada).
+pub fn probe_definition<'db>(db: &'db dyn crate::Db, span: AbsoluteSpan) -> Option<AbsoluteSpan> {
+    let (id_span, id) = find_identifier(db, span)?;
+    find_definition(db, id_span, id)
+}
+
+/// Probe for all references to the name found in a given file at a given span
+/// within `source_files` (typically all the files of the crate).
+/// The references include the definition itself, if it is in one of `source_files`.
+/// A trait method and the methods that implement it are referenced together.
+/// Returns an empty vector if the name does not refer to anything.
+pub fn probe_references<'db>(
+    db: &'db dyn crate::Db,
+    span: AbsoluteSpan,
+    source_files: &[SourceFile],
+) -> Vec<AbsoluteSpan> {
+    let Some((id_span, id)) = find_identifier(db, span) else {
+        return vec![];
+    };
+    let Some(definition) = find_definition(db, id_span, id) else {
+        return vec![];
+    };
+    let definitions = related_definitions(db, definition, source_files);
+    references_to(db, id, &definitions, source_files)
+}
+
+/// Probe for the spans to replace in order to rename the name found in a given file at a given span.
+/// Everything the name refers to must be defined in `source_files` (typically all the files of the crate),
+/// since we rename the definitions along with the references.
+/// Returns an empty vector if the name does not refer to anything.
+pub fn probe_rename<'db>(
+    db: &'db dyn crate::Db,
+    span: AbsoluteSpan,
+    source_files: &[SourceFile],
+) -> Fallible<Vec<AbsoluteSpan>> {
+    let Some((id_span, id)) = find_identifier(db, span) else {
+        return Ok(vec![]);
+    };
+    let Some(definition) = find_definition(db, id_span, id) else {
+        return Ok(vec![]);
+    };
+
+    let definitions = related_definitions(db, definition, source_files);
+    if let Some(definition) = definitions
+        .iter()
+        .find(|definition| !source_files.contains(&definition.source_file))
+    {
+        bail!(
+            "cannot rename something defined in `{}`",
+            definition.source_file.url_display(db)
+        );
+    }
+    Ok(references_to(db, id, &definitions, source_files))
+}
+
+/// Every identifier `id` in `source_files` whose definition is one of `definitions`, in order.
+/// Identifiers are found in the syntax tree, so mentions in comments and string literals are skipped.
+fn references_to<'db>(
+    db: &'db dyn crate::Db,
+    id: Identifier<'db>,
+    definitions: &[AbsoluteSpan],
+    source_files: &[SourceFile],
+) -> Vec<AbsoluteSpan> {
+    let mut references = vec![];
+    for &source_file in source_files {
+        let mut candidates = identifiers_in_module(db, source_file.parse(db), id)
+            .into_iter()
+            .map(|span| span.absolute_span(db))
+            .collect::<Vec<_>>();
+        candidates.sort_by_key(|candidate| candidate.start);
+        candidates.dedup();
+
+        references.extend(candidates.into_iter().filter(|&candidate| {
+            find_definition(db, candidate, id)
+                .is_some_and(|definition| definitions.contains(&definition))
+        }));
+    }
+    references
+}
+
+/// Returns `definition` along with the definitions of the methods related to it:
+/// a trait method is related to the methods implementing it in the classes of `source_files`
+/// (and so those methods are related to each other).
+fn related_definitions<'db>(
+    db: &'db dyn crate::Db,
+    definition: AbsoluteSpan,
+    source_files: &[SourceFile],
+) -> Vec<AbsoluteSpan> {
+    // Pair each trait method with the method of the same name in each class implementing the trait.
+    let mut pairs = vec![];
+    for &source_file in source_files {
+        for item in source_file.symbol(db).items(db) {
+            let SymItem::SymClass(aggr) = item else {
+                continue;
+            };
+            for &sym_trait in aggr.traits(db) {
+                for trait_method in sym_trait.methods(db) {
+                    if let Some(method) = aggr
+                        .methods(db)
+                        .find(|method| method.name(db) == trait_method.name(db))
+                    {
+                        pairs.push((
+                            trait_method.name_span(db).absolute_span(db),
+                            method.name_span(db).absolute_span(db),
+                        ));
+                    }
+                }
+            }
+        }
+    }
+
+    let mut definitions = vec![definition];
+    while let Some(&(a, b)) = pairs
+        .iter()
+        .find(|(a, b)| definitions.contains(a) != definitions.contains(b))
+    {
+        definitions.push(if definitions.contains(&a) { b } else { a });
+    }
+    definitions
+}
+
+/// Find the definition of the identifier `id` found at `id_span`.
+fn find_definition<'db>(
+    db: &'db dyn crate::Db,
+    id_span: AbsoluteSpan,
+    id: Identifier<'db>,
+) -> Option<AbsoluteSpan> {
+    // The name in a declaration is its own definition.
+    if let Some(declaration) = find_declaration(db, id_span) {
+        return Some(declaration.absolute_span(db));
+    }
+
+    // Names in the function body were resolved when it was checked,
+    // so look for the innermost expression that refers to the identifier.
+    let mut exprs = vec![];
+    let mut captures = Map::default();
+    visit_exprs(db, id_span, &mut |expr| {
+        if let SymExprKind::Closure {
+            captures: closure_captures,
+            ..
+        } = expr.kind(db)
+        {
+            for capture in closure_captures {
+                if let SymExprKind::PermissionOp(_, place) = capture.value.kind(db)
+                    && let SymPlaceExprKind::Var(var) = place.kind(db)
+                {
+                    captures.insert(capture.lv, *var);
+                }
+            }
+        }
+        exprs.push(expr);
+        ControlFlow::<()>::Continue(())
+    });
+    let cx = DefinitionCx {
+        db,
+        id_span,
+        id,
+        captures,
+    };
+    if let Some(definition) = exprs
+        .iter()
+        .rev()
+        .find_map(|&expr| cx.definition_in_expr(expr))
+    {
+        return Some(definition.absolute_span(db));
+    }
+
+    // Otherwise (e.g., for types in signatures), resolve the identifier
+    // in the scope where it appears.
+    let scope = find_scope(db, id_span);
+    Some(scope.lookup_name_span(db, id)?.absolute_span(db))
+}
+
+/// Find the name resolution scope of the function or field containing `span`,
+/// falling back to the scope of the module. Local variables are not included.
+fn find_scope<'db>(db: &'db dyn crate::Db, span: AbsoluteSpan) -> Scope<'db, 'db> {
+    let item_scope = find_item(db, span).and_then(|item| match item {
+        SymItem::SymClass(aggr) => match find_func(db, span) {
+            Some(func) => Some(func.scope(db)),
+            None => Some(
+                aggr.fields(db)
+                    .find(|f| f.source_span(db).absolute_span(db).contains(span))?
+                    .into_scope(db),
+            ),
+        },
+        SymItem::SymTrait(_) | SymItem::SymFunction(_) => Some(find_func(db, span)?.scope(db)),
+        SymItem::SymPrimitive(_) => None,
+    });
+    item_scope.unwrap_or_else(|| span.source_file.symbol(db).mod_scope(db))
+}
+
+/// Find the identifier in the source text that contains `span`.
+fn find_identifier<'db>(
+    db: &'db dyn crate::Db,
+    span: AbsoluteSpan,
+) -> Option<(AbsoluteSpan, Identifier<'db>)> {
+    let text = span.source_file.contents_if_ok(db);
+
+    let before = text.get(..span.start.as_usize())?;
+    let start = before
+        .char_indices()
+        .rev()
+        .take_while(|&(_, ch)| is_id_char(ch))
+        .last()
+        .map_or(before.len(), |(index, _)| index);
+
+    let after = text.get(span.end.as_usize()..)?;
+    let end = span.end.as_usize() + after.find(|ch| !is_id_char(ch)).unwrap_or(after.len());
+
+    let word = text.get(start..end)?;
+    if !word.starts_with(|ch: char| ch.is_alphabetic() || ch == '_') {
+        return None;
+    }
+
+    let id_span = AbsoluteSpan {
+        source_file: span.source_file,
+        start: AbsoluteOffset::from(start),
+        end: AbsoluteOffset::from(end),
+    };
+    Some((id_span, Identifier::new(db, word.to_string())))
+}
+
+fn is_id_char(ch: char) -> bool {
+    ch.is_alphanumeric() || ch == '_'
+}
+
+/// True if `offset` in `text` is in code rather than in a comment or string literal
+/// (the `{}` interpolations within a string literal are code).
+/// Only the line containing `offset` is considered.
+fn is_code(text: &str, offset: usize) -> bool {
+    let line_start = text[..offset].rfind('\n').map_or(0, |index| index + 1);
+    let mut in_string = false;
+    let mut interpolations = 0;
+    let mut chars = text[line_start..offset].chars();
+    while let Some(ch) = chars.next() {
+        if in_string && interpolations == 0 {
+            match ch {
+                '\\' => {
+                    chars.next();
+                }
+                '"' => in_string = false,
+                '{' => interpolations += 1,
+                _ => {}
+            }
+        } else {
+            match ch {
+                '#' => return false,
+                '"' if !in_string => in_string = true,
+                '{' if in_string => interpolations += 1,
+                '}' if in_string => interpolations -= 1,
+                _ => {}
+            }
+        }
+    }
+    !in_string || interpolations > 0
+}
+
+/// If `id_span` is the name of an item or member in its declaration, returns the span of that name.
+fn find_declaration<'db>(db: &'db dyn crate::Db, id_span: AbsoluteSpan) -> Option<Span<'db>> {
+    let mut names = vec![];
+    match find_item(db, id_span)? {
+        SymItem::SymClass(aggr) => {
+            names.push(aggr.name_span(db));
+            names.extend(aggr.fields(db).map(|field| field.name_span(db)));
+            names.extend(aggr.variants(db).map(|variant| variant.name_span(db)));
+            names.extend(aggr.methods(db).map(|method| method.name_span(db)));
+        }
+        SymItem::SymTrait(sym_trait) => {
+            names.push(sym_trait.name_span(db));
+            names.extend(
+                sym_trait
+                    .methods(db)
+                    .iter()
+                    .map(|method| method.name_span(db)),
+            );
+        }
+        SymItem::SymFunction(func) => names.push(func.name_span(db)),
+        SymItem::SymPrimitive(_) => {}
+    }
+    names
+        .into_iter()
+        .find(|name| name.absolute_span(db) == id_span)
+}
+
+struct DefinitionCx<'db> {
+    db: &'db dyn crate::Db,
+
+    /// Span of the identifier whose definition we are looking for.
+    id_span: AbsoluteSpan,
+
+    /// The identifier whose definition we are looking for.
+    id: Identifier<'db>,
+
+    /// Maps the variables that closures use for their captures to the captured variables.
+    captures: Map<SymVariable<'db>, SymVariable<'db>>,
+}
+
+impl<'db> DefinitionCx<'db> {
+    fn contains_id(&self, span: Span<'db>) -> bool {
+        span.absolute_span(self.db).contains(self.id_span)
+    }
+
+    /// If `expr` itself (rather than one of its subexpressions) names the identifier,
+    /// returns the span of its definition.
+    fn definition_in_expr(&self, expr: SymExpr<'db>) -> Option<Span<'db>> {
+        let db = self.db;
+        match *expr.kind(db) {
+            SymExprKind::LetIn { lv, .. } if self.contains_id(lv.span(db)) => Some(lv.span(db)),
+            SymExprKind::PermissionOp(_, place)
+            | SymExprKind::Assign { place, .. }
+            | SymExprKind::IsVariant { place, .. }
+            | SymExprKind::CallIndirect { callee: place, .. } => self.definition_in_place(place),
+            SymExprKind::Call { function, .. } => match function.source(db) {
+                // Constructors are named after their aggregate (e.g., `Point(x, y)`)
+                SymFunctionSource::Constructor(aggr, _) if aggr.name(db) == self.id => {
+                    Some(aggr.name_span(db))
+                }
+                _ if function.name(db) == self.id => Some(function.name_span(db)),
+                _ => None,
+            },
+            SymExprKind::Variant { variant, .. } if variant.name(db) == self.id => {
+                Some(variant.name_span(db))
+            }
+            SymExprKind::Aggregate { ty, ref labels, .. } => {
+                let aggr = aggregate_named_by(db, ty)?;
+
+                // A field name given in the constructor, like the `x` in `Point { x: 22 }`
+                if let Some(index) = labels
+                    .iter()
+                    .position(|label| label.is_some_and(|label| self.contains_id(label)))
+                {
+                    return Some(aggr.fields(db).nth(index)?.name_span(db));
+                }
+
+                (aggr.name(db) == self.id).then(|| aggr.name_span(db))
+            }
+            SymExprKind::Variant { ty, .. } => {
+                let aggr = aggregate_named_by(db, ty)?;
+                (aggr.name(db) == self.id).then(|| aggr.name_span(db))
+            }
+            _ => None,
+        }
+    }
+
+    fn definition_in_place(&self, place: SymPlaceExpr<'db>) -> Option<Span<'db>> {
+        let db = self.db;
+        if !self.contains_id(place.span(db)) {
+            return None;
+        }
+
+        match *place.kind(db) {
+            SymPlaceExprKind::Var(var) if var.name(db) == Some(self.id) => {
+                let var = self.captures.get(&var).copied().unwrap_or(var);
+                Some(var.span(db))
+            }
+            SymPlaceExprKind::Field(owner, _) | SymPlaceExprKind::TupleElement(owner, _)
+                if self.contains_id(owner.span(db)) =>
+            {
+                self.definition_in_place(owner)
+            }
+            SymPlaceExprKind::Field(_, field) if field.name(db) == self.id => {
+                Some(field.name_span(db))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The aggregate named by `ty`, ignoring any permissions.
+fn aggregate_named_by<'db>(db: &'db dyn crate::Db, ty: SymTy<'db>) -> Option<SymAggregate<'db>> {
+    match *ty.kind(db) {
+        SymTyKind::Named(SymTyName::Aggregate(aggr), _) => Some(aggr),
+        SymTyKind::Perm(_, ty) => aggregate_named_by(db, ty),
+        _ => None,
+    }
+}
+
+/// A name that could be typed at some position in the source.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Completion {
+    pub label: String,
+    pub kind: CompletionKind,
+}
+
+/// What kind of thing a [`Completion`] names.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CompletionKind {
+    Field,
+    Method,
+    Variable,
+    TypeParameter,
+    Function,
+    Class,
+    Trait,
+    Module,
+    Primitive,
+    Variant,
+    Keyword,
+}
+
+/// The keywords that can begin a type, like `ref` in `ref[x] String`.
+const PERMISSION_KEYWORDS: &[&str] = &["given", "mut", "my", "our", "ref"];
+
+/// If the identifier being typed at `offset` in `text` is a member access like `x.fo`
+/// (or just `x.`), returns `text` with the `.` and the partial member name removed,
+/// so that `x` can be type checked on its own, along with the offset just past `x`.
+pub fn strip_member_access(text: &str, offset: usize) -> Option<(String, usize)> {
+    let before = text.get(..offset)?;
+    let name_start = before.trim_end_matches(is_id_char).len();
+    let receiver_end = before[..name_start].strip_suffix('.')?.len();
+    if !is_code(text, receiver_end) {
+        return None;
+    }
+
+    let after = &text[offset..];
+    let name_end = offset + after.find(|ch| !is_id_char(ch)).unwrap_or(after.len());
+    Some((
+        format!("{}{}", &text[..receiver_end], &text[name_end..]),
+        receiver_end,
+    ))
+}
+
+/// Probe for the fields and methods of the expression that ends at `span`
+/// (the receiver of a `.`, see [`strip_member_access`]).
+pub fn probe_member_completions<'db>(
+    db: &'db dyn crate::Db,
+    span: AbsoluteSpan,
+) -> Vec<Completion> {
+    // The receiver is the smallest expression containing its last character.
+    let text = span.source_file.contents_if_ok(db);
+    let Some(before) = text.get(..span.start.as_usize()).map(str::trim_end) else {
+        return vec![];
+    };
+    let Some(last) = before.chars().next_back() else {
+        return vec![];
+    };
+    let receiver_span = AbsoluteSpan {
+        source_file: span.source_file,
+        start: AbsoluteOffset::from(before.len() - last.len_utf8()),
+        end: AbsoluteOffset::from(before.len()),
+    };
+    let Some(receiver) = find_smallest_containing_expr(db, receiver_span) else {
+        return vec![];
+    };
+
+    receiver
+        .ty(db)
+        .members(db)
+        .into_iter()
+        .map(|member| match member {
+            SymClassMember::SymField(field) => {
+                completion(db, field.name(db), CompletionKind::Field)
+            }
+            SymClassMember::SymFunction(function) => {
+                completion(db, function.name(db), CompletionKind::Method)
+            }
+            SymClassMember::SymVariant(variant) => {
+                completion(db, variant.name(db), CompletionKind::Variant)
+            }
+        })
+        .collect()
+}
+
+/// Probe for the names that could be typed at `span` (other than after a `.`):
+/// the local variables declared before `span` and the names in scope.
+/// If `span` appears to be in a type, only names of types are included, along with the permission keywords.
+pub fn probe_name_completions<'db>(db: &'db dyn crate::Db, span: AbsoluteSpan) -> Vec<Completion> {
+    let text = span.source_file.contents_if_ok(db);
+    let Some(before) = text.get(..span.start.as_usize()) else {
+        return vec![];
+    };
+    if !is_code(text, before.len()) {
+        return vec![];
+    }
+    let before = before.trim_end_matches(is_id_char);
+
+    let in_body = find_func(db, span)
+        .and_then(|func| func.checked_body(db))
+        .is_some_and(|body| body.source_span(db).absolute_span(db).contains(span));
+    let in_type = is_type_position(before, in_body);
+
+    let mut completions = vec![];
+    if in_type {
+        completions.extend(PERMISSION_KEYWORDS.iter().map(|keyword| Completion {
+            label: keyword.to_string(),
+            kind: CompletionKind::Keyword,
+        }));
+    } else {
+        // Local variables, innermost first so that they shadow the ones declared earlier.
+        // A variable is not in scope in its own initializer.
+        let mut locals = vec![];
+        visit_exprs(db, span, &mut |expr| {
+            match expr.kind(db) {
+                SymExprKind::LetIn {
+                    lv, initializer, ..
+                } if lv.span(db).absolute_span(db).end <= span.start
+                    && !initializer.is_some_and(|initializer| {
+                        initializer.source_span(db).absolute_span(db).contains(span)
+                    }) =>
+                {
+                    locals.push(*lv)
+                }
+                SymExprKind::Closure { inputs, .. } => locals.extend(inputs.iter().copied()),
+                _ => {}
+            }
+            ControlFlow::<()>::Continue(())
+        });
+        completions.extend(
+            locals
+                .iter()
+                .rev()
+                .filter_map(|lv| Some(completion(db, lv.name(db)?, CompletionKind::Variable))),
+        );
+    }
+
+    for (id, sym) in find_scope(db, span).names(db) {
+        let kind = match sym {
+            NameResolutionSym::SymModule(_) => CompletionKind::Module,
+            NameResolutionSym::SymAggregate(_) => CompletionKind::Class,
+            NameResolutionSym::SymTrait(_) => CompletionKind::Trait,
+            NameResolutionSym::SymFunction(_) => CompletionKind::Function,
+            NameResolutionSym::SymPrimitive(_) => CompletionKind::Primitive,
+            NameResolutionSym::SymVariable(var) => match var.kind(db) {
+                SymGenericKind::Place => CompletionKind::Variable,
+                SymGenericKind::Type | SymGenericKind::Perm => CompletionKind::TypeParameter,
+            },
+            NameResolutionSym::SymVariant(_) => CompletionKind::Variant,
+        };
+        let names_type = matches!(
+            kind,
+            CompletionKind::Module
+                | CompletionKind::Class
+                | CompletionKind::Primitive
+                | CompletionKind::TypeParameter
+        );
+        let names_value = !matches!(
+            kind,
+            CompletionKind::Trait | CompletionKind::Primitive | CompletionKind::TypeParameter
+        );
+        if (in_type && names_type) || (!in_type && names_value) {
+            completions.push(completion(db, id, kind));
+        }
+    }
+
+    // Keep only the innermost of names that are shadowed.
+    let mut labels = vec![];
+    completions.retain(|c| {
+        let new = !labels.contains(&c.label);
+        labels.push(c.label.clone());
+        new
+    });
+    completions
+}
+
+fn completion<'db>(
+    db: &'db dyn crate::Db,
+    id: Identifier<'db>,
+    kind: CompletionKind,
+) -> Completion {
+    Completion {
+        label: id.text(db).to_string(),
+        kind,
+    }
+}
+
+/// True if a name that follows the text `before` is probably part of a type,
+/// e.g., after `x: `, `-> `, or a permission keyword.
+fn is_type_position(before: &str, in_body: bool) -> bool {
+    let before = before.trim_end();
+    let word_start = before.trim_end_matches(is_id_char).len();
+    if PERMISSION_KEYWORDS.contains(&&before[word_start..]) {
+        // ...but `mut` in `let mut x` is not a permission.
+        return !before[..word_start].trim_end().ends_with("let");
+    }
+    if before.ends_with("->") {
+        return true;
+    }
+    let Some(before_colon) = before.strip_suffix(':') else {
+        return false;
+    };
+
+    // In a function body, a `:` only introduces a type in a `let`
+    // (the `x: 22` in `Point { x: 22 }` is a value).
+    if !in_body {
+        return true;
+    }
+    let before_name = before_colon
+        .trim_end()
+        .trim_end_matches(is_id_char)
+        .trim_end();
+    before_name.ends_with("let") || before_name.ends_with("mut")
+}
+
+/// An item or member declared in a source file, as shown in an outline of the file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Declaration {
+    pub name: String,
+    pub kind: DeclarationKind,
+
+    /// Span of the entire declaration.
+    pub span: AbsoluteSpan,
+
+    /// Span of the name in the declaration.
+    pub name_span: AbsoluteSpan,
+
+    /// Declarations nested within this one (e.g., the fields and methods of a class),
+    /// in the order they appear.
+    pub members: Vec<Declaration>,
+}
+
+/// What kind of thing a [`Declaration`] declares.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DeclarationKind {
+    Class,
+    Struct,
+    Enum,
+    Trait,
+    Function,
+    Field,
+    Method,
+    Variant,
+}
+
+/// Probe for the items declared in `source_file` along with their members,
+/// in the order they appear. Generated constructors are not included.
+pub fn probe_declarations<'db>(
+    db: &'db dyn crate::Db,
+    source_file: SourceFile,
+) -> Vec<Declaration> {
+    let items = source_file
+        .symbol(db)
+        .items(db)
+        .filter_map(|item| match item {
+            SymItem::SymClass(aggr) => {
+                let kind = match aggr.style(db) {
+                    SymAggregateStyle::Class => DeclarationKind::Class,
+                    SymAggregateStyle::Struct => DeclarationKind::Struct,
+                    SymAggregateStyle::Enum => DeclarationKind::Enum,
+                };
+                let members = aggr
+                    .members(db)
+                    .iter()
+                    .filter_map(|&member| match member {
+                        SymClassMember::SymField(field) => Some(declaration(
+                            db,
+                            field.name(db),
+                            DeclarationKind::Field,
+                            field.source_span(db),
+                            field.name_span(db),
+                            vec![],
+                        )),
+                        SymClassMember::SymFunction(function) => match function.source(db) {
+                            SymFunctionSource::Function(_) => {
+                                Some(function_declaration(db, function, DeclarationKind::Method))
+                            }
+                            SymFunctionSource::Constructor(..) => None,
+                        },
+                        SymClassMember::SymVariant(variant) => Some(declaration(
+                            db,
+                            variant.name(db),
+                            DeclarationKind::Variant,
+                            variant.source_span(db),
+                            variant.name_span(db),
+                            vec![],
+                        )),
+                    })
+                    .collect();
+                Some(declaration(
+                    db,
+                    aggr.name(db),
+                    kind,
+                    aggr.source_span(db),
+                    aggr.name_span(db),
+                    members,
+                ))
+            }
+            SymItem::SymTrait(sym_trait) => {
+                let methods = sym_trait
+                    .methods(db)
+                    .iter()
+                    .map(|&method| function_declaration(db, method, DeclarationKind::Method))
+                    .collect();
+                Some(declaration(
+                    db,
+                    sym_trait.name(db),
+                    DeclarationKind::Trait,
+                    sym_trait.source_span(db),
+                    sym_trait.name_span(db),
+                    methods,
+                ))
+            }
+            SymItem::SymFunction(function) => Some(function_declaration(
+                db,
+                function,
+                DeclarationKind::Function,
+            )),
+            SymItem::SymPrimitive(_) => None,
+        });
+    sorted_by_span(items.collect())
+}
+
+fn function_declaration<'db>(
+    db: &'db dyn crate::Db,
+    function: SymFunction<'db>,
+    kind: DeclarationKind,
+) -> Declaration {
+    declaration(
+        db,
+        function.name(db),
+        kind,
+        function.source_span(db),
+        function.name_span(db),
+        vec![],
+    )
+}
+
+fn declaration<'db>(
+    db: &'db dyn crate::Db,
+    name: Identifier<'db>,
+    kind: DeclarationKind,
+    span: Span<'db>,
+    name_span: Span<'db>,
+    members: Vec<Declaration>,
+) -> Declaration {
+    Declaration {
+        name: name.text(db).to_string(),
+        kind,
+        span: span.absolute_span(db),
+        name_span: name_span.absolute_span(db),
+        members: sorted_by_span(members),
+    }
+}
+
+fn sorted_by_span(mut declarations: Vec<Declaration>) -> Vec<Declaration> {
+    declarations.sort_by_key(|declaration| declaration.span.start);
+    declarations
+}
+
 /// Find the module item containing `span`
 fn find_item<'db>(db: &'db dyn crate::Db, span: AbsoluteSpan) -> Option<SymItem<'db>> {
     let module = span.source_file.symbol(db);
@@ -77,6 +830,11 @@ fn find_func<'db>(db: &'db dyn crate::Db, span: AbsoluteSpan) -> Option<SymFunct
         SymItem::SymClass(aggr) => aggr
             .methods(db)
             .find(|m| m.source_span(db).absolute_span(db).contains(span)),
+        SymItem::SymTrait(sym_trait) => sym_trait
+            .methods(db)
+            .iter()
+            .copied()
+            .find(|m| m.source_span(db).absolute_span(db).contains(span)),
         SymItem::SymFunction(func) => Some(func),
         SymItem::SymPrimitive(_) => None,
     }
@@ -136,19 +894,47 @@ fn walk_expr_and_visit<'db, B>(
         } => walk_expr_and_visit(db, *future, span, op),
         SymExprKind::Assign { place: _, value } => walk_expr_and_visit(db, *value, span, op),
         SymExprKind::PermissionOp(_, _) => None,
+        SymExprKind::IsVariant {
+            place: _,
+            variant: _,
+        } => None,
         SymExprKind::Call {
             function: _,
             substitution: _,
             arg_temps: _,
         } => None,
-        SymExprKind::Return(sym_expr) => walk_expr_and_visit(db, *sym_expr, span, op),
+        SymExprKind::CallIndirect {
+            callee: _,
+            arg_temps: _,
+        } => None,
+        // The captures are synthesized from the closure as a whole, so only the body is walked.
+        SymExprKind::Closure {
+            captures: _,
+            inputs: _,
+            input_tys: _,
+            body,
+        } => walk_expr_and_visit(db, *body, span, op),
+        SymExprKind::Return(sym_expr) | SymExprKind::Break(sym_expr) => {
+            walk_expr_and_visit(db, *sym_expr, span, op)
+        }
+        SymExprKind::Loop { body } => walk_expr_and_visit(db, *body, span, op),
+        SymExprKind::Continue => None,
         SymExprKind::Not {
             operand,
             op_span: _,
         } => walk_expr_and_visit(db, *operand, span, op),
         SymExprKind::BinaryOp(_, lhs, rhs) => walk_expr_and_visit(db, *lhs, span, op)
             .or_else(|| walk_expr_and_visit(db, *rhs, span, op)),
-        SymExprKind::Aggregate { ty: _, fields } => {
+        SymExprKind::Aggregate {
+            ty: _,
+            fields,
+            labels: _,
+        }
+        | SymExprKind::Variant {
+            ty: _,
+            variant: _,
+            fields,
+        } => {
             for &field in fields {
                 if let Some(b) = walk_expr_and_visit(db, field, span, op) {
                     return Some(b);
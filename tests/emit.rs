@@ -0,0 +1,63 @@
+//! Tests for `dada compile --emit`, which writes the generated module to disk.
+
+use std::{path::PathBuf, process::Command};
+
+/// Compile `tests/hello_world.dada` with the given `--emit` kinds into a fresh directory
+/// and return that directory.
+fn emit(test_name: &str, emit: &str) -> PathBuf {
+    let output_dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(test_name);
+    if output_dir.exists() {
+        std::fs::remove_dir_all(&output_dir).unwrap();
+    }
+
+    let status = Command::new(env!("CARGO_BIN_EXE_dada"))
+        .arg("compile")
+        .arg(format!("--emit={emit}"))
+        .arg("-o")
+        .arg(&output_dir)
+        .arg("tests/hello_world.dada")
+        .status()
+        .unwrap();
+    assert!(status.success(), "`dada compile` failed: {status}");
+
+    output_dir
+}
+
+#[test]
+fn emit_wasm_and_wat() {
+    let output_dir = emit("emit_wasm_and_wat", "wasm,wat");
+
+    let wasm = std::fs::read(output_dir.join("hello_world.wasm")).unwrap();
+    assert!(wasm.starts_with(b"\0asm"), "not a wasm module");
+
+    let wat = std::fs::read_to_string(output_dir.join("hello_world.wat")).unwrap();
+    assert!(wat.starts_with("(module"), "not a wat module:\n{wat}");
+    assert!(
+        wat.contains(r#"(export "main" (func"#),
+        "`main` not exported:\n{wat}"
+    );
+}
+
+#[test]
+fn emit_only_requested_kinds() {
+    let output_dir = emit("emit_only_requested_kinds", "wat");
+
+    assert!(output_dir.join("hello_world.wat").exists());
+    assert!(!output_dir.join("hello_world.wasm").exists());
+}
+
+#[test]
+fn compile_errors_emit_nothing() {
+    let output_dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("compile_errors_emit_nothing");
+    let status = Command::new(env!("CARGO_BIN_EXE_dada"))
+        .arg("compile")
+        .arg("--emit=wasm")
+        .arg("-o")
+        .arg(&output_dir)
+        .arg("tests/symbols/bad_name.dada")
+        .status()
+        .unwrap();
+
+    assert!(!status.success());
+    assert!(!output_dir.join("bad_name.wasm").exists());
+}